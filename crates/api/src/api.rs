@@ -1,28 +1,71 @@
 // Local imports
-use api::data_parser::{parse_data, DistributionEntry};
-use api::endpoints::{get_info, get_proof, status, ApiDoc};
-use api::AppState;
-use indexed_merkle_tree::{hasher::KeccakHasher, tree::IndexedMerkleTree};
+use api::data_parser::{address_ordering, apply_salts, parse_data, DistributionEntry};
+use api::endpoints::{get_info, get_node, get_proof, status, verify_batch, verify_proof, ApiDoc};
+use api::{build_sharded_tree, load_standard_tree, AppState, DistributionTree};
+use indexed_merkle_tree::{
+  envelope::{CommitmentEnvelope, EnvelopeOptions},
+  hasher::KeccakHasher,
+  openzeppelin::StandardTreeDump,
+  tree::{IndexedMerkleTree, TreeOptions},
+};
 
 // Core lib imports
+use std::env;
+use std::fs;
 use std::sync::Arc;
 
 // External imports
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use env_logger::Env;
+use primitive_types::U256;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-  // parse distribution data
-  let (data, total_amount) = parse_data();
+  // serve the OpenZeppelin StandardMerkleTree dump in `STANDARD_TREE_FILE` when set, otherwise build
+  // the tree from the distribution data
+  let (tree, total_amount, leaf_count) = match env::var("STANDARD_TREE_FILE") {
+    Ok(path) => {
+      let dump = fs::read_to_string(path).expect("Failed to read STANDARD_TREE_FILE");
+      let dump: StandardTreeDump = serde_json::from_str(&dump).expect("Invalid STANDARD_TREE_FILE");
+      let (tree, total_amount) = load_standard_tree(&dump).expect("Failed to load standard tree");
+      (
+        DistributionTree::Standard(tree),
+        total_amount,
+        dump.values.len() as u64,
+      )
+    }
+    Err(_) => build_tree(),
+  };
 
-  // build merkle tree
-  let tree = IndexedMerkleTree::<DistributionEntry, KeccakHasher>::new(data, KeccakHasher);
+  // bind the root to its distribution when `CHAIN_ID` is set, with the `TOKEN_ADDRESS` paid out in
+  // round `ROUND_ID` (defaults to 0)
+  let envelope = env::var("CHAIN_ID").ok().map(|chain_id| {
+    let token = env::var("TOKEN_ADDRESS").expect("TOKEN_ADDRESS is required with CHAIN_ID");
+    let token = hex::decode(token.trim_start_matches("0x")).expect("Invalid TOKEN_ADDRESS");
+    let options = EnvelopeOptions {
+      total: U256::from_dec_str(&total_amount).unwrap(),
+      round_id: env::var("ROUND_ID")
+        .map(|round_id| round_id.parse().expect("Invalid ROUND_ID"))
+        .unwrap_or(0),
+      chain_id: chain_id.parse().expect("Invalid CHAIN_ID"),
+      token: token.try_into().expect("Invalid TOKEN_ADDRESS"),
+      scheme_id: tree.scheme(tree_depth()).id(),
+    };
+    CommitmentEnvelope::new(tree.root_hash(), leaf_count, tree.leaf_capacity(), options)
+  });
+
+  // `/tree/node` serves the address and amount of leaves only with `EXPOSE_LEAF_DATA=1`
+  let expose_leaf_data = env::var("EXPOSE_LEAF_DATA").is_ok_and(|value| value == "1");
 
   // wrap in Arc for thread-safe shared access
-  let app_state = Arc::new(AppState { tree, total_amount });
+  let app_state = Arc::new(AppState {
+    tree,
+    total_amount,
+    envelope,
+    expose_leaf_data,
+  });
 
   // set log level
   env_logger::init_from_env(Env::default().default_filter_or("info"));
@@ -35,6 +78,9 @@ async fn main() -> std::io::Result<()> {
       .service(status)
       .service(get_info)
       .service(get_proof)
+      .service(verify_batch)
+      .service(verify_proof)
+      .service(get_node)
       .service(
         SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-docs/openapi.json", ApiDoc::openapi()),
       )
@@ -43,3 +89,70 @@ async fn main() -> std::io::Result<()> {
   .run()
   .await
 }
+
+// pad the tree to a fixed height with `TREE_DEPTH`, so that every proof has the same length
+fn tree_depth() -> Option<usize> {
+  env::var("TREE_DEPTH")
+    .ok()
+    .map(|depth| depth.parse().expect("Invalid TREE_DEPTH"))
+}
+
+// tree of the distribution data, with its total amount and number of entries
+fn build_tree() -> (DistributionTree, String, u64) {
+  // parse distribution data
+  let (mut data, total_amount) = parse_data().unwrap_or_else(|err| {
+    panic!(
+      "Invalid entry for {} with amount {}: {}",
+      err.entry.address, err.entry.amount, err.reason
+    )
+  });
+
+  // salt leaves with the hex encoded secret seed in `SALT_SEED_FILE`, kept apart from the data so
+  // that only the operator can rebuild the tree
+  if let Ok(path) = env::var("SALT_SEED_FILE") {
+    let seed = fs::read_to_string(path).expect("Failed to read SALT_SEED_FILE");
+    let seed = hex::decode(seed.trim().trim_start_matches("0x")).expect("Invalid salt seed");
+    apply_salts(&mut data, &seed);
+  }
+
+  let leaf_count = data.len() as u64;
+
+  let depth = tree_depth();
+
+  // build merkle tree, split into `2^SHARD_BITS` shards if set, of which only the comma separated
+  // shard ids in `SHARDS` are served (defaults to all)
+  let tree = match env::var("SHARD_BITS") {
+    Ok(shard_bits) => {
+      // shards are padded to a common height of their own, which a fixed depth would not match
+      assert!(
+        depth.is_none(),
+        "TREE_DEPTH can't be combined with SHARD_BITS"
+      );
+      let shard_bits = shard_bits.parse().expect("Invalid SHARD_BITS");
+      let served = env::var("SHARDS").ok().map(|shards| {
+        shards
+          .split(',')
+          .map(|shard| shard.trim().parse().expect("Invalid SHARDS"))
+          .collect::<Vec<usize>>()
+      });
+      let tree = build_sharded_tree(data, shard_bits, served.as_deref())
+        .expect("Failed to build sharded tree");
+      DistributionTree::Sharded(tree)
+    }
+    Err(_) => {
+      let options = TreeOptions {
+        depth,
+        ordering: address_ordering(),
+        ..Default::default()
+      };
+      let tree = IndexedMerkleTree::<DistributionEntry, KeccakHasher>::with_options(
+        data,
+        KeccakHasher,
+        options,
+      )
+      .expect("Failed to build tree");
+      DistributionTree::Full(tree)
+    }
+  };
+  (tree, total_amount, leaf_count)
+}
@@ -1,14 +1,15 @@
 // Local imports
-use crate::data_parser::DistributionEntry;
-use crate::AppState;
-use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use crate::data_parser::{address_key, check_entry, DistributionEntry};
+use crate::{AppState, DistributionTree, TreeDigest};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::{Digest, KeccakHasher};
 use indexed_merkle_tree::proof::MerkleProof;
 
 // Core lib imports
 use std::sync::Arc;
 
 // External imports
-use actix_web::{get, post, web, HttpResponse, Responder};
+use actix_web::{get, http::header, post, web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use utoipa::{IntoParams, OpenApi, ToSchema};
 
@@ -18,7 +19,9 @@ use utoipa::{IntoParams, OpenApi, ToSchema};
       status,
       get_info,
       get_proof,
-      verify_proof
+      verify_proof,
+      verify_batch,
+      get_node
     ),
     tags(
         (name = "Merkle distributor API", description = "API to request Merkle proofs for reward distribution.")
@@ -42,16 +45,31 @@ async fn status() -> impl Responder {
   get,
   path = "/info",
   responses(
-    (status = 200, description = "Return the total amount of rewards and Merkle root hash"),
+    (status = 200, description = "Return the total amount of rewards and Merkle root hash, and \
+      the commitment envelope binding the root to its round, chain and token when configured"),
   )
 )]
 #[get("/info")]
 async fn get_info(app_state: web::Data<Arc<AppState>>) -> impl Responder {
-  let root_hash = app_state.tree.root.hash;
-  HttpResponse::Ok().json(serde_json::json!({
+  let root_hash = app_state.tree.root_hash();
+  let mut info = serde_json::json!({
     "total_amount": app_state.total_amount,
-    "root_hash": format!("0x{}", hex::encode(root_hash)),
-  }))
+    "root_hash": root_hash.to_hex(),
+  });
+  if let Some(envelope) = &app_state.envelope {
+    info["envelope"] = serde_json::json!({
+      "hash": envelope.hash().to_hex(),
+      "root": envelope.root.to_hex(),
+      "total": envelope.total.to_string(),
+      "leaf_count": envelope.leaf_count,
+      "leaf_capacity": envelope.leaf_capacity,
+      "round_id": envelope.round_id,
+      "chain_id": envelope.chain_id,
+      "token": format!("0x{}", hex::encode(envelope.token)),
+      "scheme_id": format!("0x{}", hex::encode(envelope.scheme_id)),
+    });
+  }
+  HttpResponse::Ok().json(info)
 }
 
 #[derive(Deserialize, IntoParams)]
@@ -62,9 +80,14 @@ struct ProofQuery {
 #[derive(Serialize)]
 struct ProofResponse {
   amount: String,
+  index: usize,
   proof: Vec<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  salt: Option<String>,
 }
 
+const BINARY_CONTENT_TYPE: &str = "application/octet-stream";
+
 #[utoipa::path(
   get,
   path = "/proof",
@@ -72,24 +95,42 @@ struct ProofResponse {
     ProofQuery
   ),
   responses(
-    (status = 200, description = "Request Merkle proof for a given address"),
+    (status = 200, description = "Request Merkle proof for a given address. Send `Accept: \
+      application/octet-stream` to receive the compact binary encoding instead of JSON, with the \
+      amount in the `x-amount` header."),
   )
 )]
 #[get("/proof")]
 async fn get_proof(
+  req: HttpRequest,
   app_state: web::Data<Arc<AppState>>,
   query: web::Query<ProofQuery>,
 ) -> impl Responder {
-  let key = KeccakHasher.hash_leaf(&query.address.as_bytes());
+  let key = address_key(&query.address);
   match app_state.tree.get_proof(key) {
+    Ok(proof) if accepts_binary(&req) => {
+      let mut response = HttpResponse::Ok();
+      response
+        .content_type(BINARY_CONTENT_TYPE)
+        .insert_header(("x-amount", proof.data.amount.clone()));
+      if let Some(salt) = proof.data.salt {
+        response.insert_header(("x-salt", format!("0x{}", hex::encode(salt))));
+      }
+      // the served trees are binary
+      match proof.encode(&KeccakHasher, 2) {
+        Ok(bytes) => response.body(bytes),
+        Err(_) => HttpResponse::InternalServerError().body("Failed to encode proof."),
+      }
+    }
     Ok(proof) => {
       let formatted = ProofResponse {
         amount: proof.data.amount,
-        proof: proof
-          .proof
-          .iter()
-          .map(|h| format!("0x{}", hex::encode(h)))
-          .collect(),
+        index: proof.index,
+        proof: proof.proof.iter().map(Digest::to_hex).collect(),
+        salt: proof
+          .data
+          .salt
+          .map(|salt| format!("0x{}", hex::encode(salt))),
       };
       HttpResponse::Ok().json(serde_json::json!(formatted))
     }
@@ -97,11 +138,22 @@ async fn get_proof(
   }
 }
 
+fn accepts_binary(req: &HttpRequest) -> bool {
+  req
+    .headers()
+    .get(header::ACCEPT)
+    .and_then(|value| value.to_str().ok())
+    .is_some_and(|value| value.contains(BINARY_CONTENT_TYPE))
+}
+
 #[derive(Serialize, Deserialize, ToSchema)]
 pub struct VerifyProofQuery {
   pub address: String,
   pub amount: String,
   pub proof: Vec<String>,
+  // salt returned by `/proof`, required when the tree is salted
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub salt: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -125,24 +177,156 @@ async fn verify_proof(
   app_state: web::Data<Arc<AppState>>,
   body: web::Json<VerifyProofQuery>,
 ) -> impl Responder {
-  let proof: MerkleProof<DistributionEntry> = MerkleProof {
-    data: DistributionEntry {
-      address: body.address.clone(),
-      amount: body.amount.clone(),
-    },
-    proof: body
-      .proof
-      .iter()
-      .map(|h| {
-        let bytes = hex::decode(h.trim_start_matches("0x")).unwrap();
-        let mut arr = [0u8; 32];
-        arr.copy_from_slice(&bytes[..32]);
-        arr
-      })
-      .collect(),
+  let Some(proof) = parse_proof(&app_state, &body) else {
+    return HttpResponse::InternalServerError().body("Failed to verify proof.");
   };
   match app_state.tree.verify_proof(proof) {
     Ok(valid) => HttpResponse::Ok().json(VerifyProofResponse { valid }),
     _ => HttpResponse::InternalServerError().body("Failed to verify proof."),
   }
 }
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct VerifyBatchQuery {
+  pub proofs: Vec<VerifyProofQuery>,
+}
+
+#[derive(Serialize)]
+struct VerifyBatchResponse {
+  results: Vec<bool>,
+}
+
+#[utoipa::path(
+  post,
+  path = "/verify/batch",
+  request_body(
+    content = VerifyBatchQuery,
+    content_type = "application/json",
+  ),
+  responses(
+    (status = 200, description = "Verify many Merkle proofs at once, returning whether each proof is valid in request order"),
+  )
+)]
+#[post("/verify/batch")]
+async fn verify_batch(
+  app_state: web::Data<Arc<AppState>>,
+  body: web::Json<VerifyBatchQuery>,
+) -> impl Responder {
+  // malformed proofs and unknown addresses are reported as invalid without failing the batch
+  let parsed: Vec<Option<MerkleProof<DistributionEntry, TreeDigest>>> = body
+    .proofs
+    .iter()
+    .map(|query| parse_proof(&app_state, query))
+    .collect();
+  let is_parsed: Vec<bool> = parsed.iter().map(Option::is_some).collect();
+  let proofs: Vec<MerkleProof<DistributionEntry, TreeDigest>> =
+    parsed.into_iter().flatten().collect();
+  let mut verified = app_state.tree.verify_batch(&proofs).into_iter();
+
+  let results = is_parsed
+    .into_iter()
+    .map(|is_parsed| is_parsed && verified.next().unwrap().is_ok())
+    .collect();
+  HttpResponse::Ok().json(VerifyBatchResponse { results })
+}
+
+#[derive(Deserialize, IntoParams)]
+struct NodeQuery {
+  level: usize,
+  index: usize,
+}
+
+#[derive(Serialize)]
+struct NodeResponse {
+  level: usize,
+  index: usize,
+  hash: String,
+  // distribution entry held by leaves, when the API exposes leaf data
+  #[serde(skip_serializing_if = "Option::is_none")]
+  data: Option<NodeData>,
+}
+
+// salts are never served, they keep the leaves of other addresses from being recomputed
+#[derive(Serialize)]
+struct NodeData {
+  address: String,
+  amount: String,
+}
+
+#[utoipa::path(
+  get,
+  path = "/tree/node",
+  params(
+    NodeQuery
+  ),
+  responses(
+    (status = 200, description = "Browse the tree node at a level (0 for leaves) and index. Leaves \
+      include their address and amount only when the API runs with `EXPOSE_LEAF_DATA=1`, and never \
+      their salt"),
+    (status = 400, description = "Node browsing is not available for sharded trees"),
+    (status = 404, description = "No node is stored at this position"),
+  )
+)]
+#[get("/tree/node")]
+async fn get_node(
+  app_state: web::Data<Arc<AppState>>,
+  query: web::Query<NodeQuery>,
+) -> impl Responder {
+  let node = match &app_state.tree {
+    DistributionTree::Full(tree) => tree.get_node(query.level, query.index),
+    DistributionTree::Standard(tree) => tree.get_node(query.level, query.index),
+    DistributionTree::Sharded(_) => {
+      return HttpResponse::BadRequest().body("Node browsing is not available for sharded trees.")
+    }
+  };
+  match node {
+    Ok(node) => HttpResponse::Ok().json(NodeResponse {
+      level: query.level,
+      index: query.index,
+      hash: node.hash.to_hex(),
+      data: node
+        .data
+        .filter(|_| app_state.expose_leaf_data)
+        .map(|entry| NodeData {
+          address: entry.address,
+          amount: entry.amount,
+        }),
+    }),
+    Err(MerkleError::NodeNotFound { .. }) => HttpResponse::NotFound().body("Node not found."),
+    _ => HttpResponse::InternalServerError().body("Failed to get node."),
+  }
+}
+
+fn parse_proof(
+  app_state: &AppState,
+  query: &VerifyProofQuery,
+) -> Option<MerkleProof<DistributionEntry, TreeDigest>> {
+  let key = address_key(&query.address);
+  let index = app_state.tree.get_index(key).ok()?;
+  let proof = query
+    .proof
+    .iter()
+    .map(|h| TreeDigest::from_hex(h))
+    .collect::<Option<Vec<_>>>()?;
+  let salt = match &query.salt {
+    Some(salt) => Some(parse_salt(salt)?),
+    None => None,
+  };
+
+  // entries that can't be encoded as a leaf can't be part of the tree
+  let data = DistributionEntry {
+    address: query.address.clone(),
+    amount: query.amount.clone(),
+    salt,
+  };
+  check_entry(&data).ok()?;
+
+  Some(MerkleProof { data, index, proof })
+}
+
+fn parse_salt(salt: &str) -> Option<[u8; 32]> {
+  hex::decode(salt.trim_start_matches("0x"))
+    .ok()?
+    .try_into()
+    .ok()
+}
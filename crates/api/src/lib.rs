@@ -1,11 +1,188 @@
 pub mod data_parser;
 pub mod endpoints;
 
-use data_parser::DistributionEntry;
-use indexed_merkle_tree::{hasher::KeccakHasher, tree::IndexedMerkleTree};
+use data_parser::{address_ordering, DistributionEntry, LEAF_SCHEME};
+use indexed_merkle_tree::{
+  batch,
+  envelope::{leaf_capacity, CommitmentEnvelope, TreeScheme},
+  errors::MerkleError,
+  hasher::{Hasher, KeccakHasher, PairOrder, StandardHasher},
+  openzeppelin::StandardTreeDump,
+  proof::MerkleProof,
+  shard::{partition, shard_height, ShardedMerkleTree},
+  tree::{IndexedMerkleTree, TreeOptions},
+};
+
+// Core lib imports
+use std::collections::HashMap;
+
+// External imports
+use primitive_types::U256;
+
+// digest of the tree hasher, as returned in proofs and root hashes
+pub type TreeDigest = <KeccakHasher as Hasher>::Digest;
+
+// name of the OpenZeppelin StandardMerkleTree leaf layout, hashed into the envelope scheme id
+pub const STANDARD_LEAF_SCHEME: &str = "keccak256/openzeppelin-standard";
 
 // Application state containing the merkle tree
 pub struct AppState {
-  pub tree: IndexedMerkleTree<DistributionEntry, KeccakHasher>,
+  pub tree: DistributionTree,
   pub total_amount: String,
+  pub envelope: Option<CommitmentEnvelope>, // commitment served by `/info` when configured
+  pub expose_leaf_data: bool, // leaf entries served by `/tree/node`, hashes only when off
+}
+
+// Merkle tree served by the API, either held in full or as a subset of shards, or an OpenZeppelin
+// StandardMerkleTree built elsewhere, whose proofs verify with sorted pairs
+pub enum DistributionTree {
+  Full(IndexedMerkleTree<DistributionEntry, KeccakHasher>),
+  Sharded(ShardedMerkleTree<DistributionEntry, KeccakHasher>),
+  Standard(IndexedMerkleTree<DistributionEntry, StandardHasher>),
+}
+
+impl DistributionTree {
+  pub fn root_hash(&self) -> TreeDigest {
+    match self {
+      DistributionTree::Full(tree) => tree.root.hash,
+      DistributionTree::Sharded(tree) => tree.root.hash,
+      DistributionTree::Standard(tree) => tree.root.hash,
+    }
+  }
+
+  // leaf positions proofs can address, committed by the envelope
+  pub fn leaf_capacity(&self) -> u64 {
+    match self {
+      DistributionTree::Full(tree) => leaf_capacity(tree.arity, tree.height),
+      DistributionTree::Sharded(tree) => leaf_capacity(2, tree.height().unwrap_or(0)),
+      DistributionTree::Standard(tree) => leaf_capacity(2, tree.height),
+    }
+  }
+
+  // envelope scheme of the leaf layout and tree shape, `depth` is the fixed depth a full tree was
+  // built with. Shards are padded to a common height, so sharded proofs always have the same
+  // length.
+  pub fn scheme(&self, depth: Option<usize>) -> TreeScheme {
+    match self {
+      DistributionTree::Full(tree) => TreeScheme {
+        name: LEAF_SCHEME,
+        pair_order: PairOrder::Positional,
+        arity: tree.arity,
+        depth,
+      },
+      DistributionTree::Sharded(tree) => TreeScheme {
+        name: LEAF_SCHEME,
+        pair_order: PairOrder::Positional,
+        arity: 2,
+        depth: tree.height(),
+      },
+      DistributionTree::Standard(_) => TreeScheme {
+        name: STANDARD_LEAF_SCHEME,
+        pair_order: PairOrder::Sorted,
+        arity: 2,
+        depth: None,
+      },
+    }
+  }
+
+  pub fn get_index(&self, key: [u8; 32]) -> Result<usize, MerkleError> {
+    match self {
+      DistributionTree::Full(tree) => tree.get_index(key),
+      DistributionTree::Sharded(tree) => tree.get_index(key),
+      DistributionTree::Standard(tree) => tree.get_index(key),
+    }
+  }
+
+  pub fn get_proof(
+    &self,
+    key: [u8; 32],
+  ) -> Result<MerkleProof<DistributionEntry, TreeDigest>, MerkleError> {
+    match self {
+      DistributionTree::Full(tree) => tree.get_proof(key),
+      DistributionTree::Sharded(tree) => tree.get_proof(key),
+      DistributionTree::Standard(tree) => tree.get_standard_proof(key),
+    }
+  }
+
+  pub fn verify_proof(
+    &self,
+    proof: MerkleProof<DistributionEntry, TreeDigest>,
+  ) -> Result<bool, MerkleError> {
+    match self {
+      DistributionTree::Full(tree) => tree.verify_proof(proof),
+      DistributionTree::Sharded(tree) => tree.verify_proof(proof),
+      DistributionTree::Standard(tree) => proof.verify_sorted(tree.root.hash, &StandardHasher),
+    }
+  }
+
+  // verify many proofs against the root, with the same result for each proof as `verify_proof`
+  pub fn verify_batch(
+    &self,
+    proofs: &[MerkleProof<DistributionEntry, TreeDigest>],
+  ) -> Vec<Result<bool, MerkleError>> {
+    match self {
+      DistributionTree::Standard(tree) => proofs
+        .iter()
+        .map(|proof| proof.verify_sorted(tree.root.hash, &StandardHasher))
+        .collect(),
+      DistributionTree::Full(tree) => {
+        batch::verify_batch_parallel(tree.root.hash, proofs, &KeccakHasher, tree.arity)
+      }
+      // shards are always binary
+      DistributionTree::Sharded(tree) => {
+        batch::verify_batch_parallel(tree.root.hash, proofs, &KeccakHasher, 2)
+      }
+    }
+  }
+}
+
+// Load an OpenZeppelin StandardMerkleTree dump, returning the tree and the total amount. Values are
+// decoded as entries, so the dump must use the `abi-leaves` layout: `["address", "uint256"]`, with
+// a trailing `bytes32` for salted entries.
+pub fn load_standard_tree(
+  dump: &StandardTreeDump,
+) -> Result<(IndexedMerkleTree<DistributionEntry, StandardHasher>, String), MerkleError> {
+  let tree = IndexedMerkleTree::<DistributionEntry, StandardHasher>::from_standard_dump(dump)?;
+  let mut total_amount = U256::zero();
+  for index in 0..1 << tree.height {
+    if let Some(entry) = tree.get_node(0, index)?.data {
+      // amounts are decoded from a uint256, but their sum may not fit one
+      total_amount = U256::from_dec_str(&entry.amount)
+        .ok()
+        .and_then(|amount| total_amount.checked_add(amount))
+        .ok_or(MerkleError::InvalidDump {
+          reason: "total amount overflows",
+        })?;
+    }
+  }
+  Ok((tree, total_amount.to_string()))
+}
+
+// Build every shard to obtain its root, but only keep the shards this replica serves (all shards
+// if `served` is `None`). The whole distribution is still parsed and partitioned up front, so peak
+// memory is that of the full data set, only the trees kept once the build is done are limited to
+// the served subset. Shards are padded to a common height so that leaf indices line up across
+// replicas.
+pub fn build_sharded_tree(
+  data: Vec<DistributionEntry>,
+  shard_bits: u32,
+  served: Option<&[usize]>,
+) -> Result<ShardedMerkleTree<DistributionEntry, KeccakHasher>, MerkleError> {
+  let mut shard_roots = Vec::new();
+  let mut shards = HashMap::new();
+  let partitions = partition(data, shard_bits);
+  let depth = shard_height(&partitions);
+  for (shard, data) in partitions.into_iter().enumerate() {
+    let options = TreeOptions {
+      depth: Some(depth),
+      ordering: address_ordering(),
+      ..Default::default()
+    };
+    let tree = IndexedMerkleTree::with_options(data, KeccakHasher, options)?;
+    shard_roots.push(tree.root.hash);
+    if served.is_none_or(|served| served.contains(&shard)) {
+      shards.insert(shard, tree);
+    }
+  }
+  ShardedMerkleTree::from_shards(shard_bits, shard_roots, shards, KeccakHasher)
 }
@@ -0,0 +1,227 @@
+#![cfg(feature = "abi-leaves")]
+
+use actix_web::{test, web, App};
+use api::data_parser::{address_key, check_entry, DistributionEntry};
+use api::endpoints::{
+  get_info, get_proof, verify_batch, verify_proof, VerifyBatchQuery, VerifyProofQuery,
+};
+use api::{load_standard_tree, AppState, DistributionTree};
+use distribution_entry::{Claim, LeafEncoding};
+use indexed_merkle_tree::{
+  hasher::{KeccakHasher, StandardHasher},
+  node::SerializableData,
+  openzeppelin::StandardTreeDump,
+  tree::IndexedMerkleTree,
+};
+use std::sync::Arc;
+
+const ALICE: &str = "0xb36fabce667ba88bdb6b24d35df681bdd37f1ecc";
+const BOB: &str = "0xc229ada115edfbaa156a721d27f8da9359f748f5";
+
+#[actix_rt::test]
+async fn test_abi_leaf_layout() {
+  let entry = DistributionEntry {
+    address: ALICE.to_uppercase().replace("0X", "0x"),
+    amount: "1500000000000000000".to_string(),
+    salt: None,
+  };
+  let bytes = entry.to_bytes();
+  let expected = hex::decode(concat!(
+    "000000000000000000000000b36fabce667ba88bdb6b24d35df681bdd37f1ecc",
+    "00000000000000000000000000000000000000000000000014d1120d7b160000",
+  ))
+  .unwrap();
+  assert_eq!(bytes, expected);
+
+  let decoded = DistributionEntry::from_bytes(bytes).unwrap();
+  assert_eq!(decoded.address, ALICE);
+  assert_eq!(decoded.amount, entry.amount);
+  assert_eq!(decoded.key(), entry.key());
+  assert_eq!(entry.key(), address_key(ALICE));
+}
+
+#[actix_rt::test]
+async fn test_keys_match_claim_keys() {
+  // the API keys an address like the claims of the wasm verifier and generated contracts
+  let claim = Claim {
+    account: hex::decode(&ALICE[2..]).unwrap().try_into().unwrap(),
+    amount: 1.into(),
+    salt: None,
+    encoding: LeafEncoding::Abi,
+  };
+  assert_eq!(address_key(ALICE), claim.key());
+  assert_eq!(
+    address_key(&ALICE.to_uppercase().replace("0X", "0x")),
+    claim.key()
+  );
+  assert_eq!(address_key(&ALICE[2..]), claim.key());
+  let entry = DistributionEntry::from_bytes(claim.to_bytes()).unwrap();
+  assert_eq!(entry.key(), claim.key());
+}
+
+#[actix_rt::test]
+async fn test_verify_abi_leaf() {
+  let entries = vec![
+    DistributionEntry {
+      address: ALICE.to_string(),
+      amount: "100".to_string(),
+      salt: None,
+    },
+    DistributionEntry {
+      address: BOB.to_string(),
+      amount: "200".to_string(),
+      salt: None,
+    },
+  ];
+  let tree = IndexedMerkleTree::<DistributionEntry, KeccakHasher>::new(entries, KeccakHasher);
+  let proof = tree.get_proof(address_key(ALICE)).unwrap();
+  let app_state = web::Data::new(Arc::new(AppState {
+    tree: DistributionTree::Full(tree),
+    total_amount: "300".to_string(),
+    envelope: None,
+    expose_leaf_data: false,
+  }));
+  let app = test::init_service(App::new().app_data(app_state).service(verify_proof)).await;
+
+  for (amount, valid) in [("100", true), ("101", false), ("not a number", false)] {
+    let req = test::TestRequest::post()
+      .uri("/verify")
+      .set_json(VerifyProofQuery {
+        address: ALICE.to_string(),
+        amount: amount.to_string(),
+        proof: proof
+          .proof
+          .iter()
+          .map(|hash| format!("0x{}", hex::encode(hash)))
+          .collect(),
+        salt: None,
+      })
+      .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().is_success(), valid, "amount {}", amount);
+  }
+
+  // an address with the key of a leaf that does not encode as an address is rejected, not hashed
+  let req = test::TestRequest::post()
+    .uri("/verify")
+    .set_json(VerifyProofQuery {
+      address: ALICE.to_uppercase(),
+      amount: "100".to_string(),
+      proof: vec![],
+      salt: None,
+    })
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+  assert!(resp.status().is_server_error());
+  assert_eq!(
+    check_entry(&DistributionEntry {
+      address: ALICE.to_uppercase(),
+      amount: "100".to_string(),
+      salt: None,
+    }),
+    Err("invalid address")
+  );
+}
+
+#[actix_rt::test]
+async fn test_decode_arbitrary_abi_leaf_bytes() {
+  // seeded so failures can be replayed
+  let mut rng = fastrand::Rng::with_seed(0x9e37_79b9_7f4a_7c15);
+
+  for _ in 0..5_000 {
+    let len = [64, 96][rng.usize(..2)] - 4 + rng.usize(..8);
+    let mut bytes: Vec<u8> = (0..len).map(|_| rng.u8(..)).collect();
+    // clear the address padding now and then so some inputs decode
+    if rng.bool() && bytes.len() >= 12 {
+      bytes[..12].fill(0);
+    }
+    if let Ok(entry) = DistributionEntry::from_bytes(bytes.clone()) {
+      assert_eq!(entry.to_bytes(), bytes);
+    }
+  }
+}
+
+#[actix_rt::test]
+async fn test_serve_standard_tree_dump() {
+  let entries: Vec<DistributionEntry> = [ALICE, BOB, "0x3333333333333333333333333333333333333333"]
+    .iter()
+    .enumerate()
+    .map(|(i, address)| DistributionEntry {
+      address: address.to_string(),
+      amount: (100 * (i + 1)).to_string(),
+      salt: None,
+    })
+    .collect();
+  let tree = IndexedMerkleTree::<DistributionEntry, StandardHasher>::standard(entries).unwrap();
+
+  // dumps written by `@openzeppelin/merkle-tree` hold checksummed addresses
+  let json = serde_json::to_string(&tree.to_standard_dump(&["address", "uint256"]).unwrap())
+    .unwrap()
+    .replace(ALICE, &ALICE.to_uppercase().replace("0X", "0x"));
+  let dump: StandardTreeDump = serde_json::from_str(&json).unwrap();
+  let (loaded, total_amount) = load_standard_tree(&dump).unwrap();
+  assert_eq!(loaded.root.hash, tree.root.hash);
+  assert_eq!(total_amount, "600");
+
+  let app_state = web::Data::new(Arc::new(AppState {
+    tree: DistributionTree::Standard(loaded),
+    total_amount,
+    envelope: None,
+    expose_leaf_data: false,
+  }));
+  let app = test::init_service(
+    App::new()
+      .app_data(app_state)
+      .service(get_info)
+      .service(get_proof)
+      .service(verify_batch)
+      .service(verify_proof),
+  )
+  .await;
+
+  let req = test::TestRequest::get().uri("/info").to_request();
+  let json: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(
+    json["root_hash"].as_str().unwrap(),
+    format!("0x{}", hex::encode(tree.root.hash))
+  );
+
+  // proofs are the sorted-pair proofs of the dump, without empty siblings
+  let req = test::TestRequest::get()
+    .uri(&format!("/proof?address={}", ALICE))
+    .to_request();
+  let json: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+  let expected = tree.get_standard_proof(address_key(ALICE)).unwrap();
+  let proof: Vec<String> = expected
+    .proof
+    .iter()
+    .map(|hash| format!("0x{}", hex::encode(hash)))
+    .collect();
+  assert_eq!(json["amount"], "100");
+  assert_eq!(json["proof"], serde_json::json!(proof));
+  assert!(expected
+    .verify_sorted(tree.root.hash, &StandardHasher)
+    .unwrap());
+
+  let query = |amount: &str| VerifyProofQuery {
+    address: ALICE.to_string(),
+    amount: amount.to_string(),
+    proof: proof.clone(),
+    salt: None,
+  };
+  let req = test::TestRequest::post()
+    .uri("/verify")
+    .set_json(query("100"))
+    .to_request();
+  let json: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(json["valid"], true);
+
+  let req = test::TestRequest::post()
+    .uri("/verify/batch")
+    .set_json(VerifyBatchQuery {
+      proofs: vec![query("100"), query("101")],
+    })
+    .to_request();
+  let json: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(json["results"], serde_json::json!([true, false]));
+}
@@ -1,27 +1,51 @@
 use actix_web::{test, web, App};
-use api::data_parser::DistributionEntry;
-use api::endpoints::{get_info, get_proof, status, verify_proof, VerifyProofQuery};
-use api::AppState;
+use api::data_parser::{
+  add_amounts, address_key, address_ordering, apply_salts, merge_entries, DistributionEntry,
+  LEAF_SCHEME,
+};
+use api::endpoints::{
+  get_info, get_node, get_proof, status, verify_batch, verify_proof, VerifyBatchQuery,
+  VerifyProofQuery,
+};
+use api::{build_sharded_tree, AppState, DistributionTree, TreeDigest};
 use indexed_merkle_tree::{
-  hasher::{Hasher, KeccakHasher},
-  tree::IndexedMerkleTree,
+  envelope::{EnvelopeOptions, TreeScheme},
+  hasher::{Digest, Hasher, KeccakHasher, PairOrder},
+  node::SerializableData,
+  proof::MerkleProof,
+  shard::shard_of,
+  stream::{build_snapshot, StreamOptions},
+  tree::{IndexedMerkleTree, TreeOptions},
 };
+use primitive_types::U256;
 use std::sync::Arc;
 
+// lowercase hex addresses, valid in both leaf layouts and sorted like their names
+const ALICE: &str = "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+const BOB: &str = "0xbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+const CHARLIE: &str = "0xcccccccccccccccccccccccccccccccccccccccc";
+
 fn create_test_data() -> web::Data<Arc<AppState>> {
   let test_data = vec![
     DistributionEntry {
-      address: "alice".to_string(),
+      address: ALICE.to_string(),
       amount: "100".to_string(),
+      salt: None,
     },
     DistributionEntry {
-      address: "bob".to_string(),
+      address: BOB.to_string(),
       amount: "200".to_string(),
+      salt: None,
     },
   ];
   let total_amount = "300".to_string();
   let tree = IndexedMerkleTree::<DistributionEntry, KeccakHasher>::new(test_data, KeccakHasher);
-  web::Data::new(Arc::new(AppState { tree, total_amount }))
+  web::Data::new(Arc::new(AppState {
+    tree: DistributionTree::Full(tree),
+    total_amount,
+    envelope: None,
+    expose_leaf_data: false,
+  }))
 }
 
 #[actix_rt::test]
@@ -45,19 +69,103 @@ async fn test_get_info_endpoint() {
   let body = test::read_body(resp).await;
   let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
-  let exp_root_hash = format!("0x{}", hex::encode(app_state.tree.root.hash));
+  let exp_root_hash = format!("0x{}", hex::encode(app_state.tree.root_hash()));
 
   assert_eq!(json["total_amount"], "300");
   assert_eq!(json["root_hash"].as_str().unwrap(), exp_root_hash);
 }
 
+#[actix_rt::test]
+async fn test_get_info_envelope() {
+  let app_state = create_test_data();
+  let DistributionTree::Full(tree) = &app_state.tree else {
+    unreachable!()
+  };
+  let options = EnvelopeOptions {
+    total: 300.into(),
+    round_id: 2,
+    chain_id: 8453,
+    token: [0x11; 20],
+    scheme_id: app_state.tree.scheme(None).id(),
+  };
+  let envelope = tree.envelope(options).unwrap();
+  let envelope_hash = envelope.hash();
+  let app_state = web::Data::new(Arc::new(AppState {
+    tree: DistributionTree::Full(tree.clone()),
+    total_amount: "300".to_string(),
+    envelope: Some(envelope.clone()),
+    expose_leaf_data: false,
+  }));
+  let app = test::init_service(App::new().app_data(app_state.clone()).service(get_info)).await;
+
+  let req = test::TestRequest::get().uri("/info").to_request();
+  let resp = test::call_service(&app, req).await;
+  assert!(resp.status().is_success());
+  let body = test::read_body(resp).await;
+  let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+  let envelope_json = &json["envelope"];
+  assert_eq!(
+    envelope_json["hash"].as_str().unwrap(),
+    format!("0x{}", hex::encode(envelope_hash))
+  );
+  assert_eq!(envelope_json["root"], json["root_hash"]);
+  assert_eq!(envelope_json["total"], "300");
+  assert_eq!(envelope_json["leaf_count"], 2);
+  assert_eq!(envelope_json["leaf_capacity"], 2);
+  assert_eq!(
+    envelope_json["scheme_id"].as_str().unwrap(),
+    format!(
+      "0x{}",
+      hex::encode(
+        TreeScheme {
+          name: LEAF_SCHEME,
+          pair_order: PairOrder::Positional,
+          arity: 2,
+          depth: None,
+        }
+        .id()
+      )
+    )
+  );
+  assert_eq!(envelope_json["round_id"], 2);
+  assert_eq!(envelope_json["chain_id"], 8453);
+  assert_eq!(
+    envelope_json["token"].as_str().unwrap(),
+    format!("0x{}", hex::encode([0x11; 20]))
+  );
+
+  // proofs served by the API verify against the envelope
+  let entry = DistributionEntry {
+    address: BOB.to_string(),
+    amount: "200".to_string(),
+    salt: None,
+  };
+  let proof = tree.get_proof(entry.key()).unwrap();
+  assert!(envelope
+    .verify_proof(
+      envelope_hash,
+      &app_state.tree.scheme(None),
+      &proof,
+      &KeccakHasher
+    )
+    .unwrap());
+
+  // without an envelope `/info` keeps its previous shape
+  let app_state = create_test_data();
+  let app = test::init_service(App::new().app_data(app_state.clone()).service(get_info)).await;
+  let req = test::TestRequest::get().uri("/info").to_request();
+  let json: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+  assert!(json.get("envelope").is_none());
+}
+
 #[actix_rt::test]
 async fn test_get_proof_endpoint() {
   let app_state = create_test_data();
   let app = test::init_service(App::new().app_data(app_state.clone()).service(get_proof)).await;
 
   let req = test::TestRequest::get()
-    .uri("/proof?address=alice")
+    .uri(&format!("/proof?address={}", ALICE))
     .to_request();
   let resp = test::call_service(&app, req).await;
 
@@ -66,22 +174,50 @@ async fn test_get_proof_endpoint() {
   let body = test::read_body(resp).await;
   let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
 
-  let sibling_hash = format!(
-    "0x{}",
-    hex::encode(app_state.tree.leaves.get(&(0, 1)).unwrap().hash)
-  );
+  let bob = DistributionEntry {
+    address: BOB.to_string(),
+    amount: "200".to_string(),
+    salt: None,
+  };
+  let sibling_hash = format!("0x{}", hex::encode(KeccakHasher.hash_leaf(&bob.to_bytes())));
 
   assert_eq!(json["amount"], "100");
   assert_eq!(json["proof"][0], sibling_hash);
 }
 
+#[actix_rt::test]
+async fn test_get_proof_endpoint_binary() {
+  let app_state = create_test_data();
+  let app = test::init_service(App::new().app_data(app_state.clone()).service(get_proof)).await;
+
+  let req = test::TestRequest::get()
+    .uri(&format!("/proof?address={}", BOB))
+    .insert_header(("Accept", "application/octet-stream"))
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+
+  assert!(resp.status().is_success());
+  assert_eq!(resp.headers().get("x-amount").unwrap(), "200");
+
+  let body = test::read_body(resp).await;
+  let data = DistributionEntry {
+    address: BOB.to_string(),
+    amount: "200".to_string(),
+    salt: None,
+  };
+  let proof = MerkleProof::decode(data, &body, &KeccakHasher, 2).unwrap();
+
+  assert_eq!(proof.index, 1);
+  app_state.tree.verify_proof(proof).unwrap();
+}
+
 #[actix_rt::test]
 async fn test_get_proof_invalid_address() {
   let app_state = create_test_data();
   let app = test::init_service(App::new().app_data(app_state.clone()).service(get_proof)).await;
 
   let req = test::TestRequest::get()
-    .uri("/proof?address=charlie")
+    .uri(&format!("/proof?address={}", CHARLIE))
     .to_request();
   let resp = test::call_service(&app, req).await;
 
@@ -99,10 +235,7 @@ async fn test_verify_proof_endpoint() {
   )
   .await;
 
-  let proof = app_state
-    .tree
-    .get_proof(KeccakHasher.hash_leaf(&"alice".as_bytes()))
-    .unwrap();
+  let proof = app_state.tree.get_proof(address_key(ALICE)).unwrap();
 
   let req = test::TestRequest::post()
     .uri("/verify")
@@ -114,6 +247,7 @@ async fn test_verify_proof_endpoint() {
         .iter()
         .map(|h| format!("0x{}", hex::encode(h)))
         .collect(),
+      salt: None,
     })
     .to_request();
   let resp = test::call_service(&app, req).await;
@@ -125,3 +259,342 @@ async fn test_verify_proof_endpoint() {
 
   assert_eq!(json["valid"], true);
 }
+
+#[actix_rt::test]
+async fn test_sharded_tree_serves_subset() {
+  let entries: Vec<DistributionEntry> = (0..16)
+    .map(|i| DistributionEntry {
+      address: format!("0x{:040x}", i + 1),
+      amount: "100".to_string(),
+      salt: None,
+    })
+    .collect();
+  let full = build_sharded_tree(entries.clone(), 2, None).unwrap();
+  let tree = build_sharded_tree(entries.clone(), 2, Some(&[0])).unwrap();
+  assert_eq!(tree.root.hash, full.root.hash);
+
+  let app_state = web::Data::new(Arc::new(AppState {
+    tree: DistributionTree::Sharded(tree),
+    total_amount: "1600".to_string(),
+    envelope: None,
+    expose_leaf_data: false,
+  }));
+  let app = test::init_service(App::new().app_data(app_state.clone()).service(get_proof)).await;
+
+  for entry in entries {
+    let req = test::TestRequest::get()
+      .uri(&format!("/proof?address={}", entry.address))
+      .to_request();
+    let resp = test::call_service(&app, req).await;
+
+    let key = address_key(&entry.address);
+    if shard_of(&key, 2) == 0 {
+      assert!(resp.status().is_success());
+      let body: serde_json::Value = test::read_body_json(resp).await;
+      let proof = MerkleProof {
+        index: body["index"].as_u64().unwrap() as usize,
+        proof: body["proof"]
+          .as_array()
+          .unwrap()
+          .iter()
+          .map(|hash| TreeDigest::from_hex(hash.as_str().unwrap()).unwrap())
+          .collect(),
+        data: entry,
+      };
+      assert_eq!(proof.index, full.get_index(key).unwrap());
+      full.verify_proof(proof).unwrap();
+    } else {
+      assert!(resp.status().is_server_error());
+    }
+  }
+}
+
+#[actix_rt::test]
+async fn test_get_node_endpoint() {
+  let app_state = create_test_data();
+  let DistributionTree::Full(tree) = &app_state.tree else {
+    unreachable!()
+  };
+  let app = test::init_service(App::new().app_data(app_state.clone()).service(get_node)).await;
+
+  let entry = DistributionEntry {
+    address: BOB.to_string(),
+    amount: "200".to_string(),
+    salt: None,
+  };
+  let index = tree.get_index(entry.key()).unwrap();
+  let req = test::TestRequest::get()
+    .uri(&format!("/tree/node?level=0&index={}", index))
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+  assert!(resp.status().is_success());
+  let json: serde_json::Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+  assert_eq!(
+    json["hash"].as_str().unwrap(),
+    format!("0x{}", hex::encode(tree.get_node(0, index).unwrap().hash))
+  );
+  // leaf data is off by default
+  assert!(json.get("data").is_none());
+
+  // internal nodes have no data
+  let req = test::TestRequest::get()
+    .uri(&format!("/tree/node?level={}&index=0", tree.height))
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+  let json: serde_json::Value = serde_json::from_slice(&test::read_body(resp).await).unwrap();
+  assert_eq!(
+    json["hash"].as_str().unwrap(),
+    format!("0x{}", hex::encode(tree.root.hash))
+  );
+  assert!(json.get("data").is_none());
+
+  let req = test::TestRequest::get()
+    .uri("/tree/node?level=0&index=100")
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+  assert_eq!(resp.status(), 404);
+
+  // opted in, leaves serve their address and amount but never their salt
+  let mut entries = vec![entry.clone()];
+  apply_salts(&mut entries, b"seed");
+  let salt = hex::encode(entries[0].salt.unwrap());
+  let salted = IndexedMerkleTree::<DistributionEntry, KeccakHasher>::new(entries, KeccakHasher);
+  let app_state = web::Data::new(Arc::new(AppState {
+    tree: DistributionTree::Full(salted),
+    total_amount: "200".to_string(),
+    envelope: None,
+    expose_leaf_data: true,
+  }));
+  let app = test::init_service(App::new().app_data(app_state).service(get_node)).await;
+  let req = test::TestRequest::get()
+    .uri("/tree/node?level=0&index=0")
+    .to_request();
+  let body = test::read_body(test::call_service(&app, req).await).await;
+  let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+  assert_eq!(json["data"]["address"], BOB);
+  assert_eq!(json["data"]["amount"], "200");
+  assert!(json["data"].get("salt").is_none());
+  assert!(!String::from_utf8(body.to_vec()).unwrap().contains(&salt));
+
+  let sharded = build_sharded_tree(vec![entry], 1, None).unwrap();
+  let app_state = web::Data::new(Arc::new(AppState {
+    tree: DistributionTree::Sharded(sharded),
+    total_amount: "200".to_string(),
+    envelope: None,
+    expose_leaf_data: false,
+  }));
+  let app = test::init_service(App::new().app_data(app_state).service(get_node)).await;
+  let req = test::TestRequest::get()
+    .uri("/tree/node?level=0&index=0")
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+  assert_eq!(resp.status(), 400);
+}
+
+#[actix_rt::test]
+async fn test_verify_batch_endpoint() {
+  let app_state = create_test_data();
+  let app = test::init_service(App::new().app_data(app_state.clone()).service(verify_batch)).await;
+
+  let query = |address: &str, amount: &str| {
+    let proof = app_state.tree.get_proof(address_key(address)).unwrap();
+    VerifyProofQuery {
+      address: address.to_string(),
+      amount: amount.to_string(),
+      proof: proof
+        .proof
+        .iter()
+        .map(|h| format!("0x{}", hex::encode(h)))
+        .collect(),
+      salt: None,
+    }
+  };
+  let malformed = VerifyProofQuery {
+    address: ALICE.to_string(),
+    amount: "100".to_string(),
+    proof: vec!["0x1234".to_string()],
+    salt: None,
+  };
+  let unknown = VerifyProofQuery {
+    address: CHARLIE.to_string(),
+    amount: "100".to_string(),
+    proof: vec![],
+    salt: None,
+  };
+
+  let req = test::TestRequest::post()
+    .uri("/verify/batch")
+    .set_json(VerifyBatchQuery {
+      proofs: vec![
+        query(ALICE, "100"),
+        query(BOB, "200"),
+        query(BOB, "999"),
+        malformed,
+        unknown,
+      ],
+    })
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+
+  assert!(resp.status().is_success());
+
+  let body = test::read_body(resp).await;
+  let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+  assert_eq!(
+    json["results"],
+    serde_json::json!([true, true, false, false, false])
+  );
+}
+
+#[actix_rt::test]
+async fn test_salted_proofs() {
+  let mut data = vec![
+    DistributionEntry {
+      address: ALICE.to_string(),
+      amount: "100".to_string(),
+      salt: None,
+    },
+    DistributionEntry {
+      address: BOB.to_string(),
+      amount: "200".to_string(),
+      salt: None,
+    },
+  ];
+  apply_salts(&mut data, b"operator seed");
+  let salt = format!("0x{}", hex::encode(data[0].salt.unwrap()));
+  let tree = IndexedMerkleTree::<DistributionEntry, KeccakHasher>::new(data, KeccakHasher);
+  let app_state = web::Data::new(Arc::new(AppState {
+    tree: DistributionTree::Full(tree),
+    total_amount: "300".to_string(),
+    envelope: None,
+    expose_leaf_data: false,
+  }));
+  let app = test::init_service(
+    App::new()
+      .app_data(app_state.clone())
+      .service(get_proof)
+      .service(verify_proof),
+  )
+  .await;
+
+  // the salt is only revealed in the address's own proof
+  let req = test::TestRequest::get()
+    .uri(&format!("/proof?address={}", ALICE))
+    .to_request();
+  let json: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+  assert_eq!(json["salt"], salt);
+  let proof: Vec<String> = serde_json::from_value(json["proof"].clone()).unwrap();
+
+  let req = test::TestRequest::get()
+    .uri(&format!("/proof?address={}", BOB))
+    .insert_header(("Accept", "application/octet-stream"))
+    .to_request();
+  let resp = test::call_service(&app, req).await;
+  assert!(resp.headers().contains_key("x-salt"));
+
+  // the leaf can't be recomputed for a guessed amount without the salt
+  for (salt, valid) in [(None, false), (Some(salt), true)] {
+    let req = test::TestRequest::post()
+      .uri("/verify")
+      .set_json(VerifyProofQuery {
+        address: ALICE.to_string(),
+        amount: "100".to_string(),
+        proof: proof.clone(),
+        salt,
+      })
+      .to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status().is_success(), valid);
+  }
+}
+
+#[actix_rt::test]
+async fn test_decode_arbitrary_entry_bytes() {
+  let valid = DistributionEntry {
+    address: ALICE.to_string(),
+    amount: "100".to_string(),
+    salt: None,
+  }
+  .to_bytes();
+
+  // seeded so failures can be replayed
+  let mut rng = fastrand::Rng::with_seed(0x9e37_79b9_7f4a_7c15);
+
+  for _ in 0..5_000 {
+    let len = rng.usize(..64);
+    let random: Vec<u8> = (0..len).map(|_| rng.u8(..)).collect();
+    let _ = DistributionEntry::from_bytes(random);
+
+    let mut mutated = valid.clone();
+    let i = rng.usize(..mutated.len());
+    mutated[i] = rng.u8(..);
+    mutated.truncate(rng.usize(..=valid.len()));
+    if let Ok(entry) = DistributionEntry::from_bytes(mutated.clone()) {
+      assert_eq!(entry.to_bytes(), mutated);
+    }
+  }
+}
+
+#[actix_rt::test]
+async fn test_streamed_build_matches_merged_entries() {
+  // the api's ordering and merge reproduce the tree of `merge_entries` without holding the data
+  let entries: Vec<DistributionEntry> = (0..40)
+    .map(|i| DistributionEntry {
+      address: format!("0x{:040x}", (i * 7) % 25 + 1),
+      amount: (i * 10).to_string(),
+      salt: None,
+    })
+    .collect();
+
+  let (merged, _) = merge_entries(entries.clone()).unwrap();
+  let tree = IndexedMerkleTree::<DistributionEntry, KeccakHasher>::with_options(
+    merged,
+    KeccakHasher,
+    TreeOptions {
+      ordering: address_ordering(),
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  let mut expected = Vec::new();
+  tree.save_snapshot(&mut expected).unwrap();
+
+  let mut bytes = Vec::new();
+  let summary = build_snapshot(
+    entries,
+    &KeccakHasher,
+    &mut bytes,
+    StreamOptions {
+      run_len: 6,
+      fan_in: 3,
+      ordering: address_ordering(),
+      merge: add_amounts,
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  assert_eq!(summary.root, tree.root.hash);
+  assert_eq!(bytes, expected);
+}
+
+#[actix_rt::test]
+async fn test_merge_rejects_invalid_entries() {
+  let entry = |amount: &str| DistributionEntry {
+    address: format!("0x{:040x}", 1),
+    amount: amount.to_string(),
+    salt: None,
+  };
+
+  let err = merge_entries(vec![entry("10"), entry("1.5")]).unwrap_err();
+  assert_eq!(err.reason, "invalid amount");
+  assert_eq!(err.entry.amount, "1.5");
+
+  // the total, and with it every merged amount, must fit a uint256
+  let max = U256::MAX.to_string();
+  let err = merge_entries(vec![entry(&max), entry("1")]).unwrap_err();
+  assert_eq!(err.reason, "total amount overflows");
+  let (merged, total) = merge_entries(vec![entry(&max), entry("0")]).unwrap();
+  assert_eq!(merged[0].amount, max);
+  assert_eq!(total, max);
+}
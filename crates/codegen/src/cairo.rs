@@ -0,0 +1,306 @@
+// Local imports
+use crate::render;
+use indexed_merkle_tree::{
+  errors::{DecodeError, MerkleError},
+  hasher::{Digest, Hasher, KeccakHasher, PairOrder, StarknetHasher},
+  node::SerializableData,
+  proof::MerkleProof,
+  salt::derive_salt,
+};
+
+// Core lib imports
+use std::path::Path;
+
+// External imports
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+// deepest positional tree a contract can verify, as claim indices are u64
+pub const MAX_DEPTH: usize = 64;
+
+// Tree and token the generated Starknet contract pays out claims of. Trees are hashed with
+// `StarknetHasher` over `StarknetClaim` leaves.
+#[derive(Debug, Clone)]
+pub struct PackageOptions {
+  // Scarb package name
+  pub name: String,
+  pub order: PairOrder,
+  pub salted: bool,
+  // proof length of a fixed-depth tree, checked by the contract
+  pub depth: Option<usize>,
+  // ERC-20 contract address, a felt252 in big-endian
+  pub token: [u8; 32],
+}
+
+impl Default for PackageOptions {
+  fn default() -> Self {
+    Self {
+      name: "merkle_distributor".to_string(),
+      order: PairOrder::Positional,
+      salted: false,
+      depth: None,
+      token: [0; 32],
+    }
+  }
+}
+
+// Claim leaf serialized as the Cairo felts `[account, amount.low, amount.high]`, followed by the
+// salt in salted trees, each felt taking a 32-byte big-endian word
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarknetClaim {
+  pub account: [u8; 32],
+  pub amount: U256,
+  pub salt: Option<[u8; 32]>,
+}
+
+impl StarknetClaim {
+  // Cairo serialization of the leaf, as passed in calldata
+  pub fn felts(&self) -> Vec<[u8; 32]> {
+    let mut felts = vec![
+      self.account,
+      low_word(self.amount),
+      low_word(self.amount >> 128),
+    ];
+    felts.extend(self.salt);
+    felts
+  }
+}
+
+impl SerializableData for StarknetClaim {
+  fn to_bytes(&self) -> Vec<u8> {
+    self.felts().concat()
+  }
+
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError> {
+    if bytes.len() != 96 && bytes.len() != 128 {
+      return Err(DecodeError {
+        offset: bytes.len().min(128),
+        reason: "invalid claim length",
+      });
+    }
+    // amount halves are u128
+    for offset in [32, 64] {
+      if bytes[offset..offset + 16].iter().any(|byte| *byte != 0) {
+        return Err(DecodeError {
+          offset,
+          reason: "amount word out of range",
+        });
+      }
+    }
+
+    Ok(StarknetClaim {
+      account: bytes[..32].try_into().unwrap(),
+      amount: (U256::from_big_endian(&bytes[64..96]) << 128)
+        + U256::from_big_endian(&bytes[32..64]),
+      salt: (bytes.len() == 128).then(|| bytes[96..].try_into().unwrap()),
+    })
+  }
+
+  fn key(&self) -> [u8; 32] {
+    KeccakHasher.hash_leaf(&self.account)
+  }
+}
+
+// salt of a claim derived from the operator's seed as by `derive_salt`, keeping its low 251 bits so
+// that it is a felt252: 2 ** 251 is below the Stark prime, while full 256-bit salts are out of
+// range most of the time
+pub fn derive_felt_salt(seed: &[u8], key: &[u8; 32]) -> [u8; 32] {
+  let mut salt = derive_salt(seed, key);
+  salt[0] &= 0x07;
+  salt
+}
+
+fn low_word(value: U256) -> [u8; 32] {
+  let mut word = [0; 32];
+  word[16..].copy_from_slice(&value.low_u128().to_be_bytes());
+  word
+}
+
+// felts are below the Stark prime
+fn check_felt(felt: &[u8; 32]) -> Result<(), MerkleError> {
+  match StarknetHasher::felt(felt).to_bytes_be() == *felt {
+    true => Ok(()),
+    false => Err(MerkleError::Decode {
+      err: DecodeError {
+        offset: 0,
+        reason: "felt out of range",
+      },
+    }),
+  }
+}
+
+// Scarb package of a Starknet `MerkleDistributor`, with the proof fixtures of the tree it was
+// generated for
+#[derive(Debug, Clone)]
+pub struct CairoPackage {
+  // paths relative to the package root and their contents
+  pub files: Vec<(String, String)>,
+  pub fixtures: Fixtures,
+}
+
+impl CairoPackage {
+  pub fn new(
+    options: &PackageOptions,
+    root: [u8; 32],
+    proofs: &[MerkleProof<StarknetClaim>],
+  ) -> Result<Self, MerkleError> {
+    let fixtures = Fixtures::new(options, root, proofs)?;
+    let files = vec![
+      ("Scarb.toml".to_string(), scarb_toml(options)),
+      ("src/lib.cairo".to_string(), generate(options)?),
+      ("fixtures.json".to_string(), fixtures.to_json()),
+    ];
+    Ok(Self { files, fixtures })
+  }
+
+  pub fn write_to(&self, dir: &Path) -> Result<(), MerkleError> {
+    for (path, contents) in &self.files {
+      let path = dir.join(path);
+      if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+      }
+      std::fs::write(path, contents)?;
+    }
+    Ok(())
+  }
+}
+
+pub fn scarb_toml(options: &PackageOptions) -> String {
+  render(
+    include_str!("../templates/Scarb.toml"),
+    &[("name", &options.name)],
+  )
+}
+
+// Cairo 1 contract paying out the token to the claims of the tree committed to by the root given
+// at deployment. Positional trees track claims by leaf index, sorted trees don't bind the index so
+// they track the claimed leaf hashes instead.
+pub fn generate(options: &PackageOptions) -> Result<String, MerkleError> {
+  let positional = options.order == PairOrder::Positional;
+  if let Some(depth) = options.depth {
+    // the index of a positional tree must fit a u64
+    if depth == 0 || (positional && depth > MAX_DEPTH) {
+      return Err(MerkleError::DepthExceeded {
+        depth,
+        max: MAX_DEPTH,
+      });
+    }
+  }
+  check_felt(&options.token)?;
+
+  let template = match positional {
+    true => include_str!("../templates/positional.cairo"),
+    false => include_str!("../templates/sorted.cairo"),
+  };
+  let (salt_param, salt_arg) = match options.salted {
+    true => (", salt: felt252", ", salt"),
+    false => ("", ""),
+  };
+  let depth = options
+    .depth
+    .map_or("variable".to_string(), |depth| depth.to_string());
+  let (depth_constant, depth_check) = match options.depth {
+    Some(depth) => (
+      format!("    const DEPTH: u32 = {};\n", depth),
+      "        if proof.len() != DEPTH {\n            return false;\n        }\n",
+    ),
+    None => (String::new(), ""),
+  };
+  Ok(render(
+    template,
+    &[
+      ("token", &hex::encode(options.token)),
+      ("depth", &depth),
+      ("depth_constant", &depth_constant),
+      ("depth_check", depth_check),
+      ("salt_param", salt_param),
+      ("salt_arg", salt_arg),
+    ],
+  ))
+}
+
+// Claims of a tree with their proofs and the calldata to submit them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixtures {
+  pub order: String,
+  pub salted: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub depth: Option<usize>,
+  pub token: String,
+  pub root: String,
+  pub constructor_calldata: Vec<String>,
+  pub claims: Vec<Fixture>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+  pub index: usize,
+  pub account: String,
+  pub amount: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub salt: Option<String>,
+  // expected `leaf_hash`
+  pub leaf: String,
+  pub proof: Vec<String>,
+  // felts of the `claim` call
+  pub calldata: Vec<String>,
+}
+
+impl Fixtures {
+  pub fn new(
+    options: &PackageOptions,
+    root: [u8; 32],
+    proofs: &[MerkleProof<StarknetClaim>],
+  ) -> Result<Self, MerkleError> {
+    let positional = options.order == PairOrder::Positional;
+    let mut claims = Vec::with_capacity(proofs.len());
+    for proof in proofs {
+      let claim = &proof.data;
+      check_felt(&claim.account)?;
+      if let Some(salt) = &claim.salt {
+        check_felt(salt)?;
+      }
+      if claim.salt.is_some() != options.salted {
+        return Err(MerkleError::InvalidDataLength {
+          len: claim.to_bytes().len(),
+        });
+      }
+
+      let mut calldata = Vec::new();
+      if positional {
+        calldata.push(format!("{:#x}", proof.index));
+      }
+      calldata.extend(claim.felts().iter().map(Digest::to_hex));
+      calldata.push(format!("{:#x}", proof.proof.len()));
+      calldata.extend(proof.proof.iter().map(Digest::to_hex));
+
+      claims.push(Fixture {
+        index: proof.index,
+        account: claim.account.to_hex(),
+        amount: claim.amount.to_string(),
+        salt: claim.salt.as_ref().map(Digest::to_hex),
+        leaf: StarknetHasher.hash_leaf(&claim.to_bytes()).to_hex(),
+        proof: proof.proof.iter().map(Digest::to_hex).collect(),
+        calldata,
+      });
+    }
+
+    Ok(Self {
+      order: match options.order {
+        PairOrder::Positional => "positional",
+        PairOrder::Sorted => "sorted",
+      }
+      .to_string(),
+      salted: options.salted,
+      depth: options.depth,
+      token: options.token.to_hex(),
+      root: root.to_hex(),
+      constructor_calldata: vec![root.to_hex()],
+      claims,
+    })
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap()
+  }
+}
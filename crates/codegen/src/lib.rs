@@ -0,0 +1,12 @@
+pub mod cairo;
+pub mod solidity;
+pub mod witness;
+
+// fill the `{{name}}` placeholders of a contract template
+pub(crate) fn render(template: &str, values: &[(&str, &str)]) -> String {
+  let mut out = template.to_string();
+  for (name, value) in values {
+    out = out.replace(&format!("{{{{{}}}}}", name), value);
+  }
+  out
+}
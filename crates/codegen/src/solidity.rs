@@ -0,0 +1,199 @@
+// Local imports
+use crate::render;
+pub use distribution_entry::{Claim, LeafEncoding};
+use indexed_merkle_tree::{
+  errors::MerkleError,
+  hasher::{Digest, Hasher, KeccakHasher, PairOrder, Ripemd160Hasher},
+  node::SerializableData,
+  proof::MerkleProof,
+};
+
+// External imports
+use serde::{Deserialize, Serialize};
+
+// deepest fixed-depth tree a contract can verify, so that `2 ** DEPTH` fits a uint256
+pub const MAX_DEPTH: usize = 255;
+
+// Hash function of the tree, limited to those the EVM computes natively
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContractHasher {
+  Keccak256,
+  Ripemd160,
+}
+
+impl ContractHasher {
+  pub fn name(&self) -> &'static str {
+    match self {
+      ContractHasher::Keccak256 => "keccak256",
+      ContractHasher::Ripemd160 => "ripemd160",
+    }
+  }
+
+  // Solidity type of the digests
+  fn digest_type(&self) -> &'static str {
+    match self {
+      ContractHasher::Keccak256 => "bytes32",
+      ContractHasher::Ripemd160 => "bytes20",
+    }
+  }
+
+  pub fn hash_leaf(&self, bytes: &[u8]) -> Vec<u8> {
+    match self {
+      ContractHasher::Keccak256 => KeccakHasher.hash_leaf(bytes).to_vec(),
+      ContractHasher::Ripemd160 => Ripemd160Hasher.hash_leaf(bytes).to_vec(),
+    }
+  }
+}
+
+// Tree the generated contract verifies proofs of
+#[derive(Debug, Clone)]
+pub struct ContractOptions {
+  pub name: String,
+  pub hasher: ContractHasher,
+  // `Sorted` contracts hash leaves once, so they verify sorted `KeccakHasher` trees but not
+  // OpenZeppelin trees of `StandardHasher`, which hashes leaves twice
+  pub order: PairOrder,
+  pub leaf: LeafEncoding,
+  pub salted: bool,
+  // proof length of a fixed-depth tree, taken as a static array
+  pub depth: Option<usize>,
+}
+
+impl Default for ContractOptions {
+  fn default() -> Self {
+    Self {
+      name: "MerkleDistributor".to_string(),
+      hasher: ContractHasher::Keccak256,
+      order: PairOrder::Positional,
+      leaf: LeafEncoding::Abi,
+      salted: false,
+      depth: None,
+    }
+  }
+}
+
+// Solidity `MerkleDistributor` paying out `token` to the claims of the tree committed to by the
+// root given at deployment. Positional trees track claims in a bitmap by leaf index, sorted trees
+// don't bind the index so they track the claimed leaf hashes instead.
+pub fn generate(options: &ContractOptions) -> Result<String, MerkleError> {
+  if let Some(depth) = options.depth {
+    // fixed depths run from 1 to `MAX_DEPTH`
+    if depth == 0 || depth > MAX_DEPTH {
+      return Err(MerkleError::DepthExceeded {
+        depth,
+        max: MAX_DEPTH,
+      });
+    }
+  }
+
+  let digest = options.hasher.digest_type();
+  let hash = options.hasher.name();
+  let positional = options.order == PairOrder::Positional;
+  let proof_type = match options.depth {
+    Some(depth) => format!("{}[{}] calldata", digest, depth),
+    None => format!("{}[] calldata", digest),
+  };
+  let (salt_param, salt_arg) = match options.salted {
+    true => (", bytes32 salt", ", salt"),
+    false => ("", ""),
+  };
+  let encode = match options.leaf {
+    LeafEncoding::Abi => "abi.encode",
+    LeafEncoding::Packed => "abi.encodePacked",
+  };
+
+  let template = match positional {
+    true => include_str!("../templates/positional.sol"),
+    false => include_str!("../templates/sorted.sol"),
+  };
+  let depth = options
+    .depth
+    .map_or("variable".to_string(), |depth| depth.to_string());
+  let depth_constant = options.depth.map_or(String::new(), |depth| {
+    format!("  uint256 public constant DEPTH = {};\n", depth)
+  });
+  Ok(render(
+    template,
+    &[
+      ("name", &options.name),
+      ("hash", hash),
+      ("digest", digest),
+      ("encode", encode),
+      ("depth", &depth),
+      ("depth_constant", &depth_constant),
+      ("proof_type", &proof_type),
+      ("salt_param", salt_param),
+      ("salt_arg", salt_arg),
+    ],
+  ))
+}
+
+// Claims of a tree with their proofs, for testing a generated contract against real proofs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestVectors {
+  pub hasher: String,
+  pub order: String,
+  pub leaf: String,
+  pub salted: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub depth: Option<usize>,
+  pub root: String,
+  pub claims: Vec<TestVector>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestVector {
+  // leaf index, passed to `claim` in positional trees
+  pub index: usize,
+  pub account: String,
+  pub amount: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub salt: Option<String>,
+  // expected `leafHash`
+  pub leaf: String,
+  pub proof: Vec<String>,
+}
+
+impl TestVectors {
+  pub fn new<G: Digest>(
+    options: &ContractOptions,
+    root: G,
+    proofs: &[MerkleProof<Claim, G>],
+  ) -> Self {
+    let claims = proofs
+      .iter()
+      .map(|proof| TestVector {
+        index: proof.index,
+        account: format!("0x{}", hex::encode(proof.data.account)),
+        amount: proof.data.amount.to_string(),
+        salt: proof
+          .data
+          .salt
+          .map(|salt| format!("0x{}", hex::encode(salt))),
+        leaf: format!(
+          "0x{}",
+          hex::encode(options.hasher.hash_leaf(&proof.data.to_bytes()))
+        ),
+        proof: proof.proof.iter().map(Digest::to_hex).collect(),
+      })
+      .collect();
+
+    Self {
+      hasher: options.hasher.name().to_string(),
+      order: match options.order {
+        PairOrder::Positional => "positional",
+        PairOrder::Sorted => "sorted",
+      }
+      .to_string(),
+      leaf: options.leaf.name().to_string(),
+      salted: options.salted,
+      depth: options.depth,
+      root: root.to_hex(),
+      claims,
+    }
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap()
+  }
+}
@@ -0,0 +1,145 @@
+// Local imports
+use indexed_merkle_tree::{
+  errors::{DecodeError, MerkleError},
+  hasher::{Bn254PoseidonHasher, POSEIDON_LEAF_FIELDS},
+  node::SerializableData,
+  proof::MerkleProof,
+  store::NodeStore,
+  tree::IndexedMerkleTree,
+  verify::compute_root,
+};
+
+// Core lib imports
+use std::fmt::Write;
+
+// External imports
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+// Private input of a Merkle membership circuit over a tree hashed with `Bn254PoseidonHasher`, field
+// elements written in decimal. Path indices are the bits of the leaf index from the bottom up, 1
+// when the sibling is on the left.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Witness {
+  // leaf bytes as 32-byte words, hashed with `Bn254PoseidonHasher::hash_fields`. Zero-padded to a
+  // multiple of `POSEIDON_LEAF_FIELDS` as the hasher pads them, so a circuit takes a fixed width.
+  pub leaf: Vec<String>,
+  #[serde(rename = "pathElements")]
+  pub path_elements: Vec<String>,
+  #[serde(rename = "pathIndices")]
+  pub path_indices: Vec<String>,
+  pub root: String,
+}
+
+impl Witness {
+  pub fn new<D: SerializableData>(proof: &MerkleProof<D>, root: [u8; 32]) -> Self {
+    let mut leaf: Vec<String> = proof
+      .data
+      .to_bytes()
+      .chunks(32)
+      .map(|word| decimal(&Bn254PoseidonHasher::field(word)))
+      .collect();
+    let width = leaf.len().div_ceil(POSEIDON_LEAF_FIELDS).max(1) * POSEIDON_LEAF_FIELDS;
+    leaf.resize(width, "0".to_string());
+    Self {
+      leaf,
+      path_elements: proof.proof.iter().map(decimal).collect(),
+      path_indices: (0..proof.proof.len())
+        .map(|level| ((proof.index >> level) & 1).to_string())
+        .collect(),
+      root: decimal(&root),
+    }
+  }
+
+  // Witness of the leaf with the given key. Path indices are one bit per level, so the tree must
+  // be binary. Its pairs are positional, as `IndexedMerkleTree` never sorts them.
+  pub fn from_tree<D: SerializableData, S: NodeStore<D, [u8; 32]>>(
+    tree: &IndexedMerkleTree<D, Bn254PoseidonHasher, S>,
+    key: [u8; 32],
+  ) -> Result<Self, MerkleError> {
+    if tree.arity != 2 {
+      return Err(MerkleError::InvalidArity { arity: tree.arity });
+    }
+    let proof = tree.get_proof(key)?;
+    Ok(Self::new(&proof, tree.root.hash))
+  }
+
+  // Circom `input.json`
+  pub fn to_circom_json(&self) -> String {
+    serde_json::to_string_pretty(self).unwrap()
+  }
+
+  // Noir `Prover.toml`, with snake case names
+  pub fn to_prover_toml(&self) -> String {
+    let list = |values: &[String]| {
+      let values: Vec<String> = values
+        .iter()
+        .map(|value| format!("\"{}\"", value))
+        .collect();
+      format!("[{}]", values.join(", "))
+    };
+    let mut out = String::new();
+    writeln!(out, "leaf = {}", list(&self.leaf)).unwrap();
+    writeln!(out, "path_elements = {}", list(&self.path_elements)).unwrap();
+    writeln!(out, "path_indices = {}", list(&self.path_indices)).unwrap();
+    writeln!(out, "root = \"{}\"", self.root).unwrap();
+    out
+  }
+
+  // root reached from the witness fields, as the circuit computes it
+  pub fn compute_root(&self) -> Result<[u8; 32], MerkleError> {
+    let leaf: Vec<[u8; 32]> = self
+      .leaf
+      .iter()
+      .map(|value| parse_field(value))
+      .collect::<Result<_, _>>()?;
+    let path: Vec<[u8; 32]> = self
+      .path_elements
+      .iter()
+      .map(|value| parse_field(value))
+      .collect::<Result<_, _>>()?;
+    if self.path_indices.len() != path.len() || path.len() > usize::BITS as usize {
+      return Err(MerkleError::InvalidProofEncoding {
+        offset: self.path_indices.len(),
+      });
+    }
+
+    let mut index = 0;
+    for (level, bit) in self.path_indices.iter().enumerate() {
+      match bit.as_str() {
+        "0" => {}
+        "1" => index |= 1 << level,
+        _ => return Err(MerkleError::InvalidProofEncoding { offset: level }),
+      }
+    }
+    let leaf = Bn254PoseidonHasher.hash_fields(&leaf);
+    Ok(compute_root(&Bn254PoseidonHasher, leaf, index, &path))
+  }
+
+  // check the witness against its root
+  pub fn verify(&self) -> Result<bool, MerkleError> {
+    Ok(self.compute_root()? == parse_field(&self.root)?)
+  }
+}
+
+fn decimal(field: &[u8; 32]) -> String {
+  U256::from_big_endian(field).to_string()
+}
+
+// decimal field element, below the field order
+fn parse_field(value: &str) -> Result<[u8; 32], MerkleError> {
+  let invalid = || MerkleError::Decode {
+    err: DecodeError {
+      offset: 0,
+      reason: "invalid field element",
+    },
+  };
+  let mut field = [0; 32];
+  U256::from_dec_str(value)
+    .map_err(|_| invalid())?
+    .to_big_endian(&mut field);
+  match Bn254PoseidonHasher::field(&field) == field {
+    true => Ok(field),
+    false => Err(invalid()),
+  }
+}
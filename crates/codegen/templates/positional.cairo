@@ -0,0 +1,108 @@
+// Generated by the `codegen` crate, do not edit.
+// hasher: poseidon, pair order: positional, leaf: poseidon_hash_span([account, amount.low, amount.high{{salt_arg}}]), depth: {{depth}}
+
+use starknet::ContractAddress;
+
+#[starknet::interface]
+pub trait IERC20<TContractState> {
+    fn transfer(ref self: TContractState, recipient: ContractAddress, amount: u256) -> bool;
+}
+
+#[starknet::interface]
+pub trait IMerkleDistributor<TContractState> {
+    fn claim(ref self: TContractState, index: u64, account: ContractAddress, amount: u256{{salt_param}}, proof: Span<felt252>);
+    fn is_claimed(self: @TContractState, index: u64) -> bool;
+    fn leaf_hash(self: @TContractState, account: ContractAddress, amount: u256{{salt_param}}) -> felt252;
+    fn merkle_root(self: @TContractState) -> felt252;
+    fn token(self: @TContractState) -> ContractAddress;
+}
+
+#[starknet::contract]
+pub mod MerkleDistributor {
+    use core::poseidon::{hades_permutation, poseidon_hash_span};
+    use starknet::ContractAddress;
+    use starknet::storage::{
+        Map, StorageMapReadAccess, StorageMapWriteAccess, StoragePointerReadAccess,
+        StoragePointerWriteAccess,
+    };
+    use super::{IERC20Dispatcher, IERC20DispatcherTrait};
+
+    const TOKEN: felt252 = 0x{{token}};
+{{depth_constant}}
+    #[storage]
+    struct Storage {
+        merkle_root: felt252,
+        claimed: Map<u64, bool>,
+    }
+
+    #[event]
+    #[derive(Drop, starknet::Event)]
+    pub enum Event {
+        Claimed: Claimed,
+    }
+
+    #[derive(Drop, starknet::Event)]
+    pub struct Claimed {
+        pub index: u64,
+        pub account: ContractAddress,
+        pub amount: u256,
+    }
+
+    #[constructor]
+    fn constructor(ref self: ContractState, merkle_root: felt252) {
+        self.merkle_root.write(merkle_root);
+    }
+
+    #[abi(embed_v0)]
+    impl MerkleDistributorImpl of super::IMerkleDistributor<ContractState> {
+        fn claim(ref self: ContractState, index: u64, account: ContractAddress, amount: u256{{salt_param}}, proof: Span<felt252>) {
+            let leaf = hash_leaf(account, amount{{salt_arg}});
+            assert(!self.claimed.read(index), 'Already claimed');
+            assert(verify(proof, index, leaf, self.merkle_root.read()), 'Invalid proof');
+            self.claimed.write(index, true);
+            let token = IERC20Dispatcher { contract_address: TOKEN.try_into().unwrap() };
+            assert(token.transfer(account, amount), 'Transfer failed');
+            self.emit(Claimed { index, account, amount });
+        }
+
+        fn is_claimed(self: @ContractState, index: u64) -> bool {
+            self.claimed.read(index)
+        }
+
+        fn leaf_hash(self: @ContractState, account: ContractAddress, amount: u256{{salt_param}}) -> felt252 {
+            hash_leaf(account, amount{{salt_arg}})
+        }
+
+        fn merkle_root(self: @ContractState) -> felt252 {
+            self.merkle_root.read()
+        }
+
+        fn token(self: @ContractState) -> ContractAddress {
+            TOKEN.try_into().unwrap()
+        }
+    }
+
+    fn verify(mut proof: Span<felt252>, mut index: u64, leaf: felt252, root: felt252) -> bool {
+{{depth_check}}        let mut hash = leaf;
+        loop {
+            match proof.pop_front() {
+                Option::Some(sibling) => {
+                    hash = if index % 2 == 0 { hash_pair(hash, *sibling) } else { hash_pair(*sibling, hash) };
+                    index /= 2;
+                },
+                Option::None => { break; },
+            };
+        };
+        // index bits above the proof length pick no sibling, so each leaf has a single index
+        index == 0 && hash == root
+    }
+
+    fn hash_leaf(account: ContractAddress, amount: u256{{salt_param}}) -> felt252 {
+        poseidon_hash_span(array![account.into(), amount.low.into(), amount.high.into(){{salt_arg}}].span())
+    }
+
+    fn hash_pair(left: felt252, right: felt252) -> felt252 {
+        let (hash, _, _) = hades_permutation(left, right, 2);
+        hash
+    }
+}
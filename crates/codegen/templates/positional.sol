@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: MIT
+// Generated by the `codegen` crate, do not edit.
+// hasher: {{hash}}, pair order: positional, leaf: {{encode}}(account, amount{{salt_arg}}), depth: {{depth}}
+pragma solidity ^0.8.20;
+
+interface IERC20 {
+  function transfer(address to, uint256 amount) external returns (bool);
+}
+
+contract {{name}} {
+  address public immutable token;
+  {{digest}} public immutable merkleRoot;
+{{depth_constant}}  mapping(uint256 => uint256) private claimedBitMap;
+
+  event Claimed(uint256 index, address account, uint256 amount);
+
+  error AlreadyClaimed();
+  error InvalidProof();
+  error TransferFailed();
+
+  constructor(address token_, {{digest}} merkleRoot_) {
+    token = token_;
+    merkleRoot = merkleRoot_;
+  }
+
+  function isClaimed(uint256 index) public view returns (bool) {
+    uint256 mask = uint256(1) << (index % 256);
+    return claimedBitMap[index / 256] & mask == mask;
+  }
+
+  function claim(uint256 index, address account, uint256 amount{{salt_param}}, {{proof_type}} proof) external {
+    if (isClaimed(index)) revert AlreadyClaimed();
+    if (!verify(proof, index, leafHash(account, amount{{salt_arg}}))) revert InvalidProof();
+    claimedBitMap[index / 256] |= uint256(1) << (index % 256);
+    if (!IERC20(token).transfer(account, amount)) revert TransferFailed();
+    emit Claimed(index, account, amount);
+  }
+
+  function leafHash(address account, uint256 amount{{salt_param}}) public pure returns ({{digest}}) {
+    return {{hash}}({{encode}}(account, amount{{salt_arg}}));
+  }
+
+  function verify({{proof_type}} proof, uint256 index, {{digest}} leaf) public view returns (bool) {
+    // index bits above the proof length pick no sibling, so each leaf has a single index
+    if (index >> proof.length != 0) return false;
+    {{digest}} hash = leaf;
+    for (uint256 i = 0; i < proof.length; i++) {
+      hash = (index >> i) & 1 == 0 ? hashPair(hash, proof[i]) : hashPair(proof[i], hash);
+    }
+    return hash == merkleRoot;
+  }
+
+  function hashPair({{digest}} left, {{digest}} right) private pure returns ({{digest}}) {
+    return {{hash}}(abi.encodePacked(left, right));
+  }
+}
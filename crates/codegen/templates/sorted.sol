@@ -0,0 +1,50 @@
+// SPDX-License-Identifier: MIT
+// Generated by the `codegen` crate, do not edit.
+// hasher: {{hash}}, pair order: sorted, leaf: {{encode}}(account, amount{{salt_arg}}), depth: {{depth}}
+pragma solidity ^0.8.20;
+
+interface IERC20 {
+  function transfer(address to, uint256 amount) external returns (bool);
+}
+
+contract {{name}} {
+  address public immutable token;
+  {{digest}} public immutable merkleRoot;
+{{depth_constant}}  mapping({{digest}} => bool) public claimed;
+
+  event Claimed(address account, uint256 amount);
+
+  error AlreadyClaimed();
+  error InvalidProof();
+  error TransferFailed();
+
+  constructor(address token_, {{digest}} merkleRoot_) {
+    token = token_;
+    merkleRoot = merkleRoot_;
+  }
+
+  function claim(address account, uint256 amount{{salt_param}}, {{proof_type}} proof) external {
+    {{digest}} leaf = leafHash(account, amount{{salt_arg}});
+    if (claimed[leaf]) revert AlreadyClaimed();
+    if (!verify(proof, leaf)) revert InvalidProof();
+    claimed[leaf] = true;
+    if (!IERC20(token).transfer(account, amount)) revert TransferFailed();
+    emit Claimed(account, amount);
+  }
+
+  function leafHash(address account, uint256 amount{{salt_param}}) public pure returns ({{digest}}) {
+    return {{hash}}({{encode}}(account, amount{{salt_arg}}));
+  }
+
+  function verify({{proof_type}} proof, {{digest}} leaf) public view returns (bool) {
+    {{digest}} hash = leaf;
+    for (uint256 i = 0; i < proof.length; i++) {
+      hash = proof[i] < hash ? hashPair(proof[i], hash) : hashPair(hash, proof[i]);
+    }
+    return hash == merkleRoot;
+  }
+
+  function hashPair({{digest}} left, {{digest}} right) private pure returns ({{digest}}) {
+    return {{hash}}(abi.encodePacked(left, right));
+  }
+}
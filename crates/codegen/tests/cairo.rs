@@ -0,0 +1,277 @@
+use codegen::cairo::{derive_felt_salt, generate, CairoPackage, PackageOptions, StarknetClaim};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher, PairOrder, StarknetHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+use indexed_merkle_tree::verify::{verify, verify_sorted};
+use indexed_merkle_tree::weighted::WeightedMerkleTree;
+use primitive_types::U256;
+
+const TOKEN: &str = "0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+
+fn test_claims(salted: bool) -> Vec<StarknetClaim> {
+  (0..5u8)
+    .map(|i| {
+      let mut account = [0; 32];
+      account[1..].fill(i + 1);
+      StarknetClaim {
+        account,
+        // amounts spanning both u128 halves
+        amount: (U256::from(i) << 128) + U256::from(1000 * (i as u64 + 1)),
+        salt: salted.then(|| derive_felt_salt(b"seed", &KeccakHasher.hash_leaf(&account))),
+      }
+    })
+    .collect()
+}
+
+fn test_package(options: &PackageOptions) -> CairoPackage {
+  let claims = test_claims(options.salted);
+  let (root, proofs) = match options.order {
+    PairOrder::Positional => {
+      let tree_options = TreeOptions {
+        depth: options.depth,
+        ..Default::default()
+      };
+      let tree =
+        IndexedMerkleTree::with_options(claims.clone(), StarknetHasher, tree_options).unwrap();
+      let proofs: Vec<_> = claims
+        .iter()
+        .map(|claim| tree.get_proof(claim.key()).unwrap())
+        .collect();
+      (tree.root.hash, proofs)
+    }
+    PairOrder::Sorted => {
+      let data = claims.iter().map(|claim| (claim.clone(), 1.0)).collect();
+      let tree = WeightedMerkleTree::new(data, StarknetHasher, PairOrder::Sorted).unwrap();
+      let proofs: Vec<_> = claims
+        .iter()
+        .map(|claim| tree.get_proof(claim.key()).unwrap())
+        .collect();
+      (tree.root, proofs)
+    }
+  };
+  CairoPackage::new(options, root, &proofs).unwrap()
+}
+
+// calldata felts may be shorter than a word
+fn felt(hex: &str) -> [u8; 32] {
+  let mut word = [0; 32];
+  U256::from_str_radix(hex.trim_start_matches("0x"), 16)
+    .unwrap()
+    .to_big_endian(&mut word);
+  word
+}
+
+#[test]
+fn test_generate_package() {
+  for order in [PairOrder::Positional, PairOrder::Sorted] {
+    for salted in [false, true] {
+      for depth in [None, Some(3)] {
+        if order == PairOrder::Sorted && depth.is_some() {
+          continue;
+        }
+        let options = PackageOptions {
+          order,
+          salted,
+          depth,
+          token: felt(TOKEN),
+          ..Default::default()
+        };
+        let package = test_package(&options);
+        let paths: Vec<&str> = package
+          .files
+          .iter()
+          .map(|(path, _)| path.as_str())
+          .collect();
+        assert_eq!(paths, ["Scarb.toml", "src/lib.cairo", "fixtures.json"]);
+        assert!(package.files[0].1.contains("name = \"merkle_distributor\""));
+
+        let source = &package.files[1].1;
+        assert_eq!(source.matches('{').count(), source.matches('}').count());
+        assert!(source.contains(&format!("const TOKEN: felt252 = {};", TOKEN)));
+        assert!(source.contains("hades_permutation(left, right, 2)"));
+        assert_eq!(
+          source.contains("index: u64"),
+          order == PairOrder::Positional
+        );
+        assert_eq!(source.contains("salt: felt252"), salted);
+        assert_eq!(source.contains("const DEPTH: u32 = 3;"), depth.is_some());
+
+        let fixtures: codegen::cairo::Fixtures = serde_json::from_str(&package.files[2].1).unwrap();
+        assert_eq!(fixtures.claims.len(), 5);
+        assert_eq!(fixtures.constructor_calldata, vec![fixtures.root]);
+      }
+    }
+  }
+
+  let dir = tempfile::tempdir().unwrap();
+  let package = test_package(&PackageOptions::default());
+  package.write_to(dir.path()).unwrap();
+  let source = std::fs::read_to_string(dir.path().join("src/lib.cairo")).unwrap();
+  assert_eq!(source, package.files[1].1);
+}
+
+#[test]
+fn test_fixture_calldata() {
+  for (order, salted) in [
+    (PairOrder::Positional, false),
+    (PairOrder::Positional, true),
+    (PairOrder::Sorted, true),
+  ] {
+    let options = PackageOptions {
+      order,
+      salted,
+      ..Default::default()
+    };
+    let fixtures = test_package(&options).fixtures;
+    let root = felt(&fixtures.root);
+
+    for fixture in &fixtures.claims {
+      // decode the `claim` arguments as the contract does and check them against the root
+      let mut calldata: Vec<[u8; 32]> = fixture.calldata.iter().map(|hex| felt(hex)).collect();
+      let index = match order {
+        PairOrder::Positional => {
+          let index = calldata.remove(0);
+          U256::from_big_endian(&index).as_usize()
+        }
+        PairOrder::Sorted => fixture.index,
+      };
+      let leaf_len = if salted { 4 } else { 3 };
+      let proof = calldata.split_off(leaf_len + 1);
+      assert_eq!(
+        U256::from_big_endian(&calldata.pop().unwrap()).as_usize(),
+        proof.len()
+      );
+      let leaf = calldata.concat();
+
+      let claim = StarknetClaim::from_bytes(leaf.clone()).unwrap();
+      assert_eq!(claim.amount.to_string(), fixture.amount);
+      assert_eq!(claim.account, felt(&fixture.account));
+      assert_eq!(StarknetHasher.hash_leaf(&leaf), felt(&fixture.leaf));
+      let valid = match order {
+        PairOrder::Positional => verify(&StarknetHasher, &root, &leaf, index, &proof),
+        PairOrder::Sorted => verify_sorted(&StarknetHasher, &root, &leaf, &proof),
+      };
+      assert!(valid);
+      assert!(!verify_sorted(&StarknetHasher, &root, &leaf[..96], &proof));
+    }
+  }
+}
+
+#[test]
+fn test_invalid_options() {
+  // the token must be a felt252
+  let options = PackageOptions {
+    token: [0xff; 32],
+    ..Default::default()
+  };
+  assert!(matches!(
+    generate(&options),
+    Err(MerkleError::Decode { .. })
+  ));
+
+  // positional indices are u64
+  let options = PackageOptions {
+    depth: Some(65),
+    ..Default::default()
+  };
+  assert!(matches!(
+    generate(&options),
+    Err(MerkleError::DepthExceeded { max: 64, .. })
+  ));
+  let options = PackageOptions {
+    order: PairOrder::Sorted,
+    depth: Some(65),
+    ..Default::default()
+  };
+  assert!(generate(&options).is_ok());
+
+  // amount words hold u128 halves
+  let mut bytes = test_claims(false)[0].to_bytes();
+  assert_eq!(
+    StarknetClaim::from_bytes(bytes.clone()).unwrap(),
+    test_claims(false)[0]
+  );
+  bytes[32] = 1;
+  assert!(StarknetClaim::from_bytes(bytes).is_err());
+  assert!(StarknetClaim::from_bytes(vec![0; 64]).is_err());
+}
+
+#[test]
+fn test_felt_salts() {
+  // unmasked salts are out of range for about 31 in 32 keys
+  let options = PackageOptions {
+    salted: true,
+    ..Default::default()
+  };
+  let claims: Vec<StarknetClaim> = (0..64u8)
+    .map(|i| {
+      let mut account = [0; 32];
+      account[1..].fill(i);
+      StarknetClaim {
+        account,
+        amount: U256::from(i),
+        salt: Some(derive_felt_salt(b"seed", &KeccakHasher.hash_leaf(&account))),
+      }
+    })
+    .collect();
+  let tree = IndexedMerkleTree::new(claims.clone(), StarknetHasher);
+  let proofs: Vec<_> = claims
+    .iter()
+    .map(|claim| tree.get_proof(claim.key()).unwrap())
+    .collect();
+  let fixtures = CairoPackage::new(&options, tree.root.hash, &proofs)
+    .unwrap()
+    .fixtures;
+  for (fixture, claim) in fixtures.claims.iter().zip(&claims) {
+    assert_eq!(felt(fixture.salt.as_ref().unwrap()), claim.salt.unwrap());
+  }
+
+  // the same salt as `derive_salt` below the top 5 bits
+  let key = [0x11; 32];
+  let salt = indexed_merkle_tree::salt::derive_salt(b"seed", &key);
+  let felt_salt = derive_felt_salt(b"seed", &key);
+  assert_eq!(felt_salt[0], salt[0] & 0x07);
+  assert_eq!(felt_salt[1..], salt[1..]);
+}
+
+// builds the generated package with Scarb, taken from `SCARB` or the path
+#[test]
+#[ignore = "needs scarb"]
+fn test_scarb_build() {
+  let scarb = std::env::var("SCARB").unwrap_or("scarb".to_string());
+  match std::process::Command::new(&scarb).arg("--version").output() {
+    Ok(output) if output.status.success() => {}
+    _ => panic!(
+      "scarb not found at `{}`, set SCARB to build the generated packages",
+      scarb
+    ),
+  }
+
+  for (order, salted, depth) in [
+    (PairOrder::Positional, false, None),
+    (PairOrder::Positional, true, Some(3)),
+    (PairOrder::Sorted, true, None),
+  ] {
+    let options = PackageOptions {
+      order,
+      salted,
+      depth,
+      token: felt(TOKEN),
+      ..Default::default()
+    };
+    let dir = tempfile::tempdir().unwrap();
+    test_package(&options).write_to(dir.path()).unwrap();
+    let output = std::process::Command::new(&scarb)
+      .arg("build")
+      .current_dir(dir.path())
+      .output()
+      .unwrap();
+    assert!(
+      output.status.success(),
+      "scarb build failed for {:?}: {}",
+      options,
+      String::from_utf8_lossy(&output.stdout)
+    );
+  }
+}
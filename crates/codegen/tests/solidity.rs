@@ -0,0 +1,457 @@
+use codegen::solidity::{
+  generate, Claim, ContractHasher, ContractOptions, LeafEncoding, TestVector, TestVectors,
+};
+use indexed_merkle_tree::abi::{self, Token};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::{Digest, Hasher, KeccakHasher, PairOrder, Ripemd160Hasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::proof::MerkleProof;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+use indexed_merkle_tree::verify::{verify, verify_sorted};
+use indexed_merkle_tree::weighted::WeightedMerkleTree;
+use primitive_types::U256;
+use revm::db::{CacheDB, EmptyDB};
+use revm::primitives::{Address, Bytes, ExecutionResult, Output, TxKind};
+use revm::Evm;
+use std::collections::HashMap;
+use std::process::Command;
+
+// credits the claimed amounts to the accounts, standing in for an ERC-20
+const MOCK_TOKEN: &str = r#"
+contract MockToken {
+  mapping(address => uint256) public balanceOf;
+
+  function mint(address to, uint256 amount) external {
+    balanceOf[to] += amount;
+  }
+
+  function transfer(address to, uint256 amount) external returns (bool) {
+    balanceOf[msg.sender] -= amount;
+    balanceOf[to] += amount;
+    return true;
+  }
+}
+"#;
+
+fn test_claims(options: &ContractOptions, len: usize) -> Vec<Claim> {
+  (0..len)
+    .map(|i| Claim {
+      account: [i as u8 + 1; 20],
+      amount: U256::from(1000 * (i + 1)),
+      salt: options.salted.then_some([i as u8 + 0x10; 32]),
+      encoding: options.leaf,
+    })
+    .collect()
+}
+
+fn test_cases() -> Vec<ContractOptions> {
+  vec![
+    ContractOptions::default(),
+    ContractOptions {
+      leaf: LeafEncoding::Packed,
+      salted: true,
+      depth: Some(4),
+      ..Default::default()
+    },
+    ContractOptions {
+      hasher: ContractHasher::Ripemd160,
+      salted: true,
+      ..Default::default()
+    },
+    ContractOptions {
+      order: PairOrder::Sorted,
+      ..Default::default()
+    },
+    ContractOptions {
+      hasher: ContractHasher::Ripemd160,
+      order: PairOrder::Sorted,
+      leaf: LeafEncoding::Packed,
+      salted: true,
+      ..Default::default()
+    },
+  ]
+}
+
+// proofs of every claim of a tree built to match the options
+fn build<H: Hasher>(hasher: H, options: &ContractOptions, claims: Vec<Claim>) -> TestVectors {
+  let (root, proofs): (H::Digest, Vec<MerkleProof<Claim, H::Digest>>) = match options.order {
+    PairOrder::Positional => {
+      let tree_options = TreeOptions {
+        depth: options.depth,
+        ..Default::default()
+      };
+      let tree = IndexedMerkleTree::with_options(claims.clone(), hasher, tree_options).unwrap();
+      let proofs = claims
+        .iter()
+        .map(|claim| tree.get_proof(claim.key()).unwrap())
+        .collect();
+      (tree.root.hash, proofs)
+    }
+    PairOrder::Sorted => {
+      let data = claims.iter().map(|claim| (claim.clone(), 1.0)).collect();
+      let tree = WeightedMerkleTree::new(data, hasher, PairOrder::Sorted).unwrap();
+      let proofs = claims
+        .iter()
+        .map(|claim| tree.get_proof(claim.key()).unwrap())
+        .collect();
+      (tree.root, proofs)
+    }
+  };
+  TestVectors::new(options, root, &proofs)
+}
+
+fn test_vectors(options: &ContractOptions) -> TestVectors {
+  let claims = test_claims(options, 5);
+  match options.hasher {
+    ContractHasher::Keccak256 => build(KeccakHasher, options, claims),
+    ContractHasher::Ripemd160 => build(Ripemd160Hasher, options, claims),
+  }
+}
+
+fn parse_hex(hex: &str) -> Vec<u8> {
+  hex::decode(hex.trim_start_matches("0x")).unwrap()
+}
+
+// ABI word of a `bytesN` value, left aligned
+fn word(bytes: &[u8]) -> [u8; 32] {
+  let mut word = [0; 32];
+  word[..bytes.len()].copy_from_slice(bytes);
+  word
+}
+
+fn claim_of(vector: &TestVector, options: &ContractOptions) -> Claim {
+  Claim {
+    account: parse_hex(&vector.account).try_into().unwrap(),
+    amount: U256::from_dec_str(&vector.amount).unwrap(),
+    salt: vector
+      .salt
+      .as_ref()
+      .map(|salt| parse_hex(salt).try_into().unwrap()),
+    encoding: options.leaf,
+  }
+}
+
+fn verify_vector<H: Hasher>(
+  hasher: &H,
+  vectors: &TestVectors,
+  vector: &TestVector,
+  leaf: &[u8],
+) -> bool {
+  let root = H::Digest::from_hex(&vectors.root).unwrap();
+  let proof: Vec<H::Digest> = vector
+    .proof
+    .iter()
+    .map(|hash| H::Digest::from_hex(hash).unwrap())
+    .collect();
+  match vectors.order.as_str() {
+    "positional" => verify(hasher, &root, leaf, vector.index, &proof),
+    _ => verify_sorted(hasher, &root, leaf, &proof),
+  }
+}
+
+#[test]
+fn test_generate() {
+  for hasher in [ContractHasher::Keccak256, ContractHasher::Ripemd160] {
+    for order in [PairOrder::Positional, PairOrder::Sorted] {
+      for leaf in [LeafEncoding::Abi, LeafEncoding::Packed] {
+        for salted in [false, true] {
+          for depth in [None, Some(1), Some(32)] {
+            let options = ContractOptions {
+              name: "Distributor".to_string(),
+              hasher,
+              order,
+              leaf,
+              salted,
+              depth,
+            };
+            let source = generate(&options).unwrap();
+            assert!(
+              solang_parser::parse(&source, 0).is_ok(),
+              "{:?}:\n{}",
+              options,
+              source
+            );
+            assert!(source.contains("contract Distributor {"));
+            assert!(source.contains(&format!("{}(abi.encodePacked(left, right))", hasher.name())));
+            assert_eq!(
+              source.contains("uint256 index"),
+              order == PairOrder::Positional
+            );
+            assert_eq!(source.contains("bytes32 salt"), salted);
+          }
+        }
+      }
+    }
+  }
+
+  // the index of a fixed-depth tree must fit a uint256
+  for depth in [0, 256] {
+    let options = ContractOptions {
+      depth: Some(depth),
+      ..Default::default()
+    };
+    assert!(matches!(
+      generate(&options),
+      Err(MerkleError::DepthExceeded { max: 255, .. })
+    ));
+  }
+}
+
+#[test]
+fn test_vectors_verify() {
+  for options in test_cases() {
+    let vectors = test_vectors(&options);
+    let json = vectors.to_json();
+    let vectors: TestVectors = serde_json::from_str(&json).unwrap();
+    assert_eq!(vectors.claims.len(), 5);
+    assert_eq!(vectors.hasher, options.hasher.name());
+    if let Some(depth) = options.depth {
+      assert!(vectors
+        .claims
+        .iter()
+        .all(|vector| vector.proof.len() == depth));
+    }
+
+    for vector in &vectors.claims {
+      let claim = claim_of(vector, &options);
+      let leaf = claim.to_bytes();
+      assert_eq!(Claim::from_bytes(leaf.clone()).unwrap(), claim);
+      assert_eq!(
+        vector.leaf,
+        format!("0x{}", hex::encode(options.hasher.hash_leaf(&leaf)))
+      );
+
+      let valid = match options.hasher {
+        ContractHasher::Keccak256 => verify_vector(&KeccakHasher, &vectors, vector, &leaf),
+        ContractHasher::Ripemd160 => verify_vector(&Ripemd160Hasher, &vectors, vector, &leaf),
+      };
+      assert!(valid);
+    }
+  }
+
+  // leaves match the `abi.encode` and `abi.encodePacked` layouts
+  let claim = Claim {
+    account: [0xaa; 20],
+    amount: U256::from(7),
+    salt: None,
+    encoding: LeafEncoding::Abi,
+  };
+  assert_eq!(claim.to_bytes().len(), 64);
+  assert_eq!(&claim.to_bytes()[12..32], &[0xaa; 20]);
+  let claim = Claim {
+    encoding: LeafEncoding::Packed,
+    salt: Some([0xbb; 32]),
+    ..claim
+  };
+  assert_eq!(claim.to_bytes().len(), 84);
+  assert_eq!(&claim.to_bytes()[..20], &[0xaa; 20]);
+  let unsalted = Claim {
+    salt: None,
+    ..claim
+  };
+  assert_eq!(unsalted.to_bytes().len(), 52);
+  assert_eq!(Claim::from_bytes(unsalted.to_bytes()).unwrap(), unsalted);
+  assert!(Claim::from_bytes(vec![0; 63]).is_err());
+}
+
+// compiler for the EVM tests, taken from `SOLC` or the path
+fn solc() -> String {
+  let solc = std::env::var("SOLC").unwrap_or("solc".to_string());
+  match Command::new(&solc).arg("--version").output() {
+    Ok(output) if output.status.success() => solc,
+    _ => panic!(
+      "solc not found at `{}`, set SOLC to run the generated contracts",
+      solc
+    ),
+  }
+}
+
+// creation bytecode of each contract of the source
+fn compile(solc: &str, source: &str) -> HashMap<String, Vec<u8>> {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("Distributor.sol");
+  std::fs::write(&path, source).unwrap();
+  let output = Command::new(solc)
+    .arg("--combined-json")
+    .arg("bin")
+    .arg(&path)
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "{}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+
+  let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+  json["contracts"]
+    .as_object()
+    .unwrap()
+    .iter()
+    .map(|(name, contract)| {
+      let name = name.rsplit(':').next().unwrap().to_string();
+      (name, parse_hex(contract["bin"].as_str().unwrap()))
+    })
+    .collect()
+}
+
+fn selector(signature: &str) -> Vec<u8> {
+  KeccakHasher.hash_leaf(signature.as_bytes())[..4].to_vec()
+}
+
+fn call_data(signature: &str, tokens: &[Token]) -> Vec<u8> {
+  let mut data = selector(signature);
+  data.extend(abi::encode(tokens));
+  data
+}
+
+struct Chain {
+  evm: Evm<'static, (), CacheDB<EmptyDB>>,
+}
+
+impl Chain {
+  fn new() -> Self {
+    Self {
+      evm: Evm::builder()
+        .with_db(CacheDB::new(EmptyDB::default()))
+        .build(),
+    }
+  }
+
+  fn transact(&mut self, to: TxKind, data: Vec<u8>) -> ExecutionResult {
+    let tx = self.evm.tx_mut();
+    tx.transact_to = to;
+    tx.data = Bytes::from(data);
+    tx.gas_limit = 30_000_000;
+    self.evm.transact_commit().unwrap()
+  }
+
+  fn deploy(&mut self, code: Vec<u8>) -> Address {
+    match self.transact(TxKind::Create, code) {
+      ExecutionResult::Success {
+        output: Output::Create(_, Some(address)),
+        ..
+      } => address,
+      result => panic!("deployment failed: {:?}", result),
+    }
+  }
+
+  fn call(&mut self, to: Address, data: Vec<u8>) -> Result<Vec<u8>, Vec<u8>> {
+    match self.transact(TxKind::Call(to), data) {
+      ExecutionResult::Success {
+        output: Output::Call(output),
+        ..
+      } => Ok(output.to_vec()),
+      ExecutionResult::Revert { output, .. } => Err(output.to_vec()),
+      result => panic!("call failed: {:?}", result),
+    }
+  }
+}
+
+fn address_token(address: Address) -> Token {
+  Token::Address(address.0 .0)
+}
+
+#[test]
+#[ignore = "needs solc"]
+fn test_claim_in_evm() {
+  let solc = solc();
+
+  for options in test_cases() {
+    let vectors = test_vectors(&options);
+    let source = generate(&options).unwrap() + MOCK_TOKEN;
+    let contracts = compile(&solc, &source);
+
+    let mut chain = Chain::new();
+    let token = chain.deploy(contracts["MockToken"].clone());
+    let mut code = contracts[options.name.as_str()].clone();
+    code.extend(abi::encode(&[
+      address_token(token),
+      Token::Bytes32(word(&parse_hex(&vectors.root))),
+    ]));
+    let distributor = chain.deploy(code);
+    let data = call_data(
+      "mint(address,uint256)",
+      &[
+        address_token(distributor),
+        Token::Uint(U256::from(u64::MAX)),
+      ],
+    );
+    chain.call(token, data).unwrap();
+
+    let digest = match options.hasher {
+      ContractHasher::Keccak256 => "bytes32",
+      ContractHasher::Ripemd160 => "bytes20",
+    };
+    let proof_type = match options.depth {
+      Some(depth) => format!("{}[{}]", digest, depth),
+      None => format!("{}[]", digest),
+    };
+    let positional = options.order == PairOrder::Positional;
+    let signature = format!(
+      "claim({}address,uint256,{}{})",
+      if positional { "uint256," } else { "" },
+      if options.salted { "bytes32," } else { "" },
+      proof_type
+    );
+    let claim_data = |vector: &TestVector, amount: U256| {
+      let claim = claim_of(vector, &options);
+      let mut tokens = Vec::new();
+      if positional {
+        tokens.push(Token::Uint(U256::from(vector.index)));
+      }
+      tokens.push(Token::Address(claim.account));
+      tokens.push(Token::Uint(amount));
+      tokens.extend(claim.salt.map(Token::Bytes32));
+      let proof = vector
+        .proof
+        .iter()
+        .map(|hash| Token::Bytes32(word(&parse_hex(hash))))
+        .collect();
+      tokens.push(match options.depth {
+        Some(_) => Token::FixedArray(proof),
+        None => Token::Array(proof),
+      });
+      call_data(&signature, &tokens)
+    };
+
+    for vector in &vectors.claims {
+      let claim = claim_of(vector, &options);
+
+      // the contract hashes leaves as the tree does
+      let mut tokens = vec![Token::Address(claim.account), Token::Uint(claim.amount)];
+      tokens.extend(claim.salt.map(Token::Bytes32));
+      let signature = format!(
+        "leafHash(address,uint256{})",
+        if options.salted { ",bytes32" } else { "" }
+      );
+      let leaf = chain
+        .call(distributor, call_data(&signature, &tokens))
+        .unwrap();
+      assert_eq!(leaf, word(&parse_hex(&vector.leaf)));
+
+      // a wrong amount fails the proof
+      let invalid = chain
+        .call(distributor, claim_data(vector, claim.amount + 1))
+        .unwrap_err();
+      assert_eq!(invalid, selector("InvalidProof()"));
+
+      chain
+        .call(distributor, claim_data(vector, claim.amount))
+        .unwrap();
+      let balance = chain
+        .call(
+          token,
+          call_data("balanceOf(address)", &[Token::Address(claim.account)]),
+        )
+        .unwrap();
+      assert_eq!(U256::from_big_endian(&balance), claim.amount);
+
+      // each claim pays out once
+      let claimed = chain
+        .call(distributor, claim_data(vector, claim.amount))
+        .unwrap_err();
+      assert_eq!(claimed, selector("AlreadyClaimed()"));
+    }
+  }
+}
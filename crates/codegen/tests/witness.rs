@@ -0,0 +1,163 @@
+use codegen::witness::Witness;
+use indexed_merkle_tree::errors::{DecodeError, MerkleError};
+use indexed_merkle_tree::hasher::{
+  Bn254PoseidonHasher, Hasher, KeccakHasher, POSEIDON_LEAF_FIELDS,
+};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+use primitive_types::U256;
+
+#[derive(Debug, Clone)]
+struct TestData(String);
+
+impl SerializableData for TestData {
+  fn to_bytes(&self) -> Vec<u8> {
+    self.0.as_bytes().to_vec()
+  }
+
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError> {
+    Ok(TestData(String::from_utf8(bytes)?))
+  }
+
+  fn key(&self) -> [u8; 32] {
+    KeccakHasher.hash_leaf(self.0.as_bytes())
+  }
+}
+
+// leaves spanning several field elements
+fn test_data() -> Vec<TestData> {
+  (0..6)
+    .map(|i| TestData(format!("0x{:040x}:{}", i + 1, 1000 * (i + 1))))
+    .collect()
+}
+
+fn test_tree() -> IndexedMerkleTree<TestData, Bn254PoseidonHasher> {
+  let options = TreeOptions {
+    depth: Some(4),
+    ..Default::default()
+  };
+  IndexedMerkleTree::with_options(test_data(), Bn254PoseidonHasher, options).unwrap()
+}
+
+fn decimal(field: &[u8; 32]) -> String {
+  U256::from_big_endian(field).to_string()
+}
+
+fn strings(value: &toml::Value) -> Vec<String> {
+  value
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|value| value.as_str().unwrap().to_string())
+    .collect()
+}
+
+#[test]
+fn test_witness_round_trip() {
+  let tree = test_tree();
+  for data in test_data() {
+    let witness = Witness::from_tree(&tree, data.key()).unwrap();
+    let index = tree.get_index(data.key()).unwrap();
+    // padded to the fixed leaf width
+    let fields = data.to_bytes().len().div_ceil(32);
+    assert_eq!(witness.leaf.len(), POSEIDON_LEAF_FIELDS);
+    assert!(witness.leaf[fields..].iter().all(|field| field == "0"));
+    assert_eq!(witness.path_elements.len(), 4);
+    assert_eq!(witness.root, decimal(&tree.root.hash));
+    for (level, bit) in witness.path_indices.iter().enumerate() {
+      assert_eq!(bit, &((index >> level) & 1).to_string());
+    }
+
+    // Circom input
+    let json: serde_json::Value = serde_json::from_str(&witness.to_circom_json()).unwrap();
+    for name in ["leaf", "pathElements", "pathIndices", "root"] {
+      assert!(json.get(name).is_some());
+    }
+    let circom: Witness = serde_json::from_value(json).unwrap();
+    assert_eq!(circom, witness);
+    assert_eq!(circom.compute_root().unwrap(), tree.root.hash);
+
+    // Noir prover input
+    let toml: toml::Table = witness.to_prover_toml().parse().unwrap();
+    let noir = Witness {
+      leaf: strings(&toml["leaf"]),
+      path_elements: strings(&toml["path_elements"]),
+      path_indices: strings(&toml["path_indices"]),
+      root: toml["root"].as_str().unwrap().to_string(),
+    };
+    assert_eq!(noir, witness);
+    assert_eq!(noir.compute_root().unwrap(), tree.root.hash);
+    assert!(noir.verify().unwrap());
+
+    // the leaf fields hash to the tree leaf
+    let leaf: Vec<[u8; 32]> = witness
+      .leaf
+      .iter()
+      .map(|value| {
+        let mut field = [0; 32];
+        U256::from_dec_str(value).unwrap().to_big_endian(&mut field);
+        field
+      })
+      .collect();
+    assert_eq!(
+      Bn254PoseidonHasher.hash_fields(&leaf),
+      tree.get_node(0, index).unwrap().hash
+    );
+  }
+}
+
+#[test]
+fn test_invalid_witness() {
+  let tree = test_tree();
+  let witness = Witness::from_tree(&tree, test_data()[2].key()).unwrap();
+
+  // a flipped path index or leaf field reaches another root
+  let mut flipped = witness.clone();
+  flipped.path_indices[0] = if flipped.path_indices[0] == "0" {
+    "1"
+  } else {
+    "0"
+  }
+  .to_string();
+  assert!(!flipped.verify().unwrap());
+  let mut changed = witness.clone();
+  changed.leaf[0] = "1".to_string();
+  assert!(!changed.verify().unwrap());
+
+  let mut invalid = witness.clone();
+  invalid.path_indices[1] = "2".to_string();
+  assert!(matches!(
+    invalid.compute_root(),
+    Err(MerkleError::InvalidProofEncoding { offset: 1 })
+  ));
+  let mut invalid = witness.clone();
+  invalid.path_indices.pop();
+  assert!(invalid.compute_root().is_err());
+
+  // field elements are decimal and below the BN254 scalar field order
+  let mut invalid = witness.clone();
+  invalid.path_elements[0] =
+    "21888242871839275222246405745257275088548364400416034343698204186575808495617".to_string();
+  assert!(matches!(
+    invalid.compute_root(),
+    Err(MerkleError::Decode { .. })
+  ));
+  let mut invalid = witness;
+  invalid.root = "0x01".to_string();
+  assert!(invalid.verify().is_err());
+
+  assert!(Witness::from_tree(&tree, [0; 32]).is_err());
+}
+
+#[test]
+fn test_witness_of_k_ary_tree() {
+  let options = TreeOptions {
+    arity: 4,
+    ..Default::default()
+  };
+  let tree = IndexedMerkleTree::with_options(test_data(), Bn254PoseidonHasher, options).unwrap();
+  assert!(matches!(
+    Witness::from_tree(&tree, test_data()[0].key()),
+    Err(MerkleError::InvalidArity { arity: 4 })
+  ));
+}
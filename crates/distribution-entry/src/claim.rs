@@ -0,0 +1,90 @@
+// Local imports
+use indexed_merkle_tree::{
+  abi::{self, ParamType, Token},
+  errors::DecodeError,
+  hasher::{Hasher, KeccakHasher},
+  node::SerializableData,
+};
+
+// Core lib imports
+use alloc::vec;
+use alloc::vec::Vec;
+
+// External imports
+use primitive_types::U256;
+
+// Layout of `(account, amount)` leaves, followed by the salt in salted trees
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafEncoding {
+  // `abi.encode(account, amount)`
+  Abi,
+  // `abi.encodePacked(account, amount)`
+  Packed,
+}
+
+impl LeafEncoding {
+  pub fn name(&self) -> &'static str {
+    match self {
+      LeafEncoding::Abi => "abi",
+      LeafEncoding::Packed => "packed",
+    }
+  }
+}
+
+// Leaf of a distribution verified on-chain, keyed by the keccak256 of the account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Claim {
+  pub account: [u8; 20],
+  pub amount: U256,
+  pub salt: Option<[u8; 32]>,
+  pub encoding: LeafEncoding,
+}
+
+impl SerializableData for Claim {
+  fn to_bytes(&self) -> Vec<u8> {
+    let mut tokens = vec![Token::Address(self.account), Token::Uint(self.amount)];
+    tokens.extend(self.salt.map(Token::Bytes32));
+    match self.encoding {
+      LeafEncoding::Abi => abi::encode(&tokens),
+      LeafEncoding::Packed => abi::encode_packed(&tokens),
+    }
+  }
+
+  // the four layouts have distinct lengths
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError> {
+    match bytes.len() {
+      64 | 96 => {
+        let mut types = vec![ParamType::Address, ParamType::Uint];
+        if bytes.len() == 96 {
+          types.push(ParamType::Bytes32);
+        }
+        match abi::decode(&types, &bytes)?.as_slice() {
+          [Token::Address(account), Token::Uint(amount), salt @ ..] => Ok(Claim {
+            account: *account,
+            amount: *amount,
+            salt: match salt {
+              [Token::Bytes32(salt)] => Some(*salt),
+              _ => None,
+            },
+            encoding: LeafEncoding::Abi,
+          }),
+          _ => unreachable!(),
+        }
+      }
+      52 | 84 => Ok(Claim {
+        account: bytes[..20].try_into().unwrap(),
+        amount: U256::from_big_endian(&bytes[20..52]),
+        salt: (bytes.len() == 84).then(|| bytes[52..].try_into().unwrap()),
+        encoding: LeafEncoding::Packed,
+      }),
+      len => Err(DecodeError {
+        offset: len.min(96),
+        reason: "invalid claim length",
+      }),
+    }
+  }
+
+  fn key(&self) -> [u8; 32] {
+    KeccakHasher.hash_leaf(&self.account)
+  }
+}
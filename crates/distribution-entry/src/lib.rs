@@ -0,0 +1,192 @@
+#![no_std]
+
+extern crate alloc;
+
+mod claim;
+pub use claim::{Claim, LeafEncoding};
+
+// Local imports
+#[cfg(feature = "abi-leaves")]
+use indexed_merkle_tree::errors::DecodeError;
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use indexed_merkle_tree::node::SerializableData;
+#[cfg(feature = "std")]
+use indexed_merkle_tree::ordering::LeafOrdering;
+use indexed_merkle_tree::salt::derive_salt;
+
+// Core lib imports
+use alloc::collections::btree_map::{BTreeMap, Entry};
+#[cfg(feature = "abi-leaves")]
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// External imports
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+// name of the leaf layout below, hashed into the scheme id of the commitment envelope
+#[cfg(not(feature = "abi-leaves"))]
+pub const LEAF_SCHEME: &str = "keccak256/length-prefixed";
+#[cfg(feature = "abi-leaves")]
+pub const LEAF_SCHEME: &str = "keccak256/abi-encode";
+
+// leaf encoding is the length-prefixed address followed by the length-prefixed amount, keyed by
+// keccak256 of the address. With the `abi-leaves` feature leaves are `abi.encode(address, uint256)`
+// instead, matching claim contracts that hash `keccak256(abi.encode(account, amount))`. Salted
+// entries append the 32-byte salt to either layout.
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[cfg_attr(not(feature = "abi-leaves"), derive(SerializableData))]
+pub struct DistributionEntry {
+  #[cfg_attr(not(feature = "abi-leaves"), key)]
+  pub address: String,
+  pub amount: String,
+  #[serde(skip)]
+  #[cfg_attr(not(feature = "abi-leaves"), merkle(encoding = "packed"))]
+  pub salt: Option<[u8; 32]>,
+}
+
+// ABI leaves are only defined for entries passing `check_entry`, which every entry from user input
+// goes through before it is hashed. They share the encoding of an ABI `Claim`.
+#[cfg(feature = "abi-leaves")]
+impl SerializableData for DistributionEntry {
+  fn to_bytes(&self) -> Vec<u8> {
+    Claim {
+      account: parse_address(&self.address).expect("Invalid address"),
+      amount: U256::from_dec_str(&self.amount).expect("Invalid amount"),
+      salt: self.salt,
+      encoding: LeafEncoding::Abi,
+    }
+    .to_bytes()
+  }
+
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError> {
+    // packed claims would encode back to another layout
+    if !matches!(bytes.len(), 64 | 96) {
+      return Err(DecodeError {
+        offset: bytes.len().min(96),
+        reason: "invalid entry length",
+      });
+    }
+    let claim = Claim::from_bytes(bytes)?;
+    Ok(DistributionEntry {
+      address: format!("0x{}", hex::encode(claim.account)),
+      amount: claim.amount.to_string(),
+      salt: claim.salt,
+    })
+  }
+
+  fn key(&self) -> [u8; 32] {
+    address_key(&self.address)
+  }
+}
+
+// entry rejected by `check_entry`, or whose amount takes the total past a uint256
+#[derive(Debug, Clone)]
+pub struct InvalidEntry {
+  pub entry: DistributionEntry,
+  pub reason: &'static str,
+}
+
+// check that the entry can be encoded as a leaf: amounts must fit a uint256, and ABI leaves also
+// need a 20-byte hex address
+pub fn check_entry(entry: &DistributionEntry) -> Result<(), &'static str> {
+  U256::from_dec_str(&entry.amount).map_err(|_| "invalid amount")?;
+  #[cfg(feature = "abi-leaves")]
+  parse_address(&entry.address).ok_or("invalid address")?;
+  Ok(())
+}
+
+#[cfg(feature = "abi-leaves")]
+fn parse_address(address: &str) -> Option<[u8; 20]> {
+  let bytes = hex::decode(address.trim_start_matches("0x")).ok()?;
+  bytes.try_into().ok()
+}
+
+// key of the entry for an address. Length-prefixed leaves hold any address string, so they are
+// keyed by the keccak256 of the string itself. ABI leaves are claims, keyed like `Claim::key` by
+// the keccak256 of the 20 address bytes, so that the API, the wasm verifier and generated
+// contracts agree on keys and the salts derived from them, and keys are case insensitive. Strings
+// that aren't an address match no ABI leaf and keep the string key.
+#[cfg(not(feature = "abi-leaves"))]
+pub fn address_key(address: &str) -> [u8; 32] {
+  KeccakHasher.hash_leaf(address.as_bytes())
+}
+
+#[cfg(feature = "abi-leaves")]
+pub fn address_key(address: &str) -> [u8; 32] {
+  match parse_address(address) {
+    Some(account) => KeccakHasher.hash_leaf(&account),
+    None => KeccakHasher.hash_leaf(address.as_bytes()),
+  }
+}
+
+// leaves sorted by address string, so that the root does not depend on the order of the data files
+#[cfg(feature = "std")]
+pub fn address_ordering() -> LeafOrdering<DistributionEntry> {
+  LeafOrdering::Custom {
+    name: "address",
+    cmp: |a, b| a.address.cmp(&b.address),
+  }
+}
+
+// salt every entry from the operator's secret seed, so the same seed always rebuilds the same tree
+pub fn apply_salts(entries: &mut [DistributionEntry], seed: &[u8]) {
+  for entry in entries {
+    entry.salt = Some(derive_salt(seed, &address_key(&entry.address)));
+  }
+}
+
+// sum the amounts of entries sharing an address, returning the merged entries and the total amount.
+// Every entry is checked with `check_entry`, and the total must fit a uint256.
+pub fn merge_entries<I: IntoIterator<Item = DistributionEntry>>(
+  data: I,
+) -> Result<(Vec<DistributionEntry>, String), InvalidEntry> {
+  let mut entries: BTreeMap<String, DistributionEntry> = BTreeMap::new();
+  let mut total_amount: U256 = U256::from(0);
+
+  for mut entry in data {
+    if let Err(reason) = check_entry(&entry) {
+      return Err(InvalidEntry { entry, reason });
+    }
+    if cfg!(feature = "abi-leaves") {
+      entry.address = entry.address.to_lowercase();
+    }
+    entry.salt = None;
+    // merged amounts are at most the total, so they can't overflow either
+    total_amount = match total_amount.checked_add(U256::from_dec_str(&entry.amount).unwrap()) {
+      Some(total_amount) => total_amount,
+      None => {
+        return Err(InvalidEntry {
+          entry,
+          reason: "total amount overflows",
+        })
+      }
+    };
+    match entries.entry(entry.address.clone()) {
+      Entry::Occupied(mut existing) => {
+        let earlier = existing.get().clone();
+        *existing.get_mut() = add_amounts(earlier, entry);
+      }
+      Entry::Vacant(vacant) => {
+        vacant.insert(entry);
+      }
+    }
+  }
+
+  // the tree orders the entries with `address_ordering`
+  Ok((entries.into_values().collect(), total_amount.to_string()))
+}
+
+// merge two entries of the same address as `merge_entries` does, e.g. as the `merge` of a streamed
+// build along with `address_ordering`. Like `to_bytes` this is only defined for entries passing
+// `check_entry` whose amounts sum to a uint256, and addresses must already be lowercase with
+// `abi-leaves`.
+pub fn add_amounts(earlier: DistributionEntry, later: DistributionEntry) -> DistributionEntry {
+  let amount = U256::from_dec_str(&earlier.amount).expect("Invalid amount")
+    + U256::from_dec_str(&later.amount).expect("Invalid amount");
+  DistributionEntry {
+    amount: amount.to_string(),
+    ..earlier
+  }
+}
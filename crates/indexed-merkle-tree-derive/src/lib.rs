@@ -0,0 +1,224 @@
+//! A packed field that takes the rest of the input can only come last:
+//!
+//! ```
+//! use indexed_merkle_tree::node::SerializableData;
+//!
+//! #[derive(Clone, SerializableData)]
+//! struct Claim {
+//!   #[key]
+//!   account: [u8; 20],
+//!   #[merkle(encoding = "packed")]
+//!   memo: String,
+//! }
+//! ```
+//!
+//! ```compile_fail
+//! use indexed_merkle_tree::node::SerializableData;
+//!
+//! #[derive(Clone, SerializableData)]
+//! struct Claim {
+//!   #[merkle(encoding = "packed")]
+//!   memo: String,
+//!   #[key]
+//!   account: [u8; 20],
+//! }
+//! ```
+
+// External imports
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr, Type};
+
+// Derives `SerializableData` for a struct with named fields. Fields are encoded in declaration
+// order with the encoding picked by `#[merkle(encoding = "...")]` (`length-prefixed` when absent,
+// `packed` or `abi`), and exactly one field must be marked `#[key]`. Packed `String`, `Vec` and
+// `Option` fields take the rest of the input, so they are rejected anywhere but last. Also
+// generates an inherent `try_from_bytes` that reports where decoding failed instead of panicking.
+#[proc_macro_derive(SerializableData, attributes(key, merkle))]
+pub fn derive_serializable_data(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  expand(input)
+    .unwrap_or_else(|err| err.to_compile_error())
+    .into()
+}
+
+enum Encoding {
+  Packed,
+  LengthPrefixed,
+  Abi,
+}
+
+impl Encoding {
+  fn functions(&self) -> (Ident, Ident) {
+    let name = match self {
+      Encoding::Packed => "packed",
+      Encoding::LengthPrefixed => "length_prefixed",
+      Encoding::Abi => "abi",
+    };
+    (
+      Ident::new(&format!("encode_{}", name), Span::call_site()),
+      Ident::new(&format!("decode_{}", name), Span::call_site()),
+    )
+  }
+}
+
+struct Field {
+  ident: Ident,
+  ty: Type,
+  encoding: Encoding,
+  key: bool,
+}
+
+impl Field {
+  // packed `String`, `Vec` and `Option` values take the rest of the input
+  fn takes_rest(&self) -> bool {
+    let Encoding::Packed = self.encoding else {
+      return false;
+    };
+    match &self.ty {
+      Type::Path(path) => path.path.segments.last().is_some_and(|segment| {
+        segment.ident == "String" || segment.ident == "Vec" || segment.ident == "Option"
+      }),
+      _ => false,
+    }
+  }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+  let fields = match &input.data {
+    Data::Struct(data) => match &data.fields {
+      Fields::Named(fields) => &fields.named,
+      _ => {
+        return Err(syn::Error::new_spanned(
+          &input.ident,
+          "SerializableData can only be derived for structs with named fields",
+        ))
+      }
+    },
+    _ => {
+      return Err(syn::Error::new_spanned(
+        &input.ident,
+        "SerializableData can only be derived for structs",
+      ))
+    }
+  };
+
+  let fields = fields
+    .iter()
+    .map(parse_field)
+    .collect::<syn::Result<Vec<_>>>()?;
+  if let Some((_, leading)) = fields.split_last() {
+    if let Some(field) = leading.iter().find(|field| field.takes_rest()) {
+      return Err(syn::Error::new_spanned(
+        &field.ty,
+        "packed `String`, `Vec` and `Option` fields take the rest of the input and can only be the last field",
+      ));
+    }
+  }
+
+  let mut keys = fields.iter().filter(|field| field.key);
+  let key = match (keys.next(), keys.next()) {
+    (Some(key), None) => &key.ident,
+    (None, _) => {
+      return Err(syn::Error::new_spanned(
+        &input.ident,
+        "exactly one field must be marked #[key]",
+      ))
+    }
+    (Some(_), Some(second)) => {
+      return Err(syn::Error::new_spanned(
+        &second.ident,
+        "only one field can be marked #[key]",
+      ))
+    }
+  };
+
+  let codec = quote!(::indexed_merkle_tree::codec);
+  let encode = fields.iter().map(|field| {
+    let ident = &field.ident;
+    let (encode, _) = field.encoding.functions();
+    quote!(#codec::#encode(&self.#ident, &mut bytes);)
+  });
+  let decode = fields.iter().map(|field| {
+    let ident = &field.ident;
+    let (_, decode) = field.encoding.functions();
+    quote!(let #ident = #codec::#decode(&mut decoder)?;)
+  });
+  let idents = fields.iter().map(|field| &field.ident);
+
+  let name = &input.ident;
+  let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+  Ok(quote! {
+    impl #impl_generics ::indexed_merkle_tree::node::SerializableData for #name #ty_generics
+    #where_clause
+    {
+      fn to_bytes(&self) -> #codec::ByteVec<u8> {
+        let mut bytes = #codec::ByteVec::new();
+        #(#encode)*
+        bytes
+      }
+
+      fn from_bytes(
+        bytes: #codec::ByteVec<u8>,
+      ) -> ::core::result::Result<Self, ::indexed_merkle_tree::errors::DecodeError> {
+        Self::try_from_bytes(&bytes)
+      }
+
+      fn key(&self) -> [u8; 32] {
+        #codec::KeyField::to_key(&self.#key)
+      }
+    }
+
+    impl #impl_generics #name #ty_generics #where_clause {
+      pub fn try_from_bytes(
+        bytes: &[u8],
+      ) -> ::core::result::Result<Self, ::indexed_merkle_tree::errors::DecodeError> {
+        let mut decoder = #codec::Decoder::new(bytes);
+        #(#decode)*
+        decoder.finish()?;
+        Ok(Self { #(#idents),* })
+      }
+    }
+  })
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<Field> {
+  let mut encoding = Encoding::LengthPrefixed;
+  let mut key = false;
+
+  for attr in &field.attrs {
+    if attr.path().is_ident("key") {
+      attr.meta.require_path_only()?;
+      key = true;
+    } else if attr.path().is_ident("merkle") {
+      attr.parse_nested_meta(|meta| {
+        if !meta.path.is_ident("encoding") {
+          return Err(meta.error("expected `encoding = \"...\"`"));
+        }
+        let value: LitStr = meta.value()?.parse()?;
+        encoding = match value.value().as_str() {
+          "packed" => Encoding::Packed,
+          "length-prefixed" => Encoding::LengthPrefixed,
+          "abi" => Encoding::Abi,
+          _ => {
+            return Err(syn::Error::new_spanned(
+              value,
+              "expected one of \"packed\", \"length-prefixed\" or \"abi\"",
+            ))
+          }
+        };
+        Ok(())
+      })?;
+    }
+  }
+
+  Ok(Field {
+    // named fields always have an identifier
+    ident: field.ident.clone().unwrap(),
+    ty: field.ty.clone(),
+    encoding,
+    key,
+  })
+}
@@ -0,0 +1,63 @@
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use indexed_merkle_tree::index::{KeyIndex, SortedKeyIndex};
+
+use std::collections::HashMap;
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+const KEY_COUNT: usize = 10_000_000;
+const LOOKUPS: usize = 100_000;
+
+fn keys() -> Vec<[u8; 32]> {
+  (0..KEY_COUNT as u64)
+    .map(|i| KeccakHasher.hash_leaf(&i.to_le_bytes()))
+    .collect()
+}
+
+fn build<I: KeyIndex>(keys: &[[u8; 32]]) -> I {
+  let mut index = I::default();
+  for (position, key) in keys.iter().enumerate() {
+    index.insert(*key, position).unwrap();
+  }
+  index.seal();
+  index
+}
+
+fn bench_key_index(c: &mut Criterion) {
+  let keys = keys();
+  let map: HashMap<[u8; 32], usize> = build(&keys);
+  let sorted: SortedKeyIndex = build(&keys);
+
+  let probes: Vec<[u8; 32]> = keys.iter().step_by(KEY_COUNT / LOOKUPS).copied().collect();
+
+  let mut group = c.benchmark_group("key_index_lookup_10m");
+  group.bench_function("hash_map", |b| {
+    b.iter(|| {
+      for key in &probes {
+        black_box(KeyIndex::get(&map, key));
+      }
+    })
+  });
+  group.bench_function("sorted", |b| {
+    b.iter(|| {
+      for key in &probes {
+        black_box(sorted.get(key));
+      }
+    })
+  });
+  group.finish();
+
+  let mut group = c.benchmark_group("key_index_build_10m");
+  group.sample_size(10);
+  group.bench_function("hash_map", |b| {
+    b.iter(|| black_box(build::<HashMap<[u8; 32], usize>>(&keys)))
+  });
+  group.bench_function("sorted", |b| {
+    b.iter(|| black_box(build::<SortedKeyIndex>(&keys)))
+  });
+  group.finish();
+}
+
+criterion_group!(benches, bench_key_index);
+criterion_main!(benches);
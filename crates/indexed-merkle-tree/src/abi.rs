@@ -0,0 +1,285 @@
+// Local imports
+use crate::errors::DecodeError;
+
+// Core lib imports
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+
+// External imports
+use primitive_types::U256;
+
+// Solidity value, encoded like `abi.encode` / `abi.encodePacked` would encode it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+  Address([u8; 20]),
+  Uint(U256),
+  Bytes32([u8; 32]),
+  Bool(bool),
+  String(String),
+  Bytes(Vec<u8>),
+  Array(Vec<Token>),      // T[]
+  FixedArray(Vec<Token>), // T[k]
+}
+
+// Solidity type used to decode `abi.encode` output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamType {
+  Address,
+  Uint,
+  Bytes32,
+  Bool,
+  String,
+  Bytes,
+  Array(Box<ParamType>),
+  FixedArray(Box<ParamType>, usize),
+}
+
+impl Token {
+  fn is_dynamic(&self) -> bool {
+    match self {
+      Token::String(_) | Token::Bytes(_) | Token::Array(_) => true,
+      Token::FixedArray(tokens) => tokens.iter().any(Token::is_dynamic),
+      _ => false,
+    }
+  }
+
+  // bytes taken in the head of the enclosing tuple
+  fn head_size(&self) -> usize {
+    match self {
+      Token::FixedArray(tokens) if !self.is_dynamic() => tokens.iter().map(Token::head_size).sum(),
+      _ => 32,
+    }
+  }
+}
+
+impl ParamType {
+  fn is_dynamic(&self) -> bool {
+    match self {
+      ParamType::String | ParamType::Bytes | ParamType::Array(_) => true,
+      ParamType::FixedArray(inner, _) => inner.is_dynamic(),
+      _ => false,
+    }
+  }
+
+  // bytes taken in the head of the enclosing tuple
+  fn head_size(&self) -> usize {
+    match self {
+      ParamType::FixedArray(inner, len) if !inner.is_dynamic() => inner.head_size() * len,
+      _ => 32,
+    }
+  }
+}
+
+// `abi.encode(tokens...)`
+pub fn encode(tokens: &[Token]) -> Vec<u8> {
+  encode_sequence(tokens)
+}
+
+// `abi.encodePacked(tokens...)`: static values take their natural width, strings and bytes are
+// not padded, and array elements are padded to 32 bytes. Solidity rejects dynamic values nested in
+// arrays, here they are packed without padding.
+pub fn encode_packed(tokens: &[Token]) -> Vec<u8> {
+  let mut out = Vec::new();
+  for token in tokens {
+    match token {
+      Token::Address(address) => out.extend_from_slice(address),
+      Token::Bool(value) => out.push(*value as u8),
+      Token::String(value) => out.extend_from_slice(value.as_bytes()),
+      Token::Bytes(value) => out.extend_from_slice(value),
+      Token::Array(tokens) | Token::FixedArray(tokens) => {
+        for token in tokens {
+          if token.is_dynamic() {
+            out.extend_from_slice(&encode_packed(core::slice::from_ref(token)));
+          } else {
+            out.extend_from_slice(&encode_token(token));
+          }
+        }
+      }
+      Token::Uint(_) | Token::Bytes32(_) => out.extend_from_slice(&encode_token(token)),
+    }
+  }
+  out
+}
+
+// Inverse of `encode` for the given parameter types. Trailing bytes are ignored, as in Solidity's
+// `abi.decode`.
+pub fn decode(types: &[ParamType], bytes: &[u8]) -> Result<Vec<Token>, DecodeError> {
+  decode_sequence(types, bytes, 0)
+}
+
+fn encode_sequence(tokens: &[Token]) -> Vec<u8> {
+  let head_len: usize = tokens.iter().map(Token::head_size).sum();
+
+  let mut head = Vec::with_capacity(head_len);
+  let mut tail = Vec::new();
+  for token in tokens {
+    if token.is_dynamic() {
+      head.extend_from_slice(&usize_word(head_len + tail.len()));
+      tail.extend_from_slice(&encode_token(token));
+    } else {
+      head.extend_from_slice(&encode_token(token));
+    }
+  }
+  head.extend_from_slice(&tail);
+  head
+}
+
+fn encode_token(token: &Token) -> Vec<u8> {
+  match token {
+    Token::Address(address) => {
+      let mut word = [0u8; 32];
+      word[12..].copy_from_slice(address);
+      word.to_vec()
+    }
+    Token::Uint(value) => {
+      let mut word = [0u8; 32];
+      value.to_big_endian(&mut word);
+      word.to_vec()
+    }
+    Token::Bytes32(word) => word.to_vec(),
+    Token::Bool(value) => usize_word(*value as usize).to_vec(),
+    Token::String(value) => encode_bytes(value.as_bytes()),
+    Token::Bytes(value) => encode_bytes(value),
+    Token::Array(tokens) => {
+      let mut out = usize_word(tokens.len()).to_vec();
+      out.extend_from_slice(&encode_sequence(tokens));
+      out
+    }
+    Token::FixedArray(tokens) => encode_sequence(tokens),
+  }
+}
+
+// length word followed by the data right-padded to a multiple of 32 bytes
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+  let mut out = usize_word(bytes.len()).to_vec();
+  out.extend_from_slice(bytes);
+  out.resize(32 + bytes.len().div_ceil(32) * 32, 0);
+  out
+}
+
+fn usize_word(value: usize) -> [u8; 32] {
+  let mut word = [0u8; 32];
+  word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+  word
+}
+
+// `base` is the offset of `bytes` in the full input, used for error offsets
+fn decode_sequence(
+  types: &[ParamType],
+  bytes: &[u8],
+  base: usize,
+) -> Result<Vec<Token>, DecodeError> {
+  let mut tokens = Vec::with_capacity(types.len());
+  let mut head = 0;
+  for ty in types {
+    if ty.is_dynamic() {
+      let offset = read_usize(bytes, head, base)?;
+      let tail = bytes.get(offset..).ok_or(DecodeError {
+        offset: base + head,
+        reason: "offset out of range",
+      })?;
+      tokens.push(decode_token(ty, tail, base + offset)?);
+    } else {
+      tokens.push(decode_token(
+        ty,
+        &bytes[head.min(bytes.len())..],
+        base + head,
+      )?);
+    }
+    head += ty.head_size();
+  }
+  Ok(tokens)
+}
+
+fn decode_token(ty: &ParamType, bytes: &[u8], base: usize) -> Result<Token, DecodeError> {
+  match ty {
+    ParamType::Address => {
+      let word = read_word(bytes, 0, base)?;
+      if word[..12].iter().any(|byte| *byte != 0) {
+        return Err(DecodeError {
+          offset: base,
+          reason: "address out of range",
+        });
+      }
+      Ok(Token::Address(word[12..].try_into().unwrap()))
+    }
+    ParamType::Uint => Ok(Token::Uint(U256::from_big_endian(&read_word(
+      bytes, 0, base,
+    )?))),
+    ParamType::Bytes32 => Ok(Token::Bytes32(read_word(bytes, 0, base)?)),
+    ParamType::Bool => match read_usize(bytes, 0, base)? {
+      0 => Ok(Token::Bool(false)),
+      1 => Ok(Token::Bool(true)),
+      _ => Err(DecodeError {
+        offset: base,
+        reason: "invalid bool",
+      }),
+    },
+    ParamType::Bytes | ParamType::String => {
+      let len = read_usize(bytes, 0, base)?;
+      let data = bytes
+        .get(32..32usize.saturating_add(len))
+        .ok_or(DecodeError {
+          offset: base + 32,
+          reason: "unexpected end of input",
+        })?;
+      if *ty == ParamType::Bytes {
+        return Ok(Token::Bytes(data.to_vec()));
+      }
+      String::from_utf8(data.to_vec())
+        .map(Token::String)
+        .map_err(|err| DecodeError {
+          offset: base + 32 + err.utf8_error().valid_up_to(),
+          reason: "invalid utf-8",
+        })
+    }
+    ParamType::Array(inner) => {
+      let len = read_usize(bytes, 0, base)?;
+      // every element takes at least one word, reject lengths the input cannot hold before
+      // allocating for them
+      if len > bytes.len().saturating_sub(32) / 32 {
+        return Err(DecodeError {
+          offset: base,
+          reason: "array length out of range",
+        });
+      }
+      let types = vec![(**inner).clone(); len];
+      Ok(Token::Array(decode_sequence(
+        &types,
+        &bytes[32..],
+        base + 32,
+      )?))
+    }
+    ParamType::FixedArray(inner, len) => {
+      if *len > bytes.len() / 32 {
+        return Err(DecodeError {
+          offset: base,
+          reason: "unexpected end of input",
+        });
+      }
+      let types = vec![(**inner).clone(); *len];
+      Ok(Token::FixedArray(decode_sequence(&types, bytes, base)?))
+    }
+  }
+}
+
+fn read_word(bytes: &[u8], at: usize, base: usize) -> Result<[u8; 32], DecodeError> {
+  let word = bytes.get(at..at + 32).ok_or(DecodeError {
+    offset: base + at,
+    reason: "unexpected end of input",
+  })?;
+  Ok(word.try_into().unwrap())
+}
+
+fn read_usize(bytes: &[u8], at: usize, base: usize) -> Result<usize, DecodeError> {
+  let word = read_word(bytes, at, base)?;
+  if word[..24].iter().any(|byte| *byte != 0) {
+    return Err(DecodeError {
+      offset: base + at,
+      reason: "integer out of range",
+    });
+  }
+  usize::try_from(u64::from_be_bytes(word[24..].try_into().unwrap())).map_err(|_| DecodeError {
+    offset: base + at,
+    reason: "integer out of range",
+  })
+}
@@ -0,0 +1,194 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::Hasher;
+use crate::node::{Node, SerializableData};
+use crate::store::NodeStore;
+use crate::tree::{level_width, IndexedMerkleTree};
+
+// Core lib imports
+use std::collections::HashSet;
+
+// Inconsistency found by `IndexedMerkleTree::audit`, `exp` is the recomputed value and `act` the
+// stored one, hashes are the bytes of the hasher's digest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Discrepancy {
+  MissingNode {
+    level: usize,
+    index: usize,
+  },
+  LeafHash {
+    index: usize,
+    exp: Vec<u8>,
+    act: Vec<u8>,
+  },
+  PaddingHash {
+    index: usize,
+    exp: Vec<u8>,
+    act: Vec<u8>,
+  },
+  InternalHash {
+    level: usize,
+    index: usize,
+    exp: Vec<u8>,
+    act: Vec<u8>,
+  },
+  RootHash {
+    exp: Vec<u8>,
+    act: Vec<u8>,
+  },
+  UnindexedKey {
+    key: [u8; 32],
+    index: usize,
+  },
+  MisindexedKey {
+    key: [u8; 32],
+    exp: usize,
+    act: usize,
+  },
+  // key index entry whose leaf does not hold the key: padding, another leaf or a removed key
+  DanglingKey {
+    key: [u8; 32],
+    index: usize,
+  },
+}
+
+#[derive(Debug, Default)]
+pub struct AuditReport {
+  pub discrepancies: Vec<Discrepancy>,
+}
+
+impl AuditReport {
+  pub fn is_clean(&self) -> bool {
+    self.discrepancies.is_empty()
+  }
+}
+
+impl<D: SerializableData, H: Hasher, S: NodeStore<D, H::Digest>> IndexedMerkleTree<D, H, S> {
+  // Recompute every leaf hash from its data and every internal node from its stored children,
+  // check the root, check that the key index maps every leaf key to that leaf, and that every
+  // key of the index resolves to a leaf holding it.
+  pub fn audit(&self) -> Result<AuditReport, MerkleError> {
+    let mut report = AuditReport::default();
+    let mut misindexed = HashSet::new();
+    let base_height = self.base_height()?;
+    let zero_hashes = self.hasher.zero_hashes_with_arity(self.height, self.arity);
+
+    for index in 0..level_width(base_height, 0, self.arity) {
+      let Some(node) = self.store.get_node(0, index)? else {
+        report
+          .discrepancies
+          .push(Discrepancy::MissingNode { level: 0, index });
+        continue;
+      };
+
+      let Some(data) = &node.data else {
+        if node.hash != H::zero() {
+          report.discrepancies.push(Discrepancy::PaddingHash {
+            index,
+            exp: H::zero().as_ref().to_vec(),
+            act: node.hash.as_ref().to_vec(),
+          });
+        }
+        continue;
+      };
+
+      let exp = self.hasher.hash_leaf(&data.to_bytes());
+      if node.hash != exp {
+        report.discrepancies.push(Discrepancy::LeafHash {
+          index,
+          exp: exp.as_ref().to_vec(),
+          act: node.hash.as_ref().to_vec(),
+        });
+      }
+
+      let key = data.key();
+      match self.store.get_index(&key)? {
+        Some(act) if act != index => {
+          misindexed.insert(key);
+          report.discrepancies.push(Discrepancy::MisindexedKey {
+            key,
+            exp: index,
+            act,
+          });
+        }
+        None => {
+          report
+            .discrepancies
+            .push(Discrepancy::UnindexedKey { key, index });
+        }
+        _ => {}
+      }
+    }
+
+    for level in 1..=self.height {
+      for index in 0..level_width(base_height, level, self.arity) {
+        let node = self.store.get_node(level, index)?;
+        let mut children = Vec::with_capacity(self.arity);
+        for position in 0..self.arity {
+          let child = index * self.arity + position;
+          let hash = if level > base_height && position > 0 {
+            // padding levels of a fixed-depth tree have empty subtrees on the right
+            Some(zero_hashes[level - 1])
+          } else {
+            self.store.get_node(level - 1, child)?.map(|node| node.hash)
+          };
+          children.push(hash);
+        }
+        let Some(node) = node else {
+          report
+            .discrepancies
+            .push(Discrepancy::MissingNode { level, index });
+          continue;
+        };
+
+        // missing children are already reported at their own level
+        if let Some(children) = children.into_iter().collect::<Option<Vec<_>>>() {
+          let exp = self.hasher.hash_children(&children);
+          if node.hash != exp {
+            report.discrepancies.push(Discrepancy::InternalHash {
+              level,
+              index,
+              exp: exp.as_ref().to_vec(),
+              act: node.hash.as_ref().to_vec(),
+            });
+          }
+        }
+      }
+    }
+
+    if let Some(node) = self.store.get_node(self.height, 0)? {
+      if node.hash != self.root.hash {
+        report.discrepancies.push(Discrepancy::RootHash {
+          exp: node.hash.as_ref().to_vec(),
+          act: self.root.hash.as_ref().to_vec(),
+        });
+      }
+    }
+
+    // keys already reported as pointing away from their leaf are not reported again
+    let mut dangling = Vec::new();
+    self.store.for_each_index(&mut |key, index| {
+      if misindexed.contains(&key) {
+        return Ok(());
+      }
+      let holds_key = match self.store.get_node(0, index)? {
+        Some(Node {
+          data: Some(data), ..
+        }) => data.key() == key,
+        _ => false,
+      };
+      if !holds_key {
+        dangling.push((index, key));
+      }
+      Ok(())
+    })?;
+    dangling.sort();
+    report.discrepancies.extend(
+      dangling
+        .into_iter()
+        .map(|(index, key)| Discrepancy::DanglingKey { key, index }),
+    );
+
+    Ok(report)
+  }
+}
@@ -0,0 +1,111 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::{Digest, Hasher};
+use crate::node::SerializableData;
+use crate::proof::MerkleProof;
+use crate::tree::{get_parent_node, get_sibling_node};
+
+// Core lib imports
+use std::collections::HashMap;
+
+// (proof length, level, index) -> hash of a node proven to be part of the tree
+type NodeCache<G> = HashMap<(usize, usize, usize), G>;
+
+// Verify many proofs against one root, returning the same result for each proof as
+// `MerkleProof::verify`. Nodes and siblings of every valid path are cached, so a later proof that
+// reaches a known node with the same remaining siblings is accepted without hashing up to the root.
+// Proofs of any other `arity` than 2 are verified one by one with `MerkleProof::verify_with_arity`.
+pub fn verify_batch<D: SerializableData, H: Hasher>(
+  root: H::Digest,
+  proofs: &[MerkleProof<D, H::Digest>],
+  hasher: &H,
+  arity: usize,
+) -> Vec<Result<bool, MerkleError>> {
+  if arity != 2 {
+    return proofs
+      .iter()
+      .map(|proof| proof.verify_with_arity(root, hasher, arity))
+      .collect();
+  }
+  let mut cache = NodeCache::new();
+  proofs
+    .iter()
+    .map(|proof| verify_cached(root, proof, hasher, &mut cache))
+    .collect()
+}
+
+// Same as `verify_batch`, splitting the proofs into one chunk per thread with its own cache
+#[cfg(feature = "parallel")]
+pub fn verify_batch_parallel<D: SerializableData + Sync, H: Hasher + Sync>(
+  root: H::Digest,
+  proofs: &[MerkleProof<D, H::Digest>],
+  hasher: &H,
+  arity: usize,
+) -> Vec<Result<bool, MerkleError>> {
+  use rayon::prelude::*;
+
+  let chunk_size = proofs.len().div_ceil(rayon::current_num_threads()).max(1);
+  proofs
+    .par_chunks(chunk_size)
+    .flat_map_iter(|chunk| verify_batch(root, chunk, hasher, arity))
+    .collect()
+}
+
+fn verify_cached<D: SerializableData, H: Hasher>(
+  root: H::Digest,
+  proof: &MerkleProof<D, H::Digest>,
+  hasher: &H,
+  cache: &mut NodeCache<H::Digest>,
+) -> Result<bool, MerkleError> {
+  let depth = proof.proof.len();
+  let mut hash = hasher.hash_leaf(&proof.data.to_bytes());
+  let mut path = Vec::with_capacity(depth + 1);
+  let (mut level, mut index) = (0, proof.index);
+
+  for sibling_hash in &proof.proof {
+    if cache.get(&(depth, level, index)) == Some(&hash)
+      && siblings_cached(cache, depth, level, index, &proof.proof[level..])
+    {
+      return Ok(true);
+    }
+
+    path.push((level, index, hash, *sibling_hash));
+    hash = if index.is_multiple_of(2) {
+      hasher.hash_internal(&hash, sibling_hash)
+    } else {
+      hasher.hash_internal(sibling_hash, &hash)
+    };
+    (level, index) = get_parent_node(level, index);
+  }
+
+  if hash != root {
+    return Err(MerkleError::InvalidRootHash {
+      exp: root.as_ref().to_vec(),
+      act: hash.as_ref().to_vec(),
+    });
+  }
+
+  for (level, index, hash, sibling_hash) in path {
+    cache.insert((depth, level, index), hash);
+    cache.insert((depth, level, get_sibling_node(index)), sibling_hash);
+  }
+
+  Ok(true)
+}
+
+// whether every remaining sibling of the path from (level, index) matches a cached node
+fn siblings_cached<G: Digest>(
+  cache: &NodeCache<G>,
+  depth: usize,
+  mut level: usize,
+  mut index: usize,
+  siblings: &[G],
+) -> bool {
+  for sibling_hash in siblings {
+    if cache.get(&(depth, level, get_sibling_node(index))) != Some(sibling_hash) {
+      return false;
+    }
+    (level, index) = get_parent_node(level, index);
+  }
+  true
+}
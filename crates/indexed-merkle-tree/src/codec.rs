@@ -0,0 +1,333 @@
+// Local imports
+use crate::abi::{self, ParamType, Token};
+use crate::errors::DecodeError;
+
+// Core lib imports
+use alloc::{string::String, vec::Vec};
+
+// External imports
+use primitive_types::U256;
+use sha3::{Digest, Keccak256};
+
+// for code generated by the derive macro, which can't name `alloc` in `std` crates
+#[doc(hidden)]
+pub use alloc::vec::Vec as ByteVec;
+
+// Field encodings used by `#[derive(SerializableData)]`:
+// - `packed`: raw bytes, big endian for integers. Dynamically sized values (`String`, `Vec<u8>`)
+//   and `Option`s take the rest of the input, so they can only be the last field.
+// - `length_prefixed`: little endian u32 byte length followed by the packed bytes (default).
+// - `abi`: one 32-byte word as in Solidity's `abi.encode` for static types, through the `abi`
+//   module.
+
+// Cursor over the bytes being decoded, tracking the offset for errors
+pub struct Decoder<'a> {
+  bytes: &'a [u8],
+  offset: usize,
+}
+
+impl<'a> Decoder<'a> {
+  pub fn new(bytes: &'a [u8]) -> Self {
+    Self { bytes, offset: 0 }
+  }
+
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  pub fn error(&self, reason: &'static str) -> DecodeError {
+    DecodeError {
+      offset: self.offset,
+      reason,
+    }
+  }
+
+  pub fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+    let bytes = self
+      .bytes
+      .get(self.offset..self.offset.saturating_add(len))
+      .ok_or(self.error("unexpected end of input"))?;
+    self.offset += len;
+    Ok(bytes)
+  }
+
+  pub fn take_array<const N: usize>(&mut self) -> Result<[u8; N], DecodeError> {
+    Ok(self.take(N)?.try_into().unwrap())
+  }
+
+  pub fn remaining(&self) -> usize {
+    self.bytes.len() - self.offset
+  }
+
+  pub fn take_rest(&mut self) -> &'a [u8] {
+    let bytes = &self.bytes[self.offset..];
+    self.offset = self.bytes.len();
+    bytes
+  }
+
+  // fail if any input is left over
+  pub fn finish(self) -> Result<(), DecodeError> {
+    if self.offset != self.bytes.len() {
+      return Err(self.error("trailing bytes"));
+    }
+    Ok(())
+  }
+}
+
+pub trait PackedField: Sized {
+  fn encode_packed(&self, out: &mut Vec<u8>);
+  fn decode_packed(decoder: &mut Decoder) -> Result<Self, DecodeError>;
+}
+
+// Static Solidity type of a field, converted to and from the tokens of the `abi` module
+pub trait AbiField: Sized {
+  fn param_type() -> ParamType;
+  fn to_token(&self) -> Token;
+  fn from_token(token: Token) -> Result<Self, &'static str>;
+}
+
+// Key derivation for the `#[key]` field: 32-byte values are used as-is, anything else is hashed
+// with keccak256 over its packed encoding.
+pub trait KeyField {
+  fn to_key(&self) -> [u8; 32];
+}
+
+pub fn encode_packed<T: PackedField>(value: &T, out: &mut Vec<u8>) {
+  value.encode_packed(out);
+}
+
+pub fn decode_packed<T: PackedField>(decoder: &mut Decoder) -> Result<T, DecodeError> {
+  T::decode_packed(decoder)
+}
+
+pub fn encode_length_prefixed<T: PackedField>(value: &T, out: &mut Vec<u8>) {
+  let mut bytes = Vec::new();
+  value.encode_packed(&mut bytes);
+  out.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+  out.extend_from_slice(&bytes);
+}
+
+pub fn decode_length_prefixed<T: PackedField>(decoder: &mut Decoder) -> Result<T, DecodeError> {
+  let len = u32::from_le_bytes(decoder.take_array()?) as usize;
+  let start = decoder.offset();
+  let mut inner = Decoder::new(decoder.take(len)?);
+  let value = T::decode_packed(&mut inner).map_err(|err| DecodeError {
+    offset: start + err.offset,
+    reason: err.reason,
+  })?;
+  if inner.offset() != len {
+    return Err(DecodeError {
+      offset: start + inner.offset(),
+      reason: "length prefix does not match value",
+    });
+  }
+  Ok(value)
+}
+
+pub fn encode_abi<T: AbiField>(value: &T, out: &mut Vec<u8>) {
+  out.extend_from_slice(&abi::encode(&[value.to_token()]));
+}
+
+pub fn decode_abi<T: AbiField>(decoder: &mut Decoder) -> Result<T, DecodeError> {
+  let offset = decoder.offset();
+  let word = decoder.take(32)?;
+  let token = abi::decode(&[T::param_type()], word)
+    .map_err(|err| DecodeError {
+      offset: offset + err.offset,
+      reason: err.reason,
+    })?
+    .remove(0);
+  T::from_token(token).map_err(|reason| DecodeError { offset, reason })
+}
+
+macro_rules! impl_uint {
+  ($($ty:ty),*) => {
+    $(
+      impl PackedField for $ty {
+        fn encode_packed(&self, out: &mut Vec<u8>) {
+          out.extend_from_slice(&self.to_be_bytes());
+        }
+
+        fn decode_packed(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+          Ok(<$ty>::from_be_bytes(decoder.take_array()?))
+        }
+      }
+
+      impl AbiField for $ty {
+        fn param_type() -> ParamType {
+          ParamType::Uint
+        }
+
+        fn to_token(&self) -> Token {
+          Token::Uint(U256::from(*self))
+        }
+
+        fn from_token(token: Token) -> Result<Self, &'static str> {
+          match token {
+            Token::Uint(value) => <$ty>::try_from(value).map_err(|_| "integer out of range"),
+            _ => Err("expected uint"),
+          }
+        }
+      }
+
+      impl KeyField for $ty {
+        fn to_key(&self) -> [u8; 32] {
+          keccak_packed(self)
+        }
+      }
+    )*
+  };
+}
+
+impl_uint!(u8, u16, u32, u64, u128);
+
+impl PackedField for bool {
+  fn encode_packed(&self, out: &mut Vec<u8>) {
+    out.push(*self as u8);
+  }
+
+  fn decode_packed(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    match decoder.take_array::<1>()? {
+      [0] => Ok(false),
+      [1] => Ok(true),
+      _ => Err(DecodeError {
+        offset: decoder.offset() - 1,
+        reason: "invalid bool",
+      }),
+    }
+  }
+}
+
+impl AbiField for bool {
+  fn param_type() -> ParamType {
+    ParamType::Bool
+  }
+
+  fn to_token(&self) -> Token {
+    Token::Bool(*self)
+  }
+
+  fn from_token(token: Token) -> Result<Self, &'static str> {
+    match token {
+      Token::Bool(value) => Ok(value),
+      _ => Err("expected bool"),
+    }
+  }
+}
+
+impl<const N: usize> PackedField for [u8; N] {
+  fn encode_packed(&self, out: &mut Vec<u8>) {
+    out.extend_from_slice(self);
+  }
+
+  fn decode_packed(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    decoder.take_array()
+  }
+}
+
+impl AbiField for [u8; 32] {
+  fn param_type() -> ParamType {
+    ParamType::Bytes32
+  }
+
+  fn to_token(&self) -> Token {
+    Token::Bytes32(*self)
+  }
+
+  fn from_token(token: Token) -> Result<Self, &'static str> {
+    match token {
+      Token::Bytes32(value) => Ok(value),
+      _ => Err("expected bytes32"),
+    }
+  }
+}
+
+impl AbiField for [u8; 20] {
+  fn param_type() -> ParamType {
+    ParamType::Address
+  }
+
+  fn to_token(&self) -> Token {
+    Token::Address(*self)
+  }
+
+  fn from_token(token: Token) -> Result<Self, &'static str> {
+    match token {
+      Token::Address(value) => Ok(value),
+      _ => Err("expected address"),
+    }
+  }
+}
+
+impl KeyField for [u8; 32] {
+  fn to_key(&self) -> [u8; 32] {
+    *self
+  }
+}
+
+impl KeyField for [u8; 20] {
+  fn to_key(&self) -> [u8; 32] {
+    keccak_packed(self)
+  }
+}
+
+impl PackedField for Vec<u8> {
+  fn encode_packed(&self, out: &mut Vec<u8>) {
+    out.extend_from_slice(self);
+  }
+
+  fn decode_packed(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    Ok(decoder.take_rest().to_vec())
+  }
+}
+
+impl KeyField for Vec<u8> {
+  fn to_key(&self) -> [u8; 32] {
+    keccak_packed(self)
+  }
+}
+
+impl PackedField for String {
+  fn encode_packed(&self, out: &mut Vec<u8>) {
+    out.extend_from_slice(self.as_bytes());
+  }
+
+  fn decode_packed(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    let offset = decoder.offset();
+    String::from_utf8(decoder.take_rest().to_vec()).map_err(|err| {
+      let err = DecodeError::from(err);
+      DecodeError {
+        offset: offset + err.offset,
+        reason: err.reason,
+      }
+    })
+  }
+}
+
+impl KeyField for String {
+  fn to_key(&self) -> [u8; 32] {
+    keccak_packed(self)
+  }
+}
+
+// Optional values encode as nothing when absent, so they can only be the last field
+impl<T: PackedField> PackedField for Option<T> {
+  fn encode_packed(&self, out: &mut Vec<u8>) {
+    if let Some(value) = self {
+      value.encode_packed(out);
+    }
+  }
+
+  fn decode_packed(decoder: &mut Decoder) -> Result<Self, DecodeError> {
+    if decoder.remaining() == 0 {
+      return Ok(None);
+    }
+    T::decode_packed(decoder).map(Some)
+  }
+}
+
+fn keccak_packed<T: PackedField>(value: &T) -> [u8; 32] {
+  let mut bytes = Vec::new();
+  value.encode_packed(&mut bytes);
+  Keccak256::digest(&bytes).into()
+}
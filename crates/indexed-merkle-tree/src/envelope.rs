@@ -0,0 +1,195 @@
+// Local imports
+use crate::abi::{self, Token};
+use crate::errors::MerkleError;
+use crate::hasher::{Digest, Hasher, KeccakHasher, PairOrder};
+use crate::node::SerializableData;
+use crate::proof::MerkleProof;
+use crate::store::NodeStore;
+use crate::tree::IndexedMerkleTree;
+
+// External imports
+use primitive_types::U256;
+
+// Distribution parameters bound to the root by the envelope
+#[derive(Debug, Clone, Default)]
+pub struct EnvelopeOptions {
+  pub total: U256,
+  pub round_id: u64,
+  pub chain_id: u64,
+  pub token: [u8; 20],
+  pub scheme_id: [u8; 32], // leaf encoding and tree shape, see `TreeScheme::id`
+}
+
+// Commitment to a tree root together with the distribution it belongs to, so that the root cannot
+// be replayed on another chain, round or token. Contracts store the envelope hash,
+//   H(abi.encode(bytes32 root, uint256 total, uint256 leafCount, uint256 leafCapacity,
+//     uint256 roundId, uint256 chainId, address token, bytes32 schemeId))
+// and check claims with the envelope fields and a proof against `root`. H is always keccak256,
+// whatever the hasher of the tree. As the root is a `bytes32`, envelopes are limited to hashers with
+// 32-byte digests.
+// The leaf capacity is the number of leaf positions proofs can address, including the padding of
+// fixed-depth, sharded and OpenZeppelin trees, see `leaf_capacity`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitmentEnvelope {
+  pub root: [u8; 32],
+  pub total: U256,
+  pub leaf_count: u64,
+  pub leaf_capacity: u64,
+  pub round_id: u64,
+  pub chain_id: u64,
+  pub token: [u8; 20],
+  pub scheme_id: [u8; 32],
+}
+
+impl CommitmentEnvelope {
+  pub fn new(
+    root: [u8; 32],
+    leaf_count: u64,
+    leaf_capacity: u64,
+    options: EnvelopeOptions,
+  ) -> Self {
+    Self {
+      root,
+      total: options.total,
+      leaf_count,
+      leaf_capacity,
+      round_id: options.round_id,
+      chain_id: options.chain_id,
+      token: options.token,
+      scheme_id: options.scheme_id,
+    }
+  }
+
+  pub fn encode(&self) -> Vec<u8> {
+    abi::encode(&[
+      Token::Bytes32(self.root),
+      Token::Uint(self.total),
+      Token::Uint(U256::from(self.leaf_count)),
+      Token::Uint(U256::from(self.leaf_capacity)),
+      Token::Uint(U256::from(self.round_id)),
+      Token::Uint(U256::from(self.chain_id)),
+      Token::Address(self.token),
+      Token::Bytes32(self.scheme_id),
+    ])
+  }
+
+  pub fn hash(&self) -> [u8; 32] {
+    KeccakHasher.hash_leaf(&self.encode())
+  }
+
+  // check the envelope against the committed hash and the scheme against its committed id, then
+  // the proof against the root in the pair order and arity of the scheme. The leaf index must also
+  // fall within the committed leaf capacity, as the bits of an index above the proof length are
+  // not checked by the proof.
+  pub fn verify_proof<D: SerializableData, H: Hasher<Digest = [u8; 32]>>(
+    &self,
+    envelope_hash: [u8; 32],
+    scheme: &TreeScheme,
+    proof: &MerkleProof<D, H::Digest>,
+    hasher: &H,
+  ) -> Result<bool, MerkleError> {
+    self.check(envelope_hash, proof)?;
+    let id = scheme.id();
+    if id != self.scheme_id {
+      return Err(MerkleError::InvalidEnvelope {
+        exp: self.scheme_id.to_vec(),
+        act: id.to_vec(),
+      });
+    }
+    match scheme.pair_order {
+      PairOrder::Positional => proof.verify_with_arity(self.root, hasher, scheme.arity),
+      PairOrder::Sorted if scheme.arity == 2 => proof.verify_sorted(self.root, hasher),
+      PairOrder::Sorted => Err(MerkleError::InvalidArity {
+        arity: scheme.arity,
+      }),
+    }
+  }
+
+  fn check<D: SerializableData, G: Digest>(
+    &self,
+    envelope_hash: [u8; 32],
+    proof: &MerkleProof<D, G>,
+  ) -> Result<(), MerkleError> {
+    let hash = self.hash();
+    if hash != envelope_hash {
+      return Err(MerkleError::InvalidEnvelope {
+        exp: envelope_hash.to_vec(),
+        act: hash.to_vec(),
+      });
+    }
+    if proof.index as u64 >= self.leaf_capacity {
+      return Err(MerkleError::IndexOutOfCapacity {
+        index: proof.index,
+        capacity: self.leaf_capacity,
+      });
+    }
+    Ok(())
+  }
+}
+
+// Leaf layout name, e.g. "keccak256/length-prefixed", and the shape of the tree a verifier needs to
+// check proofs: how pairs are ordered, the arity and the fixed depth if the tree is padded to one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TreeScheme {
+  pub name: &'static str,
+  pub pair_order: PairOrder,
+  pub arity: usize,
+  pub depth: Option<usize>,
+}
+
+impl TreeScheme {
+  // keccak256 of the scheme, which reads
+  //   "keccak256/length-prefixed/positional/arity-2/depth-20" ("depth-natural" without a fixed depth)
+  pub fn id(&self) -> [u8; 32] {
+    let pair_order = match self.pair_order {
+      PairOrder::Positional => "positional",
+      PairOrder::Sorted => "sorted",
+    };
+    let depth = self
+      .depth
+      .map_or("natural".to_string(), |depth| depth.to_string());
+    let scheme = format!(
+      "{}/{}/arity-{}/depth-{}",
+      self.name, pair_order, self.arity, depth
+    );
+    KeccakHasher.hash_leaf(scheme.as_bytes())
+  }
+}
+
+// number of leaf positions of a tree of the given arity and height, saturating at `u64::MAX`
+pub fn leaf_capacity(arity: usize, height: usize) -> u64 {
+  u32::try_from(height)
+    .ok()
+    .and_then(|height| (arity as u64).checked_pow(height))
+    .unwrap_or(u64::MAX)
+}
+
+impl<D: SerializableData, H: Hasher<Digest = [u8; 32]>, S: NodeStore<D, H::Digest>>
+  IndexedMerkleTree<D, H, S>
+{
+  pub fn envelope(&self, options: EnvelopeOptions) -> Result<CommitmentEnvelope, MerkleError> {
+    Ok(CommitmentEnvelope::new(
+      self.root.hash,
+      self.leaf_count()? as u64,
+      leaf_capacity(self.arity, self.height),
+      options,
+    ))
+  }
+
+  // verify a proof against an envelope built from this tree
+  pub fn verify_envelope_proof(
+    &self,
+    envelope: &CommitmentEnvelope,
+    envelope_hash: [u8; 32],
+    proof: MerkleProof<D, H::Digest>,
+  ) -> Result<bool, MerkleError> {
+    if envelope.root != self.root.hash {
+      return Err(MerkleError::InvalidRootHash {
+        exp: self.root.hash.to_vec(),
+        act: envelope.root.to_vec(),
+      });
+    }
+    envelope.check(envelope_hash, &proof)?;
+    self.verify_proof(proof)
+  }
+}
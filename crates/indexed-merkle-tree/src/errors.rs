@@ -1,7 +1,128 @@
+// Local imports
+#[cfg(feature = "std")]
+use crate::audit::Discrepancy;
+
+// Core lib imports
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "alloc")]
 #[derive(Debug)]
 pub enum MerkleError {
-  InvalidRootHash { exp: [u8; 32], act: [u8; 32] },
-  InvalidKey { key: [u8; 32] },
-  NodeNotFound { level: usize, index: usize },
-  InvalidDataLength { len: usize },
+  InvalidRootHash {
+    // digests of the tree's hasher
+    exp: Vec<u8>,
+    act: Vec<u8>,
+  },
+  InvalidEnvelope {
+    exp: Vec<u8>,
+    act: Vec<u8>,
+  },
+  InvalidKey {
+    key: [u8; 32],
+  },
+  InvalidProofIndex {
+    exp: usize,
+    act: usize,
+  },
+  // index past the leaf capacity committed by an envelope
+  IndexOutOfCapacity {
+    index: usize,
+    capacity: u64,
+  },
+  NodeNotFound {
+    level: usize,
+    index: usize,
+  },
+  InvalidDataLength {
+    len: usize,
+  },
+  InvalidProofEncoding {
+    offset: usize,
+  },
+  // number of siblings not splitting into levels of `arity - 1`
+  InvalidProofLength {
+    len: usize,
+    arity: usize,
+  },
+  InvalidSnapshot {
+    reason: &'static str,
+  },
+  // OpenZeppelin StandardMerkleTree dump
+  InvalidDump {
+    reason: &'static str,
+  },
+  InvalidShardBits {
+    bits: u32,
+  },
+  InvalidShardHeight {
+    shard: usize,
+    height: usize,
+  },
+  CapacityExceeded {
+    len: usize,
+    capacity: usize,
+  },
+  InvalidArity {
+    arity: usize,
+  },
+  UnsupportedOrdering {
+    // leaf ordering name, see `LeafOrdering::name`
+    ordering: String,
+  },
+  InvalidWeight {
+    index: usize,
+  },
+  DepthExceeded {
+    depth: usize,
+    max: usize,
+  },
+  ShardNotLoaded {
+    shard: usize,
+  },
+  #[cfg(feature = "std")]
+  AuditFailed {
+    discrepancies: Vec<Discrepancy>,
+  },
+  Decode {
+    err: DecodeError,
+  },
+  Storage {
+    reason: String,
+  },
+  #[cfg(feature = "std")]
+  Io {
+    err: std::io::Error,
+  },
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for MerkleError {
+  fn from(err: std::io::Error) -> Self {
+    MerkleError::Io { err }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl From<DecodeError> for MerkleError {
+  fn from(err: DecodeError) -> Self {
+    MerkleError::Decode { err }
+  }
+}
+
+// Malformed leaf data, `offset` is the position in the input where decoding failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+  pub offset: usize,
+  pub reason: &'static str,
+}
+
+#[cfg(feature = "alloc")]
+impl From<alloc::string::FromUtf8Error> for DecodeError {
+  fn from(err: alloc::string::FromUtf8Error) -> Self {
+    DecodeError {
+      offset: err.utf8_error().valid_up_to(),
+      reason: "invalid utf-8",
+    }
+  }
 }
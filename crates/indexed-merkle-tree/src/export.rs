@@ -0,0 +1,197 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::{Digest, Hasher};
+use crate::node::SerializableData;
+use crate::store::NodeStore;
+use crate::tree::{level_width, IndexedMerkleTree};
+
+// Core lib imports
+use std::fmt::Write;
+
+// External imports
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+  // key of the leaf whose path to the root is highlighted, along with its proof siblings
+  pub highlight: Option<[u8; 32]>,
+  // most nodes exported per level. Wider levels keep a window centered on the highlighted path,
+  // or their leftmost nodes.
+  pub max_width: Option<usize>,
+}
+
+// Part a node plays in the proof of the highlighted leaf
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeRole {
+  Path,
+  Sibling,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NodeView {
+  pub index: usize,
+  pub hash: String,
+  // hex encoded bytes of leaves holding data
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub data: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub role: Option<NodeRole>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LevelView {
+  pub level: usize,
+  // stored nodes at this level, of which `nodes` are those from index `first` on
+  pub width: usize,
+  pub first: usize,
+  pub nodes: Vec<NodeView>,
+}
+
+// Nodes of a tree level by level from the leaves up to the root, serializable as JSON. Empty
+// subtrees padding a fixed-depth tree are not stored, so only the leftmost node of each padding
+// level appears.
+#[derive(Debug, Clone, Serialize)]
+pub struct TreeView {
+  pub root: String,
+  pub height: usize,
+  pub arity: usize,
+  pub levels: Vec<LevelView>,
+}
+
+impl TreeView {
+  // Graphviz digraph with the root on top, the highlighted path filled in gold and its siblings in
+  // light blue. Truncated levels end with a note of the nodes left out on either side.
+  pub fn to_dot(&self) -> String {
+    let mut out = String::new();
+    let w = &mut out;
+    writeln!(w, "digraph merkle_tree {{").unwrap();
+    writeln!(w, "  node [shape=box, fontname=\"monospace\"];").unwrap();
+
+    for level in self.levels.iter().rev() {
+      let mut ranked = Vec::new();
+      if level.first > 0 {
+        let id = format!("n{}_before", level.level);
+        writeln!(
+          w,
+          "  {} [label=\"{} more\", shape=plaintext];",
+          id, level.first
+        )
+        .unwrap();
+        ranked.push(id);
+      }
+      for node in &level.nodes {
+        let id = format!("n{}_{}", level.level, node.index);
+        let fill = match node.role {
+          Some(NodeRole::Path) => ", style=filled, fillcolor=gold",
+          Some(NodeRole::Sibling) => ", style=filled, fillcolor=lightblue",
+          None => "",
+        };
+        writeln!(
+          w,
+          "  {} [label=\"{}:{}\\n{}\", tooltip=\"{}\"{}];",
+          id,
+          level.level,
+          node.index,
+          short_hash(&node.hash),
+          node.hash,
+          fill
+        )
+        .unwrap();
+        ranked.push(id);
+      }
+      let after = level.width - level.first - level.nodes.len();
+      if after > 0 {
+        let id = format!("n{}_after", level.level);
+        writeln!(w, "  {} [label=\"{} more\", shape=plaintext];", id, after).unwrap();
+        ranked.push(id);
+      }
+      writeln!(w, "  {{ rank=same; {}; }}", ranked.join("; ")).unwrap();
+    }
+
+    // edges from each exported node to its exported children
+    for pair in self.levels.windows(2) {
+      let (children, parents) = (&pair[0], &pair[1]);
+      for parent in &parents.nodes {
+        let first = parent.index * self.arity;
+        for child in &children.nodes {
+          if (first..first + self.arity).contains(&child.index) {
+            writeln!(
+              w,
+              "  n{}_{} -> n{}_{};",
+              parents.level, parent.index, children.level, child.index
+            )
+            .unwrap();
+          }
+        }
+      }
+    }
+    writeln!(w, "}}").unwrap();
+    out
+  }
+}
+
+// first 4 and last 2 bytes of a hex digest
+fn short_hash(hash: &str) -> String {
+  match hash.len() > 16 {
+    true => format!("{}…{}", &hash[..10], &hash[hash.len() - 4..]),
+    false => hash.to_string(),
+  }
+}
+
+impl<D: SerializableData, H: Hasher, S: NodeStore<D, H::Digest>> IndexedMerkleTree<D, H, S> {
+  pub fn export(&self, options: &ExportOptions) -> Result<TreeView, MerkleError> {
+    let highlight = match options.highlight {
+      Some(key) => Some(self.get_index(key)?),
+      None => None,
+    };
+    let base_height = self.base_height()?;
+
+    let mut levels = Vec::with_capacity(self.height + 1);
+    let mut path = highlight;
+    for level in 0..=self.height {
+      let width = level_width(base_height, level, self.arity);
+      let shown = options.max_width.unwrap_or(width).clamp(1, width);
+      let first = match path {
+        Some(index) => index.saturating_sub(shown / 2).min(width - shown),
+        None => 0,
+      };
+
+      let mut nodes = Vec::with_capacity(shown);
+      for index in first..first + shown {
+        let node = self.get_node(level, index)?;
+        let role = path.and_then(|path| match index == path {
+          true => Some(NodeRole::Path),
+          false if index / self.arity == path / self.arity => Some(NodeRole::Sibling),
+          false => None,
+        });
+        nodes.push(NodeView {
+          index,
+          hash: node.hash.to_hex(),
+          data: node
+            .data
+            .map(|data| format!("0x{}", hex::encode(data.to_bytes()))),
+          role,
+        });
+      }
+      levels.push(LevelView {
+        level,
+        width,
+        first,
+        nodes,
+      });
+      path = path.map(|index| index / self.arity);
+    }
+
+    Ok(TreeView {
+      root: self.root.hash.to_hex(),
+      height: self.height,
+      arity: self.arity,
+      levels,
+    })
+  }
+
+  pub fn to_dot(&self, options: &ExportOptions) -> Result<String, MerkleError> {
+    Ok(self.export(options)?.to_dot())
+  }
+}
@@ -1,14 +1,128 @@
-use sha3::{Digest, Keccak256};
+// Core lib imports
+#[cfg(feature = "alloc")]
+use alloc::{format, string::String, vec, vec::Vec};
+
+// External imports
+#[cfg(feature = "poseidon")]
+use ark_bn254::Fr;
+#[cfg(feature = "poseidon")]
+use ark_ff::{BigInteger, PrimeField};
+use blake2::Blake2b512;
+#[cfg(feature = "poseidon")]
+use light_poseidon::{Poseidon, PoseidonHasher as _};
+use ripemd::Ripemd160;
+use sha2::Sha384;
+use sha3::{Digest as _, Keccak256};
+#[cfg(feature = "starknet")]
+use starknet_crypto::{poseidon_hash, FieldElement, PoseidonHasher};
+
+// Fixed-size hash output, implemented for byte arrays of any length
+pub trait Digest:
+  Copy + Eq + Ord + core::hash::Hash + core::fmt::Debug + AsRef<[u8]> + Send + Sync + 'static
+{
+  const LEN: usize;
+  const ZERO: Self;
+
+  fn from_slice(bytes: &[u8]) -> Option<Self>;
+
+  // 0x-prefixed lowercase hex
+  #[cfg(feature = "alloc")]
+  fn to_hex(&self) -> String {
+    format!("0x{}", hex::encode(self.as_ref()))
+  }
+
+  // accepts hex with or without the 0x prefix
+  #[cfg(feature = "alloc")]
+  fn from_hex(hex: &str) -> Option<Self> {
+    let bytes = hex::decode(hex.strip_prefix("0x").unwrap_or(hex)).ok()?;
+    Self::from_slice(&bytes)
+  }
+}
+
+impl<const N: usize> Digest for [u8; N] {
+  const LEN: usize = N;
+  const ZERO: Self = [0; N];
+
+  fn from_slice(bytes: &[u8]) -> Option<Self> {
+    bytes.try_into().ok()
+  }
+}
 
 pub trait Hasher {
-  fn hash_leaf(&self, data: &[u8]) -> [u8; 32];
-  fn hash_internal(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32];
-  fn zero() -> [u8; 32];
+  type Digest: Digest;
+
+  fn hash_leaf(&self, data: &[u8]) -> Self::Digest;
+  fn hash_internal(&self, left: &Self::Digest, right: &Self::Digest) -> Self::Digest;
+  fn zero() -> Self::Digest;
+
+  // hash of the children of a node in a k-ary tree, from left to right. Two children must hash
+  // like `hash_internal`. The default reduces them pairwise, hashers with a native k-input
+  // function (e.g. Poseidon) should override it.
+  fn hash_children(&self, children: &[Self::Digest]) -> Self::Digest {
+    // reducing level by level and carrying an odd child up is the same as splitting at the largest
+    // power of two below the number of children, which doesn't allocate
+    if children.len() == 1 {
+      return children[0];
+    }
+    let mut split = 1;
+    while split * 2 < children.len() {
+      split *= 2;
+    }
+    self.hash_internal(
+      &self.hash_children(&children[..split]),
+      &self.hash_children(&children[split..]),
+    )
+  }
+
+  // hashes of empty subtrees, where entry `i` is the root of a subtree of height `i` built only
+  // from zero leaves
+  #[cfg(feature = "alloc")]
+  fn zero_hashes(&self, depth: usize) -> Vec<Self::Digest> {
+    self.zero_hashes_with_arity(depth, 2)
+  }
+
+  // same as `zero_hashes` for a k-ary tree
+  #[cfg(feature = "alloc")]
+  fn zero_hashes_with_arity(&self, depth: usize, arity: usize) -> Vec<Self::Digest> {
+    let mut hashes = Vec::with_capacity(depth);
+    let mut hash = Self::zero();
+    for _ in 0..depth {
+      hashes.push(hash);
+      hash = self.hash_children(&vec![hash; arity]);
+    }
+    hashes
+  }
 }
 
+// How the two children of a node are ordered before hashing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PairOrder {
+  // left then right, proofs carry the path in their index and verify with `MerkleProof::verify`
+  #[default]
+  Positional,
+  // smaller hash first, proofs verify with `MerkleProof::verify_sorted` (OpenZeppelin
+  // `MerkleProof.verify`)
+  Sorted,
+}
+
+pub(crate) fn hash_pair<H: Hasher>(
+  hasher: &H,
+  order: PairOrder,
+  left: &H::Digest,
+  right: &H::Digest,
+) -> H::Digest {
+  match order {
+    PairOrder::Sorted if right < left => hasher.hash_internal(right, left),
+    _ => hasher.hash_internal(left, right),
+  }
+}
+
+#[derive(Clone)]
 pub struct KeccakHasher;
 
 impl Hasher for KeccakHasher {
+  type Digest = [u8; 32];
+
   fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
     let mut hasher = Keccak256::new();
     hasher.update(data);
@@ -22,7 +136,262 @@ impl Hasher for KeccakHasher {
     hasher.finalize().into()
   }
 
+  fn hash_children(&self, children: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for child in children {
+      hasher.update(child);
+    }
+    hasher.finalize().into()
+  }
+
   fn zero() -> [u8; 32] {
     [0; 32]
   }
 }
+
+// OpenZeppelin StandardMerkleTree hashing: leaves are `keccak256(keccak256(data))` and pairs are
+// hashed with the smaller hash first. An empty (zero) node carries its sibling up unchanged, so a
+// complete tree with leaves on its last two levels is laid out as a perfect tree with an empty node
+// next to each upper leaf, and padding a fixed-depth tree keeps its root.
+#[derive(Clone)]
+pub struct StandardHasher;
+
+impl Hasher for StandardHasher {
+  type Digest = [u8; 32];
+
+  fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
+    let hash = Keccak256::digest(data);
+    Keccak256::digest(hash).into()
+  }
+
+  fn hash_internal(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    if *right == Self::zero() {
+      *left
+    } else if *left == Self::zero() {
+      *right
+    } else {
+      KeccakHasher.hash_internal(left.min(right), left.max(right))
+    }
+  }
+
+  fn zero() -> [u8; 32] {
+    [0; 32]
+  }
+}
+
+// 20-byte digests, as used by Bitcoin-style chains
+#[derive(Clone)]
+pub struct Ripemd160Hasher;
+
+impl Hasher for Ripemd160Hasher {
+  type Digest = [u8; 20];
+
+  fn hash_leaf(&self, data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(data).into()
+  }
+
+  fn hash_internal(&self, left: &[u8; 20], right: &[u8; 20]) -> [u8; 20] {
+    Ripemd160::new()
+      .chain_update(left)
+      .chain_update(right)
+      .finalize()
+      .into()
+  }
+
+  fn zero() -> [u8; 20] {
+    [0; 20]
+  }
+}
+
+#[derive(Clone)]
+pub struct Sha384Hasher;
+
+impl Hasher for Sha384Hasher {
+  type Digest = [u8; 48];
+
+  fn hash_leaf(&self, data: &[u8]) -> [u8; 48] {
+    Sha384::digest(data).into()
+  }
+
+  fn hash_internal(&self, left: &[u8; 48], right: &[u8; 48]) -> [u8; 48] {
+    Sha384::new()
+      .chain_update(left)
+      .chain_update(right)
+      .finalize()
+      .into()
+  }
+
+  fn zero() -> [u8; 48] {
+    [0; 48]
+  }
+}
+
+// 64-byte digests for archival roots
+#[derive(Clone)]
+pub struct Blake2b512Hasher;
+
+impl Hasher for Blake2b512Hasher {
+  type Digest = [u8; 64];
+
+  fn hash_leaf(&self, data: &[u8]) -> [u8; 64] {
+    Blake2b512::digest(data).into()
+  }
+
+  fn hash_internal(&self, left: &[u8; 64], right: &[u8; 64]) -> [u8; 64] {
+    Blake2b512::new()
+      .chain_update(left)
+      .chain_update(right)
+      .finalize()
+      .into()
+  }
+
+  fn zero() -> [u8; 64] {
+    [0; 64]
+  }
+}
+
+// Starknet Poseidon over field elements, digests holding a felt252 in big-endian. Leaf data is read
+// as 32-byte big-endian words (the last one possibly shorter), each reduced modulo the Stark prime,
+// and hashed with `poseidon_hash_many` (Cairo `poseidon_hash_span`). Pairs hash with
+// `poseidon_hash` (Cairo `hades_permutation(left, right, 2)`).
+#[cfg(feature = "starknet")]
+#[derive(Clone)]
+pub struct StarknetHasher;
+
+#[cfg(feature = "starknet")]
+impl StarknetHasher {
+  // field element of a big-endian word of up to 32 bytes, wrapping around the prime
+  pub fn felt(word: &[u8]) -> FieldElement {
+    let mut bytes = [0; 32];
+    bytes[32 - word.len()..].copy_from_slice(word);
+    let high = FieldElement::from_byte_slice_be(&bytes[..16]).unwrap();
+    let low = FieldElement::from_byte_slice_be(&bytes[16..]).unwrap();
+    high * (FieldElement::from(u128::MAX) + FieldElement::ONE) + low
+  }
+}
+
+#[cfg(feature = "starknet")]
+impl Hasher for StarknetHasher {
+  type Digest = [u8; 32];
+
+  fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
+    let mut hasher = PoseidonHasher::new();
+    for word in data.chunks(32) {
+      hasher.update(Self::felt(word));
+    }
+    hasher.finalize().to_bytes_be()
+  }
+
+  fn hash_internal(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    poseidon_hash(Self::felt(left), Self::felt(right)).to_bytes_be()
+  }
+
+  fn zero() -> [u8; 32] {
+    [0; 32]
+  }
+}
+
+// circomlib Poseidon over the BN254 scalar field (Noir `std::hash::poseidon::bn254`), digests
+// holding a field element in big-endian. Leaf data is read as 32-byte big-endian words (the last one
+// possibly shorter), each reduced modulo the field order, and hashed as the inputs of a single
+// Poseidon of up to `POSEIDON_WIDTH` inputs. Longer leaves chain: each further run of up to
+// `POSEIDON_WIDTH - 1` words is hashed after the previous hash. Pairs hash with `Poseidon(2)`.
+#[cfg(feature = "poseidon")]
+#[derive(Clone)]
+pub struct Bn254PoseidonHasher;
+
+// most inputs of a single Poseidon
+#[cfg(feature = "poseidon")]
+pub const POSEIDON_WIDTH: usize = 12;
+
+// leaf fields hashed by each Poseidon, after the leaf tag in the first one and the running hash in
+// the next. Leaves are zero-padded to a multiple of it, so circuits take a fixed-width leaf.
+#[cfg(feature = "poseidon")]
+pub const POSEIDON_LEAF_FIELDS: usize = POSEIDON_WIDTH - 1;
+
+// first input of leaf hashes, which on top of their width keeps them apart from `Poseidon(2)` pairs
+#[cfg(feature = "poseidon")]
+pub const POSEIDON_LEAF_TAG: u8 = 1;
+
+#[cfg(feature = "poseidon")]
+impl Bn254PoseidonHasher {
+  // field element of a big-endian word of up to 32 bytes, wrapping around the field order
+  pub fn field(word: &[u8]) -> [u8; 32] {
+    to_digest(Fr::from_be_bytes_mod_order(word))
+  }
+
+  // hash of the field elements of a leaf, as `hash_leaf` computes it from their bytes:
+  // `Poseidon(tag, fields[0..11])`, then `Poseidon(hash, fields[11..22])` and so on
+  pub fn hash_fields(&self, fields: &[[u8; 32]]) -> [u8; 32] {
+    let mut fields: Vec<Fr> = fields
+      .iter()
+      .map(|field| Fr::from_be_bytes_mod_order(field))
+      .collect();
+    let width = fields.len().div_ceil(POSEIDON_LEAF_FIELDS).max(1) * POSEIDON_LEAF_FIELDS;
+    fields.resize(width, Fr::from(0u8));
+
+    let mut hash = Fr::from(POSEIDON_LEAF_TAG);
+    for chunk in fields.chunks(POSEIDON_LEAF_FIELDS) {
+      let mut inputs = vec![hash];
+      inputs.extend_from_slice(chunk);
+      hash = poseidon(&inputs);
+    }
+    to_digest(hash)
+  }
+}
+
+#[cfg(feature = "poseidon")]
+fn poseidon(inputs: &[Fr]) -> Fr {
+  Poseidon::<Fr>::new_circom(inputs.len())
+    .and_then(|mut poseidon| poseidon.hash(inputs))
+    .unwrap()
+}
+
+#[cfg(feature = "poseidon")]
+fn to_digest(field: Fr) -> [u8; 32] {
+  field.into_bigint().to_bytes_be().try_into().unwrap()
+}
+
+#[cfg(feature = "poseidon")]
+impl Hasher for Bn254PoseidonHasher {
+  type Digest = [u8; 32];
+
+  fn hash_leaf(&self, data: &[u8]) -> [u8; 32] {
+    let fields: Vec<[u8; 32]> = data.chunks(32).map(Self::field).collect();
+    self.hash_fields(&fields)
+  }
+
+  fn hash_internal(&self, left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    to_digest(poseidon(&[
+      Fr::from_be_bytes_mod_order(left),
+      Fr::from_be_bytes_mod_order(right),
+    ]))
+  }
+
+  fn zero() -> [u8; 32] {
+    [0; 32]
+  }
+}
+
+// serde helpers encoding digests as 0x-prefixed hex strings, for use with `#[serde(with = ...)]`
+#[cfg(feature = "alloc")]
+pub mod hex_digests {
+  use super::*;
+  use serde::{de, Deserialize, Deserializer, Serializer};
+
+  pub fn serialize<G: Digest, S: Serializer>(
+    digests: &[G],
+    serializer: S,
+  ) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(digests.iter().map(Digest::to_hex))
+  }
+
+  pub fn deserialize<'de, G: Digest, D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Vec<G>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+      .iter()
+      .map(|hex| G::from_hex(hex).ok_or_else(|| de::Error::custom("invalid digest")))
+      .collect()
+  }
+}
@@ -0,0 +1,107 @@
+// Local imports
+use crate::errors::MerkleError;
+
+// Core lib imports
+use std::collections::HashMap;
+
+// Key -> leaf index lookup. When a key is inserted more than once the last index wins.
+pub trait KeyIndex: Default {
+  fn get(&self, key: &[u8; 32]) -> Option<usize>;
+  fn insert(&mut self, key: [u8; 32], index: usize) -> Result<(), MerkleError>;
+  fn len(&self) -> usize;
+  fn iter(&self) -> Box<dyn Iterator<Item = ([u8; 32], usize)> + '_>;
+
+  // called once all keys have been inserted
+  fn seal(&mut self) {}
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+impl KeyIndex for HashMap<[u8; 32], usize> {
+  fn get(&self, key: &[u8; 32]) -> Option<usize> {
+    HashMap::get(self, key).copied()
+  }
+
+  fn insert(&mut self, key: [u8; 32], index: usize) -> Result<(), MerkleError> {
+    HashMap::insert(self, key, index);
+    Ok(())
+  }
+
+  fn len(&self) -> usize {
+    HashMap::len(self)
+  }
+
+  fn iter(&self) -> Box<dyn Iterator<Item = ([u8; 32], usize)> + '_> {
+    Box::new(HashMap::iter(self).map(|(key, index)| (*key, *index)))
+  }
+}
+
+// Immutable index of keys sorted for binary search, with 32-bit leaf positions. Takes 36 bytes
+// per key against roughly 2-3x that for a `HashMap`, at the cost of O(log n) lookups.
+#[derive(Clone, Default)]
+pub struct SortedKeyIndex {
+  keys: Vec<[u8; 32]>,
+  positions: Vec<u32>,
+  sealed: bool,
+}
+
+impl KeyIndex for SortedKeyIndex {
+  fn get(&self, key: &[u8; 32]) -> Option<usize> {
+    if !self.sealed {
+      // only hit while the tree is being built, latest insert wins
+      let found = self.keys.iter().rposition(|k| k == key)?;
+      return Some(self.positions[found] as usize);
+    }
+    let found = self.keys.binary_search(key).ok()?;
+    Some(self.positions[found] as usize)
+  }
+
+  fn insert(&mut self, key: [u8; 32], index: usize) -> Result<(), MerkleError> {
+    let position =
+      u32::try_from(index).map_err(|_| MerkleError::InvalidDataLength { len: index })?;
+    self.keys.push(key);
+    self.positions.push(position);
+    self.sealed = false;
+    Ok(())
+  }
+
+  fn len(&self) -> usize {
+    self.keys.len()
+  }
+
+  fn iter(&self) -> Box<dyn Iterator<Item = ([u8; 32], usize)> + '_> {
+    Box::new(
+      self
+        .keys
+        .iter()
+        .zip(&self.positions)
+        .map(|(key, position)| (*key, *position as usize)),
+    )
+  }
+
+  fn seal(&mut self) {
+    if self.sealed {
+      return;
+    }
+
+    // stable sort keeps duplicates in insertion order, so keeping the last of each run matches
+    // the overwrite semantics of a map
+    let mut order: Vec<usize> = (0..self.keys.len()).collect();
+    order.sort_by(|a, b| self.keys[*a].cmp(&self.keys[*b]));
+    order.dedup_by(|next, prev| {
+      let duplicate = self.keys[*next] == self.keys[*prev];
+      if duplicate {
+        *prev = *next;
+      }
+      duplicate
+    });
+
+    self.keys = order.iter().map(|i| self.keys[*i]).collect();
+    self.positions = order.iter().map(|i| self.positions[*i]).collect();
+    self.keys.shrink_to_fit();
+    self.positions.shrink_to_fit();
+    self.sealed = true;
+  }
+}
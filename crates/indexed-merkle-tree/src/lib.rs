@@ -1,5 +1,50 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+// hashers and proof verification, available in `no_std` environments without an allocator
 pub mod errors;
 pub mod hasher;
+pub mod salt;
+pub mod verify;
+
+// proof types and leaf encodings
+#[cfg(feature = "alloc")]
+pub mod abi;
+#[cfg(feature = "alloc")]
+pub mod codec;
+#[cfg(feature = "alloc")]
 pub mod node;
+#[cfg(feature = "alloc")]
 pub mod proof;
+
+// tree building, storage and snapshots
+#[cfg(feature = "std")]
+pub mod audit;
+#[cfg(feature = "std")]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod envelope;
+#[cfg(feature = "std")]
+pub mod export;
+#[cfg(feature = "std")]
+pub mod index;
+#[cfg(feature = "std")]
+pub mod openzeppelin;
+#[cfg(feature = "std")]
+pub mod ordering;
+#[cfg(feature = "redb")]
+pub mod redb_store;
+#[cfg(feature = "std")]
+pub mod shard;
+#[cfg(feature = "std")]
+pub mod snapshot;
+#[cfg(feature = "std")]
+pub mod store;
+#[cfg(feature = "std")]
+pub mod stream;
+#[cfg(feature = "std")]
 pub mod tree;
+#[cfg(feature = "std")]
+pub mod weighted;
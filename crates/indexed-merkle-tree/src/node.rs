@@ -1,11 +1,21 @@
+// Local imports
+use crate::errors::DecodeError;
+use crate::hasher::Digest;
+
+// Core lib imports
+use alloc::vec::Vec;
+
+#[cfg(feature = "derive")]
+pub use indexed_merkle_tree_derive::SerializableData;
+
 #[derive(Debug, Clone)]
-pub struct Node<D: SerializableData> {
-  pub hash: [u8; 32],
+pub struct Node<D: SerializableData, G: Digest = [u8; 32]> {
+  pub hash: G,
   pub data: Option<D>,
 }
 
 pub trait SerializableData: Clone {
   fn to_bytes(&self) -> Vec<u8>;
-  fn from_bytes(bytes: Vec<u8>) -> Self;
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError>;
   fn key(&self) -> [u8; 32];
 }
@@ -0,0 +1,326 @@
+// Local imports
+use crate::abi::{self, ParamType, Token};
+use crate::errors::MerkleError;
+use crate::hasher::{Digest, Hasher, StandardHasher};
+use crate::node::{Node, SerializableData};
+use crate::ordering::{order_leaves, LeafOrdering, TreeMetadata};
+use crate::proof::MerkleProof;
+use crate::store::{MemoryStore, NodeStore, TreeHeader};
+use crate::tree::{build_tree, IndexedMerkleTree};
+
+// External imports
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub const STANDARD_FORMAT: &str = "standard-v1";
+
+// `StandardMerkleTree.dump()` of `@openzeppelin/merkle-tree`. The tree is a complete binary tree
+// stored as an array, with the children of node `i` at `2i + 1` and `2i + 2` and the leaves at the
+// end. Values are the leaf fields as JSON, `abi.encode`d with the leaf encoding types.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandardTreeDump {
+  pub format: String,
+  #[serde(rename = "leafEncoding")]
+  pub leaf_encoding: Vec<String>,
+  pub tree: Vec<String>,
+  pub values: Vec<StandardValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StandardValue {
+  pub value: Vec<Value>,
+  #[serde(rename = "treeIndex")]
+  pub tree_index: usize,
+}
+
+// Trees hashed with `StandardHasher` hold the array tree at level 0 as a perfect tree: leaves on
+// the last array level keep their position, and leaves one level up sit at twice their position
+// followed by an empty node. As level 0 then has gaps, the leaf count is kept in the tree header.
+impl<D: SerializableData> IndexedMerkleTree<D, StandardHasher> {
+  // same tree as `StandardMerkleTree.of` for leaf data encoded like its values, with the leaves
+  // sorted by hash
+  pub fn standard(data: Vec<D>) -> Result<Self, MerkleError> {
+    if data.is_empty() {
+      return Err(MerkleError::InvalidDataLength { len: 0 });
+    }
+    // the array holds the sorted leaves from its end
+    let mut leaves = order_leaves(data, &LeafOrdering::LeafHash, &StandardHasher);
+    leaves.reverse();
+    Self::build_standard(
+      leaves,
+      MemoryStore::default(),
+      LeafOrdering::<D>::LeafHash.name(),
+    )
+  }
+
+  pub fn from_standard_dump(dump: &StandardTreeDump) -> Result<Self, MerkleError> {
+    Self::from_standard_dump_into(dump, MemoryStore::default())
+  }
+}
+
+impl<D: SerializableData, S: NodeStore<D>> IndexedMerkleTree<D, StandardHasher, S> {
+  // Load a dump into the given store. Each value must decode into the leaf data and encode back
+  // to the same bytes, and every leaf and internal node of the dump must match the rebuilt tree.
+  pub fn from_standard_dump_into(dump: &StandardTreeDump, store: S) -> Result<Self, MerkleError> {
+    if dump.format != STANDARD_FORMAT {
+      return Err(invalid("unknown format"));
+    }
+    let types = parse_types(&dump.leaf_encoding)?;
+    let count = dump.values.len();
+    if count == 0 || dump.tree.len() != 2 * count - 1 {
+      return Err(invalid("tree size does not match the values"));
+    }
+    let tree = dump
+      .tree
+      .iter()
+      .map(|hash| <[u8; 32]>::from_hex(hash))
+      .collect::<Option<Vec<_>>>()
+      .ok_or(invalid("invalid node hash"))?;
+
+    // leaves in array order
+    let mut leaves: Vec<Option<([u8; 32], D)>> = (0..count).map(|_| None).collect();
+    for value in &dump.values {
+      let Some(leaf) = value
+        .tree_index
+        .checked_sub(count - 1)
+        .and_then(|index| leaves.get_mut(index))
+      else {
+        return Err(invalid("tree index is not a leaf"));
+      };
+      if leaf.is_some() {
+        return Err(invalid("duplicate tree index"));
+      }
+      if value.value.len() != types.len() {
+        return Err(invalid("value does not match the leaf encoding"));
+      }
+      let tokens = types
+        .iter()
+        .zip(&value.value)
+        .map(|(ty, value)| to_token(ty, value))
+        .collect::<Option<Vec<_>>>()
+        .ok_or(invalid("value does not match the leaf encoding"))?;
+
+      let bytes = abi::encode(&tokens);
+      let data = D::from_bytes(bytes.clone())?;
+      if data.to_bytes() != bytes {
+        return Err(invalid("value does not encode like the leaf data"));
+      }
+      let hash = StandardHasher.hash_leaf(&bytes);
+      if hash != tree[value.tree_index] {
+        return Err(invalid("leaf hash mismatch"));
+      }
+      *leaf = Some((hash, data));
+    }
+
+    let leaves = leaves.into_iter().flatten().collect();
+    let built = Self::build_standard(leaves, store, LeafOrdering::<D>::Insertion.name())?;
+    for (i, hash) in tree.iter().enumerate().take(count - 1) {
+      let (level, index) = position(built.height, i);
+      if built.get_node(level, index)?.hash != *hash {
+        return Err(invalid("node hash mismatch"));
+      }
+    }
+    Ok(built)
+  }
+
+  // Dump of a tree whose leaves are laid out as an array tree, such as trees built by `standard`
+  // or loaded from a dump, and binary trees of a power of two leaves. Leaf data must be the
+  // `abi.encode` of the given types.
+  pub fn to_standard_dump(&self, leaf_encoding: &[&str]) -> Result<StandardTreeDump, MerkleError> {
+    if self.arity != 2 {
+      return Err(MerkleError::InvalidArity { arity: self.arity });
+    }
+    let leaf_encoding: Vec<String> = leaf_encoding.iter().map(|ty| ty.to_string()).collect();
+    let types = parse_types(&leaf_encoding)?;
+    let count = self.leaf_count()?;
+    let len = (2 * count).saturating_sub(1);
+    // padding levels of a fixed-depth tree carry the root up unchanged
+    let height = self.base_height()?;
+    if count == 0 || len.ilog2() as usize != height {
+      return Err(invalid("leaves are not laid out as an array tree"));
+    }
+
+    let mut tree = Vec::with_capacity(len);
+    let mut values = Vec::with_capacity(count);
+    for i in 0..len {
+      let (level, index) = position(height, i);
+      let node = self.get_node(level, index)?;
+      tree.push(node.hash.to_hex());
+      if i < count - 1 {
+        continue;
+      }
+
+      // leaves one level up are stored at level 0 next to an empty node, which carries them up
+      let leaf = match level {
+        0 => node.clone(),
+        _ => self.get_node(0, 2 * index)?,
+      };
+      let Some(data) = leaf.data.filter(|_| leaf.hash == node.hash) else {
+        return Err(invalid("leaves are not laid out as an array tree"));
+      };
+      let bytes = data.to_bytes();
+      let tokens = abi::decode(&types, &bytes)?;
+      if abi::encode(&tokens) != bytes {
+        return Err(invalid("leaf data is not encoded with the leaf encoding"));
+      }
+      values.push(StandardValue {
+        value: tokens.iter().map(to_value).collect(),
+        tree_index: i,
+      });
+    }
+
+    Ok(StandardTreeDump {
+      format: STANDARD_FORMAT.to_string(),
+      leaf_encoding,
+      tree,
+      values,
+    })
+  }
+
+  // proof without the empty siblings of leaves one level up, as `StandardMerkleTree.getProof`
+  // returns it. It verifies with `MerkleProof::verify_sorted` and OpenZeppelin `MerkleProof.verify`.
+  pub fn get_standard_proof(&self, key: [u8; 32]) -> Result<MerkleProof<D>, MerkleError> {
+    let mut proof = self.get_proof(key)?;
+    proof
+      .proof
+      .retain(|sibling| *sibling != StandardHasher::zero());
+    Ok(proof)
+  }
+
+  // build from (leaf hash, data) pairs in array order, the first leaf being at array index
+  // `leaves.len() - 1`
+  fn build_standard(
+    leaves: Vec<([u8; 32], D)>,
+    mut store: S,
+    ordering: String,
+  ) -> Result<Self, MerkleError> {
+    let leaf_count = leaves.len();
+    let height = (2 * leaf_count - 1).ilog2() as usize;
+    for (i, (hash, data)) in (leaves.len() - 1..).zip(leaves) {
+      let (level, mut index) = position(height, i);
+      if level == 1 {
+        index *= 2;
+        store.put_node(
+          0,
+          index + 1,
+          Node {
+            hash: StandardHasher::zero(),
+            data: None,
+          },
+        )?;
+      }
+      store.put_index(data.key(), index)?;
+      store.put_node(
+        0,
+        index,
+        Node {
+          hash,
+          data: Some(data),
+        },
+      )?;
+    }
+
+    let (root, height) = build_tree(1 << height, 2, &mut store, &StandardHasher)?;
+    store.put_header(TreeHeader {
+      height,
+      arity: 2,
+      ordering: ordering.clone(),
+      leaf_count,
+    })?;
+    store.flush()?;
+
+    Ok(Self {
+      root,
+      store,
+      height,
+      arity: 2,
+      metadata: TreeMetadata { ordering },
+      hasher: StandardHasher,
+    })
+  }
+}
+
+// (level, index) of the array node `i` in a tree of the given height
+fn position(height: usize, i: usize) -> (usize, usize) {
+  let depth = (i + 1).ilog2() as usize;
+  (height - depth, i + 1 - (1 << depth))
+}
+
+fn invalid(reason: &'static str) -> MerkleError {
+  MerkleError::InvalidDump { reason }
+}
+
+fn parse_types(leaf_encoding: &[String]) -> Result<Vec<ParamType>, MerkleError> {
+  leaf_encoding
+    .iter()
+    .map(|ty| parse_type(ty))
+    .collect::<Option<_>>()
+    .ok_or(invalid("unsupported leaf encoding"))
+}
+
+// Solidity type name, limited to the types `abi` encodes
+fn parse_type(ty: &str) -> Option<ParamType> {
+  if let Some(inner) = ty.strip_suffix("[]") {
+    return Some(ParamType::Array(Box::new(parse_type(inner)?)));
+  }
+  if let Some((inner, len)) = ty.strip_suffix(']').and_then(|ty| ty.rsplit_once('[')) {
+    return Some(ParamType::FixedArray(
+      Box::new(parse_type(inner)?),
+      len.parse().ok()?,
+    ));
+  }
+  match ty {
+    "address" => Some(ParamType::Address),
+    "uint" | "uint256" => Some(ParamType::Uint),
+    "bytes32" => Some(ParamType::Bytes32),
+    "bool" => Some(ParamType::Bool),
+    "string" => Some(ParamType::String),
+    "bytes" => Some(ParamType::Bytes),
+    _ => None,
+  }
+}
+
+// JSON value as ethers and viem accept it: 0x-prefixed hex bytes, and integers as numbers or
+// decimal or hex strings
+fn to_token(ty: &ParamType, value: &Value) -> Option<Token> {
+  let bytes = |hex: &str| hex::decode(hex.strip_prefix("0x")?).ok();
+  match (ty, value) {
+    (ParamType::Address, Value::String(hex)) => Some(Token::Address(bytes(hex)?.try_into().ok()?)),
+    (ParamType::Uint, Value::String(value)) => match value.strip_prefix("0x") {
+      Some(hex) => U256::from_str_radix(hex, 16).ok().map(Token::Uint),
+      None => U256::from_dec_str(value).ok().map(Token::Uint),
+    },
+    (ParamType::Uint, Value::Number(value)) => Some(Token::Uint(value.as_u64()?.into())),
+    (ParamType::Bytes32, Value::String(hex)) => Some(Token::Bytes32(bytes(hex)?.try_into().ok()?)),
+    (ParamType::Bool, Value::Bool(value)) => Some(Token::Bool(*value)),
+    (ParamType::String, Value::String(value)) => Some(Token::String(value.clone())),
+    (ParamType::Bytes, Value::String(hex)) => Some(Token::Bytes(bytes(hex)?)),
+    (ParamType::Array(inner), Value::Array(values)) => values
+      .iter()
+      .map(|value| to_token(inner, value))
+      .collect::<Option<_>>()
+      .map(Token::Array),
+    (ParamType::FixedArray(inner, len), Value::Array(values)) if values.len() == *len => values
+      .iter()
+      .map(|value| to_token(inner, value))
+      .collect::<Option<_>>()
+      .map(Token::FixedArray),
+    _ => None,
+  }
+}
+
+// integers are written as decimal strings, which JavaScript reads without losing precision
+fn to_value(token: &Token) -> Value {
+  match token {
+    Token::Address(address) => Value::String(format!("0x{}", hex::encode(address))),
+    Token::Uint(value) => Value::String(value.to_string()),
+    Token::Bytes32(word) => Value::String(format!("0x{}", hex::encode(word))),
+    Token::Bool(value) => Value::Bool(*value),
+    Token::String(value) => Value::String(value.clone()),
+    Token::Bytes(bytes) => Value::String(format!("0x{}", hex::encode(bytes))),
+    Token::Array(tokens) | Token::FixedArray(tokens) => {
+      Value::Array(tokens.iter().map(to_value).collect())
+    }
+  }
+}
@@ -0,0 +1,100 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::Hasher;
+use crate::node::SerializableData;
+
+// Core lib imports
+use std::cmp::Ordering;
+
+// External imports
+use primitive_types::U256;
+use serde::{Deserialize, Serialize};
+
+// Order of the leaves at level 0, applied by the tree builder before hashing. Sorts are stable,
+// so leaves that compare equal keep their insertion order.
+#[derive(Debug, Clone)]
+pub enum LeafOrdering<D> {
+  // leaves in the order they are given
+  Insertion,
+  // ascending key
+  Key,
+  // ascending leaf hash, as OpenZeppelin's StandardMerkleTree sorts its leaves
+  LeafHash,
+  // descending weight (e.g. amount), `name` identifies the weight in the tree metadata
+  Descending {
+    name: &'static str,
+    weight: fn(&D) -> U256,
+  },
+  // any comparator, `name` identifies it in the tree metadata
+  Custom {
+    name: &'static str,
+    cmp: fn(&D, &D) -> Ordering,
+  },
+}
+
+impl<D> LeafOrdering<D> {
+  // identifier recorded in the tree metadata
+  pub fn name(&self) -> String {
+    match self {
+      LeafOrdering::Insertion => "insertion".to_string(),
+      LeafOrdering::Key => "key".to_string(),
+      LeafOrdering::LeafHash => "leaf-hash".to_string(),
+      LeafOrdering::Descending { name, .. } => format!("desc:{}", name),
+      LeafOrdering::Custom { name, .. } => format!("custom:{}", name),
+    }
+  }
+}
+
+// Build parameters needed to reproduce the root from the leaf data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TreeMetadata {
+  pub ordering: String,
+}
+
+// hash the leaves and order them, returning (leaf hash, data) pairs
+pub(crate) fn order_leaves<D: SerializableData, H: Hasher>(
+  data: Vec<D>,
+  ordering: &LeafOrdering<D>,
+  hasher: &H,
+) -> Vec<(H::Digest, D)> {
+  let mut leaves: Vec<_> = data
+    .into_iter()
+    .map(|data| (hasher.hash_leaf(&data.to_bytes()), data))
+    .collect();
+
+  match ordering {
+    LeafOrdering::Insertion => {}
+    LeafOrdering::Key => leaves.sort_by_cached_key(|(_, data)| data.key()),
+    LeafOrdering::LeafHash => leaves.sort_by_key(|(hash, _)| *hash),
+    LeafOrdering::Descending { weight, .. } => {
+      leaves.sort_by_cached_key(|(_, data)| std::cmp::Reverse(weight(data)))
+    }
+    LeafOrdering::Custom { cmp, .. } => leaves.sort_by(|(_, a), (_, b)| cmp(a, b)),
+  }
+  leaves
+}
+
+pub(crate) type LeafCmp<'a, D> = Box<dyn Fn(&D, &D) -> Ordering + 'a>;
+
+// comparator of two leaves in the ordering, failing for `Insertion` which depends on the position of
+// the leaves in the input rather than on the leaves
+pub(crate) fn leaf_comparator<'a, D: SerializableData, H: Hasher>(
+  ordering: &'a LeafOrdering<D>,
+  hasher: &'a H,
+) -> Result<LeafCmp<'a, D>, MerkleError> {
+  Ok(match ordering {
+    LeafOrdering::Insertion => {
+      return Err(MerkleError::UnsupportedOrdering {
+        ordering: ordering.name(),
+      })
+    }
+    LeafOrdering::Key => Box::new(|a, b| a.key().cmp(&b.key())),
+    LeafOrdering::LeafHash => Box::new(move |a, b| {
+      hasher
+        .hash_leaf(&a.to_bytes())
+        .cmp(&hasher.hash_leaf(&b.to_bytes()))
+    }),
+    LeafOrdering::Descending { weight, .. } => Box::new(move |a, b| weight(b).cmp(&weight(a))),
+    LeafOrdering::Custom { cmp, .. } => Box::new(cmp),
+  })
+}
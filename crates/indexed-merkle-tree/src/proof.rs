@@ -1,28 +1,209 @@
 // Local imports
+use crate::errors::MerkleError;
+use crate::hasher::{hex_digests, Digest, Hasher};
 use crate::node::SerializableData;
+use crate::verify::{compute_root, compute_sorted_root};
+
+// Core lib imports
+use alloc::{string::String, vec, vec::Vec};
 
 // External imports
-use hex;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
-pub struct MerkleProof<D: SerializableData> {
+pub struct MerkleProof<D: SerializableData, G: Digest = [u8; 32]> {
   pub data: D,
-  pub proof: Vec<[u8; 32]>,
+  pub index: usize, // position of the leaf at level 0
+  #[serde(with = "hex_digests")]
+  pub proof: Vec<G>,
+}
+
+impl<D: SerializableData, G: Digest> MerkleProof<D, G> {
+  // verify the proof of a binary tree against a root hash, using the leaf index to order each
+  // pair of siblings
+  pub fn verify<H: Hasher<Digest = G>>(&self, root: G, hasher: &H) -> Result<bool, MerkleError> {
+    self.verify_with_arity(root, hasher, 2)
+  }
+
+  // verify the proof of a k-ary tree, holding `arity - 1` siblings per level from left to right.
+  // The position of the node in its group at each level is read from the leaf index.
+  pub fn verify_with_arity<H: Hasher<Digest = G>>(
+    &self,
+    root: G,
+    hasher: &H,
+    arity: usize,
+  ) -> Result<bool, MerkleError> {
+    if arity < 2 {
+      return Err(MerkleError::InvalidArity { arity });
+    }
+    if !self.proof.len().is_multiple_of(arity - 1) {
+      return Err(MerkleError::InvalidProofLength {
+        len: self.proof.len(),
+        arity,
+      });
+    }
+
+    let mut hash = hasher.hash_leaf(&self.data.to_bytes());
+    if arity == 2 {
+      hash = compute_root(hasher, hash, self.index, &self.proof);
+    } else {
+      let mut index = self.index;
+      for siblings in self.proof.chunks(arity - 1) {
+        let mut children = siblings.to_vec();
+        children.insert(index % arity, hash);
+        hash = hasher.hash_children(&children);
+        index /= arity;
+      }
+    }
+
+    if hash != root {
+      return Err(MerkleError::InvalidRootHash {
+        exp: root.as_ref().to_vec(),
+        act: hash.as_ref().to_vec(),
+      });
+    }
+
+    Ok(true)
+  }
+
+  // verify the proof of a tree hashing each pair with the smaller hash first, the index is not used
+  pub fn verify_sorted<H: Hasher<Digest = G>>(
+    &self,
+    root: G,
+    hasher: &H,
+  ) -> Result<bool, MerkleError> {
+    let hash = compute_sorted_root(hasher, hasher.hash_leaf(&self.data.to_bytes()), &self.proof);
+
+    if hash != root {
+      return Err(MerkleError::InvalidRootHash {
+        exp: root.as_ref().to_vec(),
+        act: hash.as_ref().to_vec(),
+      });
+    }
+
+    Ok(true)
+  }
+
+  // Compact binary encoding of the proof path:
+  //   varint(index) || varint(proof length) || bitmap || sibling hashes
+  // Bit `i` of the bitmap is set when the sibling at level `i` is the well-known zero-subtree hash
+  // for that level, in which case it is omitted from the trailing list of raw digests.
+  // The leaf data itself is not part of the encoding. Zero-subtree hashes are those of a binary
+  // tree, so proofs of other arities are rejected rather than encoded with the wrong bitmap.
+  pub fn encode<H: Hasher<Digest = G>>(
+    &self,
+    hasher: &H,
+    arity: usize,
+  ) -> Result<Vec<u8>, MerkleError> {
+    if arity != 2 {
+      return Err(MerkleError::InvalidArity { arity });
+    }
+
+    let zero_hashes = hasher.zero_hashes(self.proof.len());
+    let mut bitmap = vec![0u8; self.proof.len().div_ceil(8)];
+    let mut hashes = Vec::new();
+
+    for (level, sibling) in self.proof.iter().enumerate() {
+      if *sibling == zero_hashes[level] {
+        bitmap[level / 8] |= 1 << (level % 8);
+      } else {
+        hashes.extend_from_slice(sibling.as_ref());
+      }
+    }
+
+    let mut bytes = Vec::with_capacity(20 + bitmap.len() + hashes.len());
+    write_varint(&mut bytes, self.index as u64);
+    write_varint(&mut bytes, self.proof.len() as u64);
+    bytes.extend_from_slice(&bitmap);
+    bytes.extend_from_slice(&hashes);
+    Ok(bytes)
+  }
+
+  pub fn decode<H: Hasher<Digest = G>>(
+    data: D,
+    bytes: &[u8],
+    hasher: &H,
+    arity: usize,
+  ) -> Result<Self, MerkleError> {
+    if arity != 2 {
+      return Err(MerkleError::InvalidArity { arity });
+    }
+
+    let mut cursor = 0;
+    let index = read_varint(bytes, &mut cursor)? as usize;
+    let len = read_varint(bytes, &mut cursor)? as usize;
+
+    // every level takes at least one bit, so a length beyond the input size is malformed
+    if len > bytes.len() * 8 {
+      return Err(MerkleError::InvalidProofEncoding { offset: cursor });
+    }
+
+    let bitmap_len = len.div_ceil(8);
+    let bitmap = bytes
+      .get(cursor..cursor + bitmap_len)
+      .ok_or(MerkleError::InvalidProofEncoding { offset: cursor })?;
+    cursor += bitmap_len;
+
+    let zero_hashes = hasher.zero_hashes(len);
+    let mut proof = Vec::with_capacity(len);
+    for (level, zero_hash) in zero_hashes.into_iter().enumerate() {
+      if bitmap[level / 8] & (1 << (level % 8)) != 0 {
+        proof.push(zero_hash);
+      } else {
+        let sibling = bytes
+          .get(cursor..cursor + G::LEN)
+          .and_then(G::from_slice)
+          .ok_or(MerkleError::InvalidProofEncoding { offset: cursor })?;
+        proof.push(sibling);
+        cursor += G::LEN;
+      }
+    }
+
+    if cursor != bytes.len() {
+      return Err(MerkleError::InvalidProofEncoding { offset: cursor });
+    }
+
+    Ok(MerkleProof { data, index, proof })
+  }
 }
 
-impl<D: SerializableData> std::fmt::Debug for MerkleProof<D> {
-  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl<D: SerializableData, G: Digest> core::fmt::Debug for MerkleProof<D, G> {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
     f.debug_struct("MerkleProof")
       .field("data", &String::from_utf8_lossy(&self.data.to_bytes()))
+      .field("index", &self.index)
       .field(
         "proof",
-        &self
-          .proof
-          .iter()
-          .map(|h| format!("0x{}", hex::encode(h)))
-          .collect::<Vec<_>>(),
+        &self.proof.iter().map(Digest::to_hex).collect::<Vec<_>>(),
       )
       .finish()
   }
 }
+
+// LEB128 encoding of unsigned integers
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+  while value >= 0x80 {
+    bytes.push((value as u8) | 0x80);
+    value >>= 7;
+  }
+  bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &[u8], cursor: &mut usize) -> Result<u64, MerkleError> {
+  let mut value = 0u64;
+  let mut shift = 0;
+  loop {
+    let byte = *bytes
+      .get(*cursor)
+      .ok_or(MerkleError::InvalidProofEncoding { offset: *cursor })?;
+    if shift >= 64 || (shift == 63 && byte > 1) {
+      return Err(MerkleError::InvalidProofEncoding { offset: *cursor });
+    }
+    value |= ((byte & 0x7f) as u64) << shift;
+    *cursor += 1;
+    if byte & 0x80 == 0 {
+      return Ok(value);
+    }
+    shift += 7;
+  }
+}
@@ -0,0 +1,299 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::Digest;
+use crate::node::{Node, SerializableData};
+use crate::store::{NodeStore, TreeHeader};
+
+// Core lib imports
+use std::io::ErrorKind;
+use std::marker::PhantomData;
+use std::path::Path;
+
+// External imports
+use redb::{Database, ReadOnlyTable, ReadableTable, TableDefinition, TableError, WriteTransaction};
+
+const NODES: TableDefinition<(u64, u64), &[u8]> = TableDefinition::new("nodes");
+const INDEXER: TableDefinition<&[u8], u64> = TableDefinition::new("indexer");
+const HEADER: TableDefinition<&str, &[u8]> = TableDefinition::new("header");
+
+// On-disk store backed by an embedded redb database. Writes are batched in a single transaction
+// that is committed on `flush`, after which the tree can be reopened with
+// `RedbStore::open` and `IndexedMerkleTree::open`. Reads of a committed tree share one read
+// transaction, refreshed on every flush.
+pub struct RedbStore<D: SerializableData, G: Digest = [u8; 32]> {
+  db: Database,
+  txn: Option<WriteTransaction>,
+  read: Option<ReadTables>,
+  _data: PhantomData<(D, G)>,
+}
+
+// tables of the last committed state, `None` for tables that were never written
+struct ReadTables {
+  nodes: Option<ReadOnlyTable<(u64, u64), &'static [u8]>>,
+  indexer: Option<ReadOnlyTable<&'static [u8], u64>>,
+  header: Option<ReadOnlyTable<&'static str, &'static [u8]>>,
+}
+
+impl<D: SerializableData, G: Digest> RedbStore<D, G> {
+  // create an empty database at `path`, replacing any existing file so that no nodes of a
+  // previous tree survive
+  pub fn create(path: impl AsRef<Path>) -> Result<Self, MerkleError> {
+    match std::fs::remove_file(&path) {
+      Err(err) if err.kind() != ErrorKind::NotFound => return Err(err.into()),
+      _ => {}
+    }
+    Ok(Self {
+      db: Database::create(path).map_err(storage_error)?,
+      txn: None,
+      read: None,
+      _data: PhantomData,
+    })
+  }
+
+  // open the existing database at `path`, holding a previously built tree
+  pub fn open(path: impl AsRef<Path>) -> Result<Self, MerkleError> {
+    let db = Database::open(path).map_err(storage_error)?;
+    let read = ReadTables::begin(&db)?;
+    Ok(Self {
+      db,
+      txn: None,
+      read: Some(read),
+      _data: PhantomData,
+    })
+  }
+
+  fn write_txn(&mut self) -> Result<&WriteTransaction, MerkleError> {
+    if self.txn.is_none() {
+      self.txn = Some(self.db.begin_write().map_err(storage_error)?);
+    }
+    Ok(self.txn.as_ref().unwrap())
+  }
+}
+
+impl ReadTables {
+  fn begin(db: &Database) -> Result<Self, MerkleError> {
+    let txn = db.begin_read().map_err(storage_error)?;
+    Ok(Self {
+      nodes: optional_table(txn.open_table(NODES))?,
+      indexer: optional_table(txn.open_table(INDEXER))?,
+      header: optional_table(txn.open_table(HEADER))?,
+    })
+  }
+}
+
+impl<D: SerializableData, G: Digest> NodeStore<D, G> for RedbStore<D, G> {
+  fn get_node(&self, level: usize, index: usize) -> Result<Option<Node<D, G>>, MerkleError> {
+    let key = (level as u64, index as u64);
+    let bytes = match (&self.txn, &self.read) {
+      (Some(txn), _) => {
+        let table = txn.open_table(NODES).map_err(storage_error)?;
+        let value = table.get(key).map_err(storage_error)?;
+        value.map(|value| value.value().to_vec())
+      }
+      (
+        None,
+        Some(ReadTables {
+          nodes: Some(table), ..
+        }),
+      ) => {
+        let value = table.get(key).map_err(storage_error)?;
+        value.map(|value| value.value().to_vec())
+      }
+      (None, _) => None,
+    };
+    bytes.map(|bytes| decode_node(&bytes)).transpose()
+  }
+
+  fn put_node(&mut self, level: usize, index: usize, node: Node<D, G>) -> Result<(), MerkleError> {
+    let bytes = encode_node(&node);
+    let txn = self.write_txn()?;
+    let mut table = txn.open_table(NODES).map_err(storage_error)?;
+    table
+      .insert((level as u64, index as u64), bytes.as_slice())
+      .map_err(storage_error)?;
+    Ok(())
+  }
+
+  fn get_index(&self, key: &[u8; 32]) -> Result<Option<usize>, MerkleError> {
+    let index = match (&self.txn, &self.read) {
+      (Some(txn), _) => {
+        let table = txn.open_table(INDEXER).map_err(storage_error)?;
+        let value = table.get(key.as_slice()).map_err(storage_error)?;
+        value.map(|value| value.value())
+      }
+      (
+        None,
+        Some(ReadTables {
+          indexer: Some(table),
+          ..
+        }),
+      ) => {
+        let value = table.get(key.as_slice()).map_err(storage_error)?;
+        value.map(|value| value.value())
+      }
+      (None, _) => None,
+    };
+    Ok(index.map(|index| index as usize))
+  }
+
+  fn put_index(&mut self, key: [u8; 32], index: usize) -> Result<(), MerkleError> {
+    let txn = self.write_txn()?;
+    let mut table = txn.open_table(INDEXER).map_err(storage_error)?;
+    table
+      .insert(key.as_slice(), index as u64)
+      .map_err(storage_error)?;
+    Ok(())
+  }
+
+  fn for_each_index(
+    &self,
+    f: &mut dyn FnMut([u8; 32], usize) -> Result<(), MerkleError>,
+  ) -> Result<(), MerkleError> {
+    match (&self.txn, &self.read) {
+      (Some(txn), _) => visit_index(&txn.open_table(INDEXER).map_err(storage_error)?, f),
+      (
+        None,
+        Some(ReadTables {
+          indexer: Some(table),
+          ..
+        }),
+      ) => visit_index(table, f),
+      (None, _) => Ok(()),
+    }
+  }
+
+  fn get_header(&self) -> Result<Option<TreeHeader>, MerkleError> {
+    match (&self.txn, &self.read) {
+      (Some(txn), _) => read_header(&txn.open_table(HEADER).map_err(storage_error)?),
+      (
+        None,
+        Some(ReadTables {
+          header: Some(table),
+          ..
+        }),
+      ) => read_header(table),
+      (None, _) => Ok(None),
+    }
+  }
+
+  fn put_header(&mut self, header: TreeHeader) -> Result<(), MerkleError> {
+    let txn = self.write_txn()?;
+    let mut table = txn.open_table(HEADER).map_err(storage_error)?;
+    for (name, value) in [
+      ("height", header.height),
+      ("arity", header.arity),
+      ("leaf_count", header.leaf_count),
+    ] {
+      table
+        .insert(name, (value as u64).to_le_bytes().as_slice())
+        .map_err(storage_error)?;
+    }
+    table
+      .insert("ordering", header.ordering.as_bytes())
+      .map_err(storage_error)?;
+    Ok(())
+  }
+
+  fn flush(&mut self) -> Result<(), MerkleError> {
+    if let Some(txn) = self.txn.take() {
+      txn.commit().map_err(storage_error)?;
+      self.read = Some(ReadTables::begin(&self.db)?);
+    }
+    Ok(())
+  }
+}
+
+impl<D: SerializableData, G: Digest> std::fmt::Debug for RedbStore<D, G> {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    f.debug_struct("RedbStore")
+      .field("pending_writes", &self.txn.is_some())
+      .finish()
+  }
+}
+
+// node encoding: hash (digest size) || has data (u8) || data bytes
+fn encode_node<D: SerializableData, G: Digest>(node: &Node<D, G>) -> Vec<u8> {
+  let mut bytes = node.hash.as_ref().to_vec();
+  match &node.data {
+    Some(data) => {
+      bytes.push(1);
+      bytes.extend_from_slice(&data.to_bytes());
+    }
+    None => bytes.push(0),
+  }
+  bytes
+}
+
+fn decode_node<D: SerializableData, G: Digest>(bytes: &[u8]) -> Result<Node<D, G>, MerkleError> {
+  let hash = bytes.get(..G::LEN).and_then(G::from_slice);
+  let (hash, data) = match (hash, bytes.get(G::LEN..)) {
+    (Some(hash), Some([flag, data @ ..])) if *flag <= 1 => (hash, (*flag == 1).then_some(data)),
+    _ => {
+      return Err(MerkleError::Storage {
+        reason: "malformed node".to_string(),
+      })
+    }
+  };
+  Ok(Node {
+    hash,
+    data: data.map(|data| D::from_bytes(data.to_vec())).transpose()?,
+  })
+}
+
+fn visit_index(
+  table: &impl ReadableTable<&'static [u8], u64>,
+  f: &mut dyn FnMut([u8; 32], usize) -> Result<(), MerkleError>,
+) -> Result<(), MerkleError> {
+  for entry in table.iter().map_err(storage_error)? {
+    let (key, index) = entry.map_err(storage_error)?;
+    let key = key.value().try_into().map_err(|_| MerkleError::Storage {
+      reason: "malformed index key".to_string(),
+    })?;
+    f(key, index.value() as usize)?;
+  }
+  Ok(())
+}
+
+fn read_header(
+  table: &impl ReadableTable<&'static str, &'static [u8]>,
+) -> Result<Option<TreeHeader>, MerkleError> {
+  let malformed = || MerkleError::Storage {
+    reason: "malformed tree header".to_string(),
+  };
+  let read_u64 = |name| -> Result<Option<u64>, MerkleError> {
+    match table.get(name).map_err(storage_error)? {
+      Some(value) => Ok(Some(u64::from_le_bytes(
+        value.value().try_into().map_err(|_| malformed())?,
+      ))),
+      None => Ok(None),
+    }
+  };
+  let (Some(height), Some(arity)) = (read_u64("height")?, read_u64("arity")?) else {
+    return Ok(None);
+  };
+  let leaf_count = read_u64("leaf_count")?.ok_or_else(malformed)?;
+  let ordering = match table.get("ordering").map_err(storage_error)? {
+    Some(value) => String::from_utf8(value.value().to_vec()).map_err(|_| malformed())?,
+    None => return Err(malformed()),
+  };
+  Ok(Some(TreeHeader {
+    height: height as usize,
+    arity: arity as usize,
+    ordering,
+    leaf_count: leaf_count as usize,
+  }))
+}
+
+fn optional_table<T>(table: Result<T, TableError>) -> Result<Option<T>, MerkleError> {
+  match table {
+    Ok(table) => Ok(Some(table)),
+    Err(TableError::TableDoesNotExist(_)) => Ok(None),
+    Err(err) => Err(storage_error(err)),
+  }
+}
+
+fn storage_error(err: impl Into<redb::Error>) -> MerkleError {
+  MerkleError::Storage {
+    reason: err.into().to_string(),
+  }
+}
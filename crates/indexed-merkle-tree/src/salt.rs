@@ -0,0 +1,16 @@
+// External imports
+use hkdf::Hkdf;
+use sha2::Sha256;
+
+const SALT_INFO: &[u8] = b"indexed-merkle-tree/leaf-salt";
+
+// Per-leaf salt derived from a secret seed with HKDF-SHA256, bound to the leaf key. Including it in
+// the leaf preimage stops anyone who knows a key from recomputing its leaf for guessed values, while
+// the operator can rebuild the same tree from the seed.
+pub fn derive_salt(seed: &[u8], key: &[u8; 32]) -> [u8; 32] {
+  let mut salt = [0u8; 32];
+  Hkdf::<Sha256>::new(None, seed)
+    .expand_multi_info(&[SALT_INFO, key], &mut salt)
+    .expect("32 bytes is a valid HKDF-SHA256 output length");
+  salt
+}
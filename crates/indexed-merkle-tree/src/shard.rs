@@ -0,0 +1,225 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::{Digest, Hasher};
+use crate::node::{Node, SerializableData};
+use crate::proof::MerkleProof;
+use crate::store::{MemoryStore, NodeStore};
+use crate::tree::{build_tree, get_parent_node, get_sibling_node, IndexedMerkleTree, TreeOptions};
+
+// Core lib imports
+use std::collections::HashMap;
+
+pub const MAX_SHARD_BITS: u32 = 24;
+
+// Two-level tree: leaves are split into `2^shard_bits` shards by the leading bits of their key,
+// each shard is an independent `IndexedMerkleTree`, and the shard roots form the leaves of a
+// top-level tree. Proofs are the shard proof followed by the top proof, so they verify against
+// `root` like any other proof. Only a subset of shards needs to be held in memory, as long as the
+// roots of all shards are known.
+pub struct ShardedMerkleTree<
+  D: SerializableData,
+  H: Hasher,
+  S: NodeStore<D, H::Digest> = MemoryStore<D, <H as Hasher>::Digest>,
+> {
+  pub root: Node<D, H::Digest>,
+  pub shard_bits: u32,
+  pub shard_roots: Vec<H::Digest>, // shard id -> shard root, for every shard
+  pub shards: HashMap<usize, IndexedMerkleTree<D, H, S>>, // shard id -> tree, for loaded shards
+  pub top: MemoryStore<D, H::Digest>, // top-level tree, whose leaves are the shard roots
+  pub top_height: usize,
+  hasher: H,
+}
+
+impl<D: SerializableData, H: Hasher + Clone> ShardedMerkleTree<D, H> {
+  pub fn new(data: Vec<D>, shard_bits: u32, hasher: H) -> Result<Self, MerkleError> {
+    check_shard_bits(shard_bits)?;
+    let partitions = partition(data, shard_bits);
+    let options = TreeOptions {
+      depth: Some(shard_height(&partitions)),
+      ..Default::default()
+    };
+    let shards = partitions
+      .into_iter()
+      .enumerate()
+      .map(|(shard, data)| {
+        let tree = IndexedMerkleTree::with_options(data, hasher.clone(), options.clone())?;
+        Ok((shard, tree))
+      })
+      .collect::<Result<HashMap<usize, IndexedMerkleTree<D, H>>, MerkleError>>()?;
+    let shard_roots = (0..shards.len())
+      .map(|shard| shards[&shard].root.hash)
+      .collect();
+
+    Self::from_shards(shard_bits, shard_roots, shards, hasher)
+  }
+}
+
+impl<D: SerializableData, H: Hasher, S: NodeStore<D, H::Digest>> ShardedMerkleTree<D, H, S> {
+  // assemble a tree from the roots of all shards and the subset of shards to serve, which may
+  // have been built or loaded from snapshots independently. Shards must all have the same height,
+  // as the shard id continues the leaf index above it.
+  pub fn from_shards(
+    shard_bits: u32,
+    shard_roots: Vec<H::Digest>,
+    shards: HashMap<usize, IndexedMerkleTree<D, H, S>>,
+    hasher: H,
+  ) -> Result<Self, MerkleError> {
+    check_shard_bits(shard_bits)?;
+    if shard_roots.len() != 1 << shard_bits {
+      return Err(MerkleError::InvalidDataLength {
+        len: shard_roots.len(),
+      });
+    }
+
+    let height = shards.values().map(|tree| tree.height).max();
+    for (shard, tree) in &shards {
+      if Some(tree.height) != height {
+        return Err(MerkleError::InvalidShardHeight {
+          shard: *shard,
+          height: tree.height,
+        });
+      }
+      let exp = *shard_roots
+        .get(*shard)
+        .ok_or(MerkleError::ShardNotLoaded { shard: *shard })?;
+      if tree.root.hash != exp {
+        return Err(MerkleError::InvalidRootHash {
+          exp: exp.as_ref().to_vec(),
+          act: tree.root.hash.as_ref().to_vec(),
+        });
+      }
+    }
+
+    // shard roots are used as-is for the top-level leaves so that the shard and top proofs chain
+    let mut top = MemoryStore::default();
+    for (index, hash) in shard_roots.iter().enumerate() {
+      top.put_node(
+        0,
+        index,
+        Node {
+          hash: *hash,
+          data: None,
+        },
+      )?;
+    }
+    let (root, top_height) = build_tree(shard_roots.len(), 2, &mut top, &hasher)?;
+
+    Ok(Self {
+      root,
+      shard_bits,
+      shard_roots,
+      shards,
+      top,
+      top_height,
+      hasher,
+    })
+  }
+
+  // height of the whole tree, shard levels included, known once a shard is loaded
+  pub fn height(&self) -> Option<usize> {
+    let shard = self.shards.values().next()?;
+    Some(shard.height + self.top_height)
+  }
+
+  // position of the leaf across the whole tree, where the shard id continues the leaf index above
+  // the shard's own height
+  pub fn get_index(&self, key: [u8; 32]) -> Result<usize, MerkleError> {
+    let shard = shard_of(&key, self.shard_bits);
+    let tree = self.get_shard(shard)?;
+    Ok(tree.get_index(key)? + (shard << tree.height))
+  }
+
+  pub fn get_proof(&self, key: [u8; 32]) -> Result<MerkleProof<D, H::Digest>, MerkleError> {
+    let shard = shard_of(&key, self.shard_bits);
+    let tree = self.get_shard(shard)?;
+    let mut proof = tree.get_proof(key)?;
+    proof.index += shard << tree.height;
+
+    let mut index = shard;
+    for level in 0..self.top_height {
+      let sibling_index = get_sibling_node(index);
+      let sibling_node =
+        self
+          .top
+          .get_node(level, sibling_index)?
+          .ok_or(MerkleError::NodeNotFound {
+            level,
+            index: sibling_index,
+          })?;
+      proof.proof.push(sibling_node.hash);
+      (_, index) = get_parent_node(level, index);
+    }
+
+    Ok(proof)
+  }
+
+  // the proof index is checked against the leaf of its key, which must be in a loaded shard
+  pub fn verify_proof(&self, proof: MerkleProof<D, H::Digest>) -> Result<bool, MerkleError> {
+    let index = self.get_index(proof.data.key())?;
+    if proof.index != index {
+      return Err(MerkleError::InvalidProofIndex {
+        exp: index,
+        act: proof.index,
+      });
+    }
+    proof.verify(self.root.hash, &self.hasher)
+  }
+
+  fn get_shard(&self, shard: usize) -> Result<&IndexedMerkleTree<D, H, S>, MerkleError> {
+    self
+      .shards
+      .get(&shard)
+      .ok_or(MerkleError::ShardNotLoaded { shard })
+  }
+}
+
+impl<D: SerializableData, H: Hasher, S: NodeStore<D, H::Digest>> std::fmt::Debug
+  for ShardedMerkleTree<D, H, S>
+{
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    let mut loaded = self.shards.keys().collect::<Vec<_>>();
+    loaded.sort();
+    f.debug_struct("ShardedMerkleTree")
+      .field("root", &self.root.hash.to_hex())
+      .field("shard_bits", &self.shard_bits)
+      .field(
+        "shard_roots",
+        &self
+          .shard_roots
+          .iter()
+          .map(Digest::to_hex)
+          .collect::<Vec<_>>(),
+      )
+      .field("loaded_shards", &loaded)
+      .finish()
+  }
+}
+
+// shard id of a key, taken from its leading `shard_bits` bits
+pub fn shard_of(key: &[u8; 32], shard_bits: u32) -> usize {
+  let prefix = u64::from_be_bytes(key[..8].try_into().unwrap());
+  prefix.checked_shr(u64::BITS - shard_bits).unwrap_or(0) as usize
+}
+
+// height shared by all shards, that of the largest one. Shards are built with it as their fixed
+// depth so that every leaf index continues with the shard id at the same bit.
+pub fn shard_height<D>(partitions: &[Vec<D>]) -> usize {
+  let len = partitions.iter().map(Vec::len).max().unwrap_or(0);
+  len.next_power_of_two().trailing_zeros() as usize
+}
+
+// split leaves into `2^shard_bits` shards, keeping their relative order within each shard
+pub fn partition<D: SerializableData>(data: Vec<D>, shard_bits: u32) -> Vec<Vec<D>> {
+  let mut shards: Vec<Vec<D>> = (0..1 << shard_bits).map(|_| Vec::new()).collect();
+  for entry in data {
+    shards[shard_of(&entry.key(), shard_bits)].push(entry);
+  }
+  shards
+}
+
+fn check_shard_bits(shard_bits: u32) -> Result<(), MerkleError> {
+  if shard_bits == 0 || shard_bits > MAX_SHARD_BITS {
+    return Err(MerkleError::InvalidShardBits { bits: shard_bits });
+  }
+  Ok(())
+}
@@ -0,0 +1,210 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::{Digest, Hasher};
+use crate::node::{Node, SerializableData};
+use crate::store::{MemoryStore, NodeStore, TreeHeader};
+use crate::tree::{level_width, IndexedMerkleTree};
+
+// Core lib imports
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 4] = b"IMTS";
+const VERSION: u8 = 1;
+
+// Snapshot layout (all integers little endian):
+//   magic || version (u8) || height (u32) || base height (u32) || arity (u32)
+//   digest length (u16) || ordering length (u16) || ordering (utf-8) || leaf count (u64)
+//   leaf count x (data length (u32) || data bytes)
+//   for each level from 0 to `height`: `level_width(base height, level, arity)` x node hash
+// Leaves are written in level 0 order. On load each takes the next level 0 node holding its leaf
+// hash, as OpenZeppelin trees have empty nodes between their upper leaves.
+// Node hashes take the digest length of the hasher, which must match the recorded one on load.
+// The base height is the height of the fully stored subtree, below the padding levels of a
+// fixed-depth tree. The ordering is the leaf ordering name from the tree metadata.
+impl<D: SerializableData, H: Hasher> IndexedMerkleTree<D, H> {
+  pub fn load_snapshot<R: Read>(reader: R, hasher: H) -> Result<Self, MerkleError> {
+    Self::load_snapshot_into(reader, hasher, MemoryStore::default())
+  }
+}
+
+impl<D: SerializableData, H: Hasher, S: NodeStore<D, H::Digest>> IndexedMerkleTree<D, H, S> {
+  pub fn save_snapshot<W: Write>(&self, mut writer: W) -> Result<(), MerkleError> {
+    let leaf_count = self.leaf_count()?;
+    let base_height = self.base_height()?;
+
+    write_header(
+      &mut writer,
+      self.height,
+      base_height,
+      self.arity,
+      H::Digest::LEN,
+      &self.metadata.ordering,
+      leaf_count,
+    )?;
+
+    let width = level_width(base_height, 0, self.arity);
+    let mut written = 0;
+    let mut index = 0;
+    while written < leaf_count {
+      if index == width {
+        return Err(MerkleError::NodeNotFound { level: 0, index });
+      }
+      if let Some(data) = self.get_node(0, index)?.data {
+        let bytes = data.to_bytes();
+        writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+        writer.write_all(&bytes)?;
+        written += 1;
+      }
+      index += 1;
+    }
+
+    for level in 0..=self.height {
+      for index in 0..level_width(base_height, level, self.arity) {
+        writer.write_all(self.get_node(level, index)?.hash.as_ref())?;
+      }
+    }
+
+    writer.flush()?;
+    Ok(())
+  }
+
+  // load a snapshot into the given store
+  pub fn load_snapshot_into<R: Read>(
+    mut reader: R,
+    hasher: H,
+    mut store: S,
+  ) -> Result<Self, MerkleError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+      return Err(MerkleError::InvalidSnapshot {
+        reason: "unknown magic bytes",
+      });
+    }
+    if read_array::<_, 1>(&mut reader)?[0] != VERSION {
+      return Err(MerkleError::InvalidSnapshot {
+        reason: "unsupported version",
+      });
+    }
+
+    let height = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+    let base_height = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+    let arity = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+    let digest_len = u16::from_le_bytes(read_array(&mut reader)?) as usize;
+    if digest_len != H::Digest::LEN {
+      return Err(MerkleError::InvalidSnapshot {
+        reason: "digest length does not match the hasher",
+      });
+    }
+    let len = u16::from_le_bytes(read_array(&mut reader)?) as usize;
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    let ordering = String::from_utf8(bytes).map_err(|_| MerkleError::InvalidSnapshot {
+      reason: "invalid leaf ordering",
+    })?;
+    let leaf_count = u64::from_le_bytes(read_array(&mut reader)?) as usize;
+    if base_height > height {
+      return Err(MerkleError::InvalidSnapshot {
+        reason: "base height exceeds height",
+      });
+    }
+    if arity < 2 {
+      return Err(MerkleError::InvalidSnapshot {
+        reason: "invalid arity",
+      });
+    }
+    let capacity = arity.checked_pow(base_height as u32);
+    if capacity.is_none_or(|capacity| leaf_count > capacity) {
+      return Err(MerkleError::InvalidSnapshot {
+        reason: "leaf count exceeds tree capacity",
+      });
+    }
+
+    // the leaf count is untrusted, so don't preallocate for it
+    let mut data = Vec::new();
+    for _ in 0..leaf_count {
+      let len = u32::from_le_bytes(read_array(&mut reader)?) as usize;
+      let mut bytes = Vec::new();
+      reader.by_ref().take(len as u64).read_to_end(&mut bytes)?;
+      if bytes.len() != len {
+        return Err(MerkleError::InvalidSnapshot {
+          reason: "truncated leaf data",
+        });
+      }
+      data.push((hasher.hash_leaf(&bytes), D::from_bytes(bytes)?));
+    }
+
+    let mut data = data.into_iter().peekable();
+    for level in 0..=height {
+      for index in 0..level_width(base_height, level, arity) {
+        let hash = read_digest::<_, H::Digest>(&mut reader)?;
+        let data = match level {
+          0 => data
+            .next_if(|(leaf, _)| *leaf == hash)
+            .map(|(_, data)| data),
+          _ => None,
+        };
+        if let Some(data) = &data {
+          store.put_index(data.key(), index)?;
+        }
+        store.put_node(level, index, Node { hash, data })?;
+      }
+    }
+    if data.next().is_some() {
+      return Err(MerkleError::InvalidSnapshot {
+        reason: "leaf data does not match the leaf hashes",
+      });
+    }
+    store.put_header(TreeHeader {
+      height,
+      arity,
+      ordering,
+      leaf_count,
+    })?;
+    store.flush()?;
+
+    // node hashes are taken from the snapshot as-is, the audit checks them against the leaf data
+    Self::open_audited(store, hasher)
+  }
+}
+
+// everything before the leaves
+pub(crate) fn write_header<W: Write>(
+  writer: &mut W,
+  height: usize,
+  base_height: usize,
+  arity: usize,
+  digest_len: usize,
+  ordering: &str,
+  leaf_count: usize,
+) -> Result<(), MerkleError> {
+  // checked before writing anything, so a failed save leaves no partial header behind
+  let ordering = ordering.as_bytes();
+  if ordering.len() > u16::MAX as usize {
+    return Err(MerkleError::InvalidSnapshot {
+      reason: "leaf ordering name too long",
+    });
+  }
+  writer.write_all(MAGIC)?;
+  writer.write_all(&[VERSION])?;
+  writer.write_all(&(height as u32).to_le_bytes())?;
+  writer.write_all(&(base_height as u32).to_le_bytes())?;
+  writer.write_all(&(arity as u32).to_le_bytes())?;
+  writer.write_all(&(digest_len as u16).to_le_bytes())?;
+  writer.write_all(&(ordering.len() as u16).to_le_bytes())?;
+  writer.write_all(ordering)?;
+  writer.write_all(&(leaf_count as u64).to_le_bytes())?;
+  Ok(())
+}
+
+pub(crate) fn read_digest<R: Read, G: Digest>(reader: &mut R) -> Result<G, MerkleError> {
+  let mut bytes = vec![0u8; G::LEN];
+  reader.read_exact(&mut bytes)?;
+  Ok(G::from_slice(&bytes).unwrap())
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N], MerkleError> {
+  let mut bytes = [0u8; N];
+  reader.read_exact(&mut bytes)?;
+  Ok(bytes)
+}
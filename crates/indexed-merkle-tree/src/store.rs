@@ -0,0 +1,128 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::Digest;
+use crate::index::{KeyIndex, SortedKeyIndex};
+use crate::node::{Node, SerializableData};
+
+// Core lib imports
+use std::collections::HashMap;
+
+// Storage backend for tree nodes and the key index
+pub trait NodeStore<D: SerializableData, G: Digest = [u8; 32]> {
+  fn get_node(&self, level: usize, index: usize) -> Result<Option<Node<D, G>>, MerkleError>;
+  fn put_node(&mut self, level: usize, index: usize, node: Node<D, G>) -> Result<(), MerkleError>;
+  fn get_index(&self, key: &[u8; 32]) -> Result<Option<usize>, MerkleError>;
+  fn put_index(&mut self, key: [u8; 32], index: usize) -> Result<(), MerkleError>;
+  // call `f` with every (key, leaf index) entry of the key index
+  fn for_each_index(
+    &self,
+    f: &mut dyn FnMut([u8; 32], usize) -> Result<(), MerkleError>,
+  ) -> Result<(), MerkleError>;
+  // shape of the stored tree, `None` until a tree has been built into the store
+  fn get_header(&self) -> Result<Option<TreeHeader>, MerkleError>;
+  fn put_header(&mut self, header: TreeHeader) -> Result<(), MerkleError>;
+
+  // persist pending writes, called once the tree has been built
+  fn flush(&mut self) -> Result<(), MerkleError> {
+    Ok(())
+  }
+}
+
+// Written along with the nodes, so that a tree can be reopened without probing the store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeHeader {
+  pub height: usize,
+  pub arity: usize,
+  pub ordering: String,  // leaf ordering name from the tree metadata
+  pub leaf_count: usize, // data leaves, excluding padding
+}
+
+#[derive(Clone)]
+pub struct MemoryStore<
+  D: SerializableData,
+  G: Digest = [u8; 32],
+  I: KeyIndex = HashMap<[u8; 32], usize>,
+> {
+  pub nodes: HashMap<(usize, usize), Node<D, G>>, // (level, index) -> node
+  pub indexer: I,                                 // key -> index position at level 0
+  pub header: Option<TreeHeader>,                 // shape of the built tree
+}
+
+// in-memory store using the compact sorted key index
+pub type CompactMemoryStore<D, G = [u8; 32]> = MemoryStore<D, G, SortedKeyIndex>;
+
+impl<D: SerializableData, G: Digest, I: KeyIndex> Default for MemoryStore<D, G, I> {
+  fn default() -> Self {
+    Self {
+      nodes: HashMap::new(),
+      indexer: I::default(),
+      header: None,
+    }
+  }
+}
+
+impl<D: SerializableData, G: Digest, I: KeyIndex> NodeStore<D, G> for MemoryStore<D, G, I> {
+  fn get_node(&self, level: usize, index: usize) -> Result<Option<Node<D, G>>, MerkleError> {
+    Ok(self.nodes.get(&(level, index)).cloned())
+  }
+
+  fn put_node(&mut self, level: usize, index: usize, node: Node<D, G>) -> Result<(), MerkleError> {
+    self.nodes.insert((level, index), node);
+    Ok(())
+  }
+
+  fn get_index(&self, key: &[u8; 32]) -> Result<Option<usize>, MerkleError> {
+    Ok(self.indexer.get(key))
+  }
+
+  fn put_index(&mut self, key: [u8; 32], index: usize) -> Result<(), MerkleError> {
+    self.indexer.insert(key, index)
+  }
+
+  fn for_each_index(
+    &self,
+    f: &mut dyn FnMut([u8; 32], usize) -> Result<(), MerkleError>,
+  ) -> Result<(), MerkleError> {
+    for (key, index) in self.indexer.iter() {
+      f(key, index)?;
+    }
+    Ok(())
+  }
+
+  fn get_header(&self) -> Result<Option<TreeHeader>, MerkleError> {
+    Ok(self.header.clone())
+  }
+
+  fn put_header(&mut self, header: TreeHeader) -> Result<(), MerkleError> {
+    self.header = Some(header);
+    Ok(())
+  }
+
+  fn flush(&mut self) -> Result<(), MerkleError> {
+    self.indexer.seal();
+    Ok(())
+  }
+}
+
+impl<D: SerializableData, G: Digest, I: KeyIndex> std::fmt::Debug for MemoryStore<D, G, I> {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    f.debug_struct("MemoryStore")
+      .field(
+        "nodes",
+        &self
+          .nodes
+          .iter()
+          .map(|((level, index), node)| format!("({},{}) -> {}", level, index, node.hash.to_hex()))
+          .collect::<Vec<_>>(),
+      )
+      .field(
+        "indexer",
+        &self
+          .indexer
+          .iter()
+          .map(|(key, index)| format!("0x{} -> {}", hex::encode(key), index))
+          .collect::<Vec<_>>(),
+      )
+      .finish()
+  }
+}
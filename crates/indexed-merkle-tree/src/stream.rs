@@ -0,0 +1,471 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::{Digest, Hasher};
+use crate::node::SerializableData;
+use crate::ordering::{leaf_comparator, LeafOrdering};
+use crate::snapshot::{read_digest, write_header};
+use crate::tree::padded_len;
+
+// Core lib imports
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+// External imports
+use tempfile::TempDir;
+
+// leaf key, serialized data and decoded data, as read from and written to run files
+struct Leaf<D> {
+  key: [u8; 32],
+  bytes: Vec<u8>,
+  data: D,
+}
+
+// order of the leaves in a run
+type Cmp<'a, D> = &'a dyn Fn(&Leaf<D>, &Leaf<D>) -> Ordering;
+
+#[derive(Debug, Clone)]
+pub struct StreamOptions<D> {
+  // leaves sorted in memory at a time before being spilled to a run file
+  pub run_len: usize,
+  // most run files merged at once, more runs are merged in several passes
+  pub fan_in: usize,
+  // directory for the temporary run and level files, removed once the build completes
+  pub temp_dir: PathBuf,
+  // order of the merged leaves, ties keep key order. `Insertion` is not supported, as a merged
+  // leaf has no single position in the input.
+  pub ordering: LeafOrdering<D>,
+  // combine two leaves sharing a key, the first one came earlier in the input
+  pub merge: fn(D, D) -> D,
+  // same as `TreeOptions`
+  pub depth: Option<usize>,
+  pub arity: usize,
+}
+
+impl<D> Default for StreamOptions<D> {
+  fn default() -> Self {
+    Self {
+      run_len: 1 << 20,
+      fan_in: 64,
+      temp_dir: std::env::temp_dir(),
+      ordering: LeafOrdering::Key,
+      merge: |_, later| later,
+      depth: None,
+      arity: 2,
+    }
+  }
+}
+
+// Root and shape of a tree written by `build_snapshot`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamSummary<G> {
+  pub root: G,
+  pub height: usize,
+  pub leaf_count: usize,
+}
+
+// Build a tree from an iterator of leaves straight into a snapshot, holding at most `run_len` leaves
+// in memory. Leaves are sorted by key into run files, which are merged `fan_in` at a time into a
+// single deduplicated sequence. Unless the ordering is `LeafOrdering::Key`, the merged leaves are
+// sorted again by the ordering the same way. Each level is then hashed from the file of the level
+// below. The snapshot is the same as that of the in-memory tree built with the same ordering from
+// the deduplicated leaves in key order, and loads with `IndexedMerkleTree::load_snapshot_into`.
+pub fn build_snapshot<D, H, I, W>(
+  data: I,
+  hasher: &H,
+  writer: W,
+  options: StreamOptions<D>,
+) -> Result<StreamSummary<H::Digest>, MerkleError>
+where
+  D: SerializableData,
+  H: Hasher,
+  I: IntoIterator<Item = D>,
+  W: Write,
+{
+  let arity = options.arity;
+  if arity < 2 {
+    return Err(MerkleError::InvalidArity { arity });
+  }
+  // fails for `Insertion`, as a merged leaf has no single position in the input
+  let cmp = leaf_comparator(&options.ordering, hasher)?;
+
+  let work_dir = WorkDir::create(&options.temp_dir)?;
+  let by_key: Cmp<D> = &|a, b| a.key.cmp(&b.key);
+  let mut runs = RunWriter::new(by_key, Some(options.merge), &options, &work_dir);
+  for data in data {
+    runs.push(Leaf {
+      key: data.key(),
+      bytes: data.to_bytes(),
+      data,
+    })?;
+  }
+  let runs = runs.finish()?;
+
+  let mut leaves = BufWriter::new(File::create(work_dir.leaves())?);
+  let mut level = BufWriter::new(File::create(work_dir.level(0))?);
+  let mut leaf_count = 0;
+  let mut write = |leaf: Leaf<D>| {
+    write_leaf(&mut leaves, &mut level, &leaf.bytes, hasher)?;
+    leaf_count += 1;
+    Ok(())
+  };
+  match &options.ordering {
+    // merged leaves come out in key order
+    LeafOrdering::Key => merge_runs(
+      runs,
+      by_key,
+      Some(options.merge),
+      &options,
+      &work_dir,
+      &mut write,
+    )?,
+    _ => {
+      let by_ordering: Cmp<D> = &|a, b| cmp(&a.data, &b.data);
+      let mut sorted = RunWriter::new(by_ordering, None, &options, &work_dir);
+      merge_runs(
+        runs,
+        by_key,
+        Some(options.merge),
+        &options,
+        &work_dir,
+        &mut |leaf| sorted.push(leaf),
+      )?;
+      let runs = sorted.finish()?;
+      merge_runs(runs, by_ordering, None, &options, &work_dir, &mut write)?;
+    }
+  }
+  leaves.flush()?;
+  level.flush()?;
+  drop((leaves, level));
+
+  let Some(padded_len) = padded_len(leaf_count, arity) else {
+    return Err(MerkleError::CapacityExceeded {
+      len: leaf_count,
+      capacity: usize::MAX,
+    });
+  };
+  if let Some(depth) = options.depth {
+    let capacity = arity.checked_pow(depth as u32).unwrap_or(usize::MAX);
+    if leaf_count > capacity {
+      return Err(MerkleError::CapacityExceeded {
+        len: leaf_count,
+        capacity,
+      });
+    }
+  }
+
+  // pad level 0 with empty leaves, then hash each level from the one below
+  let mut level = BufWriter::new(File::options().append(true).open(work_dir.level(0))?);
+  for _ in leaf_count..padded_len {
+    level.write_all(H::zero().as_ref())?;
+  }
+  level.flush()?;
+  drop(level);
+
+  let mut height = 0;
+  let mut width = padded_len;
+  while width > 1 {
+    let mut reader = BufReader::new(File::open(work_dir.level(height))?);
+    let mut writer = BufWriter::new(File::create(work_dir.level(height + 1))?);
+    let mut children = Vec::with_capacity(arity);
+    for _ in 0..width / arity {
+      children.clear();
+      for _ in 0..arity {
+        children.push(read_digest(&mut reader)?);
+      }
+      writer.write_all(hasher.hash_children(&children).as_ref())?;
+    }
+    writer.flush()?;
+    width /= arity;
+    height += 1;
+  }
+  let base_height = height;
+  let mut root: H::Digest = read_digest(&mut File::open(work_dir.level(height))?)?;
+
+  // padding levels of a fixed-depth tree only store their leftmost node
+  let mut padding = Vec::new();
+  if let Some(depth) = options.depth {
+    let zero_hashes = hasher.zero_hashes_with_arity(depth, arity);
+    while height < depth {
+      let mut children = vec![zero_hashes[height]; arity];
+      children[0] = root;
+      root = hasher.hash_children(&children);
+      padding.push(root);
+      height += 1;
+    }
+  }
+
+  let mut writer = BufWriter::new(writer);
+  write_header(
+    &mut writer,
+    height,
+    base_height,
+    arity,
+    H::Digest::LEN,
+    &options.ordering.name(),
+    leaf_count,
+  )?;
+  io::copy(&mut File::open(work_dir.leaves())?, &mut writer)?;
+  for level in 0..=base_height {
+    io::copy(&mut File::open(work_dir.level(level))?, &mut writer)?;
+  }
+  for hash in &padding {
+    writer.write_all(hash.as_ref())?;
+  }
+  writer.flush()?;
+
+  Ok(StreamSummary {
+    root,
+    height,
+    leaf_count,
+  })
+}
+
+// sorts leaves into run files of at most `run_len` leaves, merging leaves sharing a key within a
+// run when given `merge`
+struct RunWriter<'a, D> {
+  cmp: Cmp<'a, D>,
+  merge: Option<fn(D, D) -> D>,
+  run_len: usize,
+  work_dir: &'a WorkDir,
+  run: Vec<Leaf<D>>,
+  runs: Vec<PathBuf>,
+}
+
+impl<'a, D: SerializableData> RunWriter<'a, D> {
+  fn new(
+    cmp: Cmp<'a, D>,
+    merge: Option<fn(D, D) -> D>,
+    options: &StreamOptions<D>,
+    work_dir: &'a WorkDir,
+  ) -> Self {
+    let run_len = options.run_len.max(1);
+    Self {
+      cmp,
+      merge,
+      run_len,
+      work_dir,
+      run: Vec::with_capacity(run_len),
+      runs: Vec::new(),
+    }
+  }
+
+  fn push(&mut self, leaf: Leaf<D>) -> Result<(), MerkleError> {
+    self.run.push(leaf);
+    if self.run.len() >= self.run_len {
+      self.spill()?;
+    }
+    Ok(())
+  }
+
+  fn spill(&mut self) -> Result<(), MerkleError> {
+    if self.run.is_empty() {
+      return Ok(());
+    }
+    // stable, so leaves that compare equal stay in input order
+    self.run.sort_by(self.cmp);
+
+    let path = self.work_dir.next_run();
+    let mut writer = BufWriter::new(File::create(&path)?);
+    let mut write = |leaf: Leaf<D>| write_record(&mut writer, &leaf.key, &leaf.bytes);
+    let mut dedup = Dedup::new(self.merge);
+    for leaf in self.run.drain(..) {
+      dedup.push(leaf, &mut write)?;
+    }
+    dedup.finish(&mut write)?;
+    writer.flush()?;
+    self.runs.push(path);
+    Ok(())
+  }
+
+  fn finish(mut self) -> Result<Vec<PathBuf>, MerkleError> {
+    self.spill()?;
+    Ok(self.runs)
+  }
+}
+
+// merge sorted runs into `emit`, at most `fan_in` at a time. Intermediate passes merge groups of
+// consecutive runs into new run files, so that leaves sharing a key still merge in input order.
+fn merge_runs<D: SerializableData>(
+  mut runs: Vec<PathBuf>,
+  cmp: Cmp<D>,
+  merge: Option<fn(D, D) -> D>,
+  options: &StreamOptions<D>,
+  work_dir: &WorkDir,
+  emit: &mut dyn FnMut(Leaf<D>) -> Result<(), MerkleError>,
+) -> Result<(), MerkleError> {
+  let fan_in = options.fan_in.max(2);
+  while runs.len() > fan_in {
+    let mut merged = Vec::with_capacity(runs.len().div_ceil(fan_in));
+    for group in runs.chunks(fan_in) {
+      let path = work_dir.next_run();
+      let mut writer = BufWriter::new(File::create(&path)?);
+      merge_group(group, cmp, merge, &mut |leaf| {
+        write_record(&mut writer, &leaf.key, &leaf.bytes)
+      })?;
+      writer.flush()?;
+      for run in group {
+        fs::remove_file(run)?;
+      }
+      merged.push(path);
+    }
+    runs = merged;
+  }
+  merge_group(&runs, cmp, merge, emit)?;
+  for run in &runs {
+    fs::remove_file(run)?;
+  }
+  Ok(())
+}
+
+fn merge_group<D: SerializableData>(
+  runs: &[PathBuf],
+  cmp: Cmp<D>,
+  merge: Option<fn(D, D) -> D>,
+  emit: &mut dyn FnMut(Leaf<D>) -> Result<(), MerkleError>,
+) -> Result<(), MerkleError> {
+  let mut readers = runs
+    .iter()
+    .map(|path| Ok(BufReader::new(File::open(path)?)))
+    .collect::<Result<Vec<_>, MerkleError>>()?;
+  let mut heads = readers
+    .iter_mut()
+    .map(read_leaf)
+    .collect::<Result<Vec<_>, MerkleError>>()?;
+
+  // the smallest head first, and for equal heads the earlier run, so that the merge is stable
+  let mut dedup = Dedup::new(merge);
+  loop {
+    let next = heads
+      .iter()
+      .enumerate()
+      .filter_map(|(run, head)| Some((run, head.as_ref()?)))
+      .min_by(|(_, a), (_, b)| cmp(a, b))
+      .map(|(run, _)| run);
+    let Some(run) = next else {
+      break;
+    };
+    let leaf = std::mem::replace(&mut heads[run], read_leaf(&mut readers[run])?);
+    if let Some(leaf) = leaf {
+      dedup.push(leaf, emit)?;
+    }
+  }
+  dedup.finish(emit)
+}
+
+// combines consecutive leaves sharing a key with `merge`, passing every other leaf on as is
+struct Dedup<D> {
+  merge: Option<fn(D, D) -> D>,
+  pending: Option<Leaf<D>>,
+}
+
+impl<D: SerializableData> Dedup<D> {
+  fn new(merge: Option<fn(D, D) -> D>) -> Self {
+    Self {
+      merge,
+      pending: None,
+    }
+  }
+
+  fn push(
+    &mut self,
+    leaf: Leaf<D>,
+    emit: &mut dyn FnMut(Leaf<D>) -> Result<(), MerkleError>,
+  ) -> Result<(), MerkleError> {
+    match (self.merge, self.pending.take()) {
+      (Some(merge), Some(earlier)) if earlier.key == leaf.key => {
+        let data = merge(earlier.data, leaf.data);
+        self.pending = Some(Leaf {
+          key: leaf.key,
+          bytes: data.to_bytes(),
+          data,
+        });
+      }
+      (_, earlier) => {
+        if let Some(earlier) = earlier {
+          emit(earlier)?;
+        }
+        self.pending = Some(leaf);
+      }
+    }
+    Ok(())
+  }
+
+  fn finish(
+    &mut self,
+    emit: &mut dyn FnMut(Leaf<D>) -> Result<(), MerkleError>,
+  ) -> Result<(), MerkleError> {
+    match self.pending.take() {
+      Some(leaf) => emit(leaf),
+      None => Ok(()),
+    }
+  }
+}
+
+// leaf data as laid out in snapshots, and its hash at level 0
+fn write_leaf<W: Write, H: Hasher>(
+  leaves: &mut W,
+  level: &mut W,
+  bytes: &[u8],
+  hasher: &H,
+) -> Result<(), MerkleError> {
+  leaves.write_all(&(bytes.len() as u32).to_le_bytes())?;
+  leaves.write_all(bytes)?;
+  level.write_all(hasher.hash_leaf(bytes).as_ref())?;
+  Ok(())
+}
+
+// run record: key || data length (u32) || data bytes
+fn write_record<W: Write>(writer: &mut W, key: &[u8; 32], bytes: &[u8]) -> Result<(), MerkleError> {
+  writer.write_all(key)?;
+  writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+  writer.write_all(bytes)?;
+  Ok(())
+}
+
+fn read_leaf<R: Read, D: SerializableData>(reader: &mut R) -> Result<Option<Leaf<D>>, MerkleError> {
+  let mut key = [0u8; 32];
+  match reader.read_exact(&mut key) {
+    Ok(()) => {}
+    Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+    Err(err) => return Err(err.into()),
+  }
+  let mut len = [0u8; 4];
+  reader.read_exact(&mut len)?;
+  let mut bytes = vec![0u8; u32::from_le_bytes(len) as usize];
+  reader.read_exact(&mut bytes)?;
+  let data = D::from_bytes(bytes.clone())?;
+  Ok(Some(Leaf { key, bytes, data }))
+}
+
+// temporary directory of a build, removed with its files when dropped
+struct WorkDir {
+  dir: TempDir,
+  runs: Cell<usize>, // run files created so far
+}
+
+impl WorkDir {
+  fn create(parent: &Path) -> Result<Self, MerkleError> {
+    Ok(Self {
+      dir: tempfile::Builder::new()
+        .prefix("imt-stream-")
+        .tempdir_in(parent)?,
+      runs: Cell::new(0),
+    })
+  }
+
+  fn next_run(&self) -> PathBuf {
+    let index = self.runs.replace(self.runs.get() + 1);
+    self.dir.path().join(format!("run-{}", index))
+  }
+
+  fn leaves(&self) -> PathBuf {
+    self.dir.path().join("leaves")
+  }
+
+  fn level(&self, level: usize) -> PathBuf {
+    self.dir.path().join(format!("level-{}", level))
+  }
+}
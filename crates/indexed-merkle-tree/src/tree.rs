@@ -1,204 +1,378 @@
 // Local imports
 use crate::errors::MerkleError;
-use crate::hasher::Hasher;
+use crate::hasher::{Digest, Hasher};
 use crate::node::{Node, SerializableData};
+use crate::ordering::{order_leaves, LeafOrdering, TreeMetadata};
 use crate::proof::MerkleProof;
-
-// Core lib imports
-use std::collections::HashMap;
+use crate::store::{MemoryStore, NodeStore, TreeHeader};
 
 #[derive(Clone)]
-pub struct IndexedMerkleTree<D: SerializableData, H: Hasher> {
-  pub root: Node<D>,
-  pub leaves: HashMap<(usize, usize), Node<D>>, // (level, index) -> node
+pub struct IndexedMerkleTree<
+  D: SerializableData,
+  H: Hasher,
+  S: NodeStore<D, H::Digest> = MemoryStore<D, <H as Hasher>::Digest>,
+> {
+  pub root: Node<D, H::Digest>,
+  pub store: S, // (level, index) -> node and key -> index position at level 0
   pub height: usize,
-  pub indexer: HashMap<[u8; 32], usize>, // key -> index position in `leaves`
-  hasher: H,
+  pub arity: usize, // children per internal node
+  pub metadata: TreeMetadata,
+  pub(crate) hasher: H,
+}
+
+#[derive(Debug, Clone)]
+pub struct TreeOptions<D> {
+  // pad the tree with empty subtrees up to this height, so every proof has `depth` levels
+  // whatever the number of leaves. Only the leftmost node of each padding level is stored.
+  pub depth: Option<usize>,
+  // children per internal node, proofs then hold `arity - 1` siblings per level
+  pub arity: usize,
+  // order of the leaves, recorded in the tree metadata
+  pub ordering: LeafOrdering<D>,
+}
+
+impl<D> Default for TreeOptions<D> {
+  fn default() -> Self {
+    Self {
+      depth: None,
+      arity: 2,
+      ordering: LeafOrdering::Insertion,
+    }
+  }
 }
 
 impl<D: SerializableData, H: Hasher> IndexedMerkleTree<D, H> {
+  // binary tree in memory, whose writes can't fail. Panics only if the leaves padded to a power of
+  // two overflow `usize`, see `with_options` to get the error instead.
   pub fn new(data: Vec<D>, hasher: H) -> Self {
-    let mut indexer: HashMap<[u8; 32], usize> = HashMap::new();
-    let mut leaves: HashMap<(usize, usize), Node<D>> = HashMap::new();
+    Self::build(data, hasher, MemoryStore::default()).unwrap()
+  }
 
-    // insert leaves into the tree
-    data.iter().enumerate().for_each(|(index, data)| {
-      let hash = hasher.hash_leaf(&data.to_bytes());
+  pub fn with_options(
+    data: Vec<D>,
+    hasher: H,
+    options: TreeOptions<D>,
+  ) -> Result<Self, MerkleError> {
+    Self::build_with_options(data, hasher, MemoryStore::default(), options)
+  }
+}
+
+impl<D: SerializableData, H: Hasher, S: NodeStore<D, H::Digest>> IndexedMerkleTree<D, H, S> {
+  // build the tree into the given store
+  pub fn build(data: Vec<D>, hasher: H, store: S) -> Result<Self, MerkleError> {
+    Self::build_with_options(data, hasher, store, TreeOptions::default())
+  }
 
-      leaves.insert(
-        (0, index),
+  pub fn build_with_options(
+    data: Vec<D>,
+    hasher: H,
+    mut store: S,
+    options: TreeOptions<D>,
+  ) -> Result<Self, MerkleError> {
+    let data_len = data.len();
+    let arity = options.arity;
+    if arity < 2 {
+      return Err(MerkleError::InvalidArity { arity });
+    }
+    let Some(padded_len) = padded_len(data_len, arity) else {
+      return Err(MerkleError::CapacityExceeded {
+        len: data_len,
+        capacity: usize::MAX,
+      });
+    };
+    if let Some(depth) = options.depth {
+      // a capacity beyond `usize::MAX` holds any data
+      let capacity = arity.checked_pow(depth as u32).unwrap_or(usize::MAX);
+      if data_len > capacity {
+        return Err(MerkleError::CapacityExceeded {
+          len: data_len,
+          capacity,
+        });
+      }
+    }
+
+    // insert leaves into the tree
+    for (index, (hash, data)) in order_leaves(data, &options.ordering, &hasher)
+      .into_iter()
+      .enumerate()
+    {
+      store.put_index(data.key(), index)?;
+      store.put_node(
+        0,
+        index,
         Node {
           hash,
-          data: Some(data.clone()),
+          data: Some(data),
         },
-      );
-      indexer.insert(data.key(), index);
-    });
-
-    // pad to next power of two with empty leaves
-    let padding_count = data.len().next_power_of_two() - data.len();
-    for i in 0..padding_count {
-      leaves.insert(
-        (0, data.len() + i),
+      )?;
+    }
+
+    // pad to the next power of the arity with empty leaves
+    for i in 0..padded_len - data_len {
+      store.put_node(
+        0,
+        data_len + i,
         Node {
           hash: H::zero(),
           data: None,
         },
-      );
+      )?;
     }
 
-    // build the tree by recursively hashing pairs of leaves
-    let (root, height) = build_tree(data.len(), &mut leaves, &hasher).unwrap();
+    // build the tree by recursively hashing groups of `arity` nodes
+    let (mut root, mut height) = build_tree(padded_len, arity, &mut store, &hasher)?;
 
-    Self {
+    // hash the root up to the fixed depth with empty subtrees on its right at each level
+    if let Some(depth) = options.depth {
+      let zero_hashes = hasher.zero_hashes_with_arity(depth, arity);
+      while height < depth {
+        let mut children = vec![zero_hashes[height]; arity];
+        children[0] = root.hash;
+        let hash = hasher.hash_children(&children);
+        height += 1;
+        root = Node { hash, data: None };
+        store.put_node(height, 0, root.clone())?;
+      }
+    }
+    let ordering = options.ordering.name();
+    store.put_header(TreeHeader {
+      height,
+      arity,
+      ordering: ordering.clone(),
+      leaf_count: data_len,
+    })?;
+    store.flush()?;
+
+    Ok(Self {
       root,
-      leaves,
+      store,
       height,
-      indexer,
+      arity,
+      metadata: TreeMetadata { ordering },
       hasher,
+    })
+  }
+
+  // open a tree previously built into a persistent store, its height, arity and leaf ordering are
+  // read back from the store. Only the header and the root are read, see `open_audited` to check
+  // the stored nodes as well.
+  pub fn open(store: S, hasher: H) -> Result<Self, MerkleError> {
+    let TreeHeader {
+      height,
+      arity,
+      ordering,
+      ..
+    } = store.get_header()?.ok_or(MerkleError::Storage {
+      reason: "no tree in store".to_string(),
+    })?;
+    if arity < 2 {
+      return Err(MerkleError::InvalidArity { arity });
     }
+    let root = store
+      .get_node(height, 0)?
+      .ok_or(MerkleError::NodeNotFound {
+        level: height,
+        index: 0,
+      })?;
+
+    Ok(Self {
+      root,
+      store,
+      height,
+      arity,
+      metadata: TreeMetadata { ordering },
+      hasher,
+    })
   }
 
-  pub fn get_proof(&self, key: [u8; 32]) -> Result<MerkleProof<D>, MerkleError> {
-    let target_index = *self
-      .indexer
-      .get(&key)
-      .ok_or(MerkleError::InvalidKey { key })?;
-    let mut index = target_index;
-    let target_node = self
-      .leaves
-      .get(&(0, index))
-      .ok_or(MerkleError::NodeNotFound { level: 0, index })?;
+  // open a tree and audit its stored nodes and key index, so a store that was corrupted or
+  // partially written is rejected. Reads every node of the tree.
+  pub fn open_audited(store: S, hasher: H) -> Result<Self, MerkleError> {
+    let tree = Self::open(store, hasher)?;
+    let report = tree.audit()?;
+    if !report.is_clean() {
+      return Err(MerkleError::AuditFailed {
+        discrepancies: report.discrepancies,
+      });
+    }
+    Ok(tree)
+  }
+
+  // number of data leaves, excluding padding, as recorded in the tree header
+  pub fn leaf_count(&self) -> Result<usize, MerkleError> {
+    let header = self.store.get_header()?.ok_or(MerkleError::Storage {
+      reason: "no tree in store".to_string(),
+    })?;
+    Ok(header.leaf_count)
+  }
 
-    // tree starts bottom up at level 0 (leaves) and goes up to the root (level `height - 1`)
-    let mut proof = Vec::new();
+  // height of the fully stored subtree holding the leaves, below the padding levels of a
+  // fixed-depth tree
+  pub(crate) fn base_height(&self) -> Result<usize, MerkleError> {
     let mut level = 0;
+    while level < self.height && self.store.get_node(level, 1)?.is_some() {
+      level += 1;
+    }
+    Ok(level)
+  }
 
-    while level < self.height {
-      let sibling_index = get_sibling_node(index);
-      let sibling_node =
-        self
-          .leaves
-          .get(&(level, sibling_index))
-          .ok_or(MerkleError::NodeNotFound {
-            level,
-            index: sibling_index,
-          })?;
-      proof.push(sibling_node.hash);
-      (level, index) = get_parent_node(level, index);
+  pub fn get_index(&self, key: [u8; 32]) -> Result<usize, MerkleError> {
+    self
+      .store
+      .get_index(&key)?
+      .ok_or(MerkleError::InvalidKey { key })
+  }
+
+  pub fn get_node(&self, level: usize, index: usize) -> Result<Node<D, H::Digest>, MerkleError> {
+    self
+      .store
+      .get_node(level, index)?
+      .ok_or(MerkleError::NodeNotFound { level, index })
+  }
+
+  pub fn get_proof(&self, key: [u8; 32]) -> Result<MerkleProof<D, H::Digest>, MerkleError> {
+    let target_index = self.get_index(key)?;
+    let mut index = target_index;
+    let target_node = self.get_node(0, index)?;
+
+    // tree starts bottom up at level 0 (leaves) and goes up to the root (level `height - 1`),
+    // collecting the other `arity - 1` nodes of the group at each level from left to right
+    let mut proof = Vec::with_capacity(self.height * (self.arity - 1));
+    let mut zero_hashes = None;
+    let mut base_height = None;
+
+    for level in 0..self.height {
+      let first = index - index % self.arity;
+      for sibling in (first..first + self.arity).filter(|sibling| *sibling != index) {
+        let sibling_hash = match self.store.get_node(level, sibling)? {
+          Some(node) => node.hash,
+          // empty subtrees padding a fixed-depth tree are not stored, any other missing node means
+          // the store is incomplete
+          None => {
+            let base_height = match base_height {
+              Some(base_height) => base_height,
+              None => *base_height.insert(self.base_height()?),
+            };
+            if level < base_height {
+              return Err(MerkleError::NodeNotFound {
+                level,
+                index: sibling,
+              });
+            }
+            zero_hashes
+              .get_or_insert_with(|| self.hasher.zero_hashes_with_arity(self.height, self.arity))
+              [level]
+          }
+        };
+        proof.push(sibling_hash);
+      }
+      index /= self.arity;
     }
 
+    // the index points at a padding leaf
+    let data = target_node.data.ok_or(MerkleError::NodeNotFound {
+      level: 0,
+      index: target_index,
+    })?;
+
     Ok(MerkleProof {
-      data: target_node.data.clone().unwrap(),
+      data,
+      index: target_index,
       proof,
     })
   }
 
-  pub fn verify_proof(&self, proof: MerkleProof<D>) -> Result<bool, MerkleError> {
-    let mut hash = self.hasher.hash_leaf(&proof.data.to_bytes());
-    let mut level = 0;
-    let mut index = *self.indexer.get(&proof.data.key()).unwrap();
-
-    for sibling_hash in proof.proof {
-      hash = if index % 2 == 0 {
-        self.hasher.hash_internal(&hash, &sibling_hash)
-      } else {
-        self.hasher.hash_internal(&sibling_hash, &hash)
-      };
-      (level, index) = get_parent_node(level, index);
-    }
-
-    if hash != self.root.hash {
-      return Err(MerkleError::InvalidRootHash {
-        exp: self.root.hash,
-        act: hash,
+  // the proof index is checked against the leaf of its key, so a proof can't claim another position
+  pub fn verify_proof(&self, proof: MerkleProof<D, H::Digest>) -> Result<bool, MerkleError> {
+    let index = self.get_index(proof.data.key())?;
+    if proof.index != index {
+      return Err(MerkleError::InvalidProofIndex {
+        exp: index,
+        act: proof.index,
       });
     }
-
-    Ok(true)
+    proof.verify_with_arity(self.root.hash, &self.hasher, self.arity)
   }
 }
 
-impl<D: SerializableData, H: Hasher> std::fmt::Debug for IndexedMerkleTree<D, H> {
+impl<D: SerializableData, H: Hasher, S: NodeStore<D, H::Digest> + std::fmt::Debug> std::fmt::Debug
+  for IndexedMerkleTree<D, H, S>
+{
   fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
     f.debug_struct("IndexedMerkleTree")
-      .field("root", &format!("0x{}", hex::encode(self.root.hash)))
-      .field(
-        "leaves",
-        &self
-          .leaves
-          .iter()
-          .map(|((level, index), node)| {
-            format!("({},{}) -> 0x{}", level, index, hex::encode(node.hash))
-          })
-          .collect::<Vec<_>>(),
-      )
+      .field("root", &self.root.hash.to_hex())
       .field("height", &self.height)
-      .field(
-        "indexer",
-        &self
-          .indexer
-          .iter()
-          .map(|(key, value)| format!("0x{} -> {}", hex::encode(key), value))
-          .collect::<Vec<_>>(),
-      )
+      .field("arity", &self.arity)
+      .field("metadata", &self.metadata)
+      .field("store", &self.store)
       .finish()
   }
 }
 
-fn build_tree<H: Hasher, D: SerializableData>(
-  data_len: usize,
-  leaves: &mut HashMap<(usize, usize), Node<D>>,
+// build the internal levels over `padded_len` stored leaves, which must be a power of the arity
+pub(crate) fn build_tree<H: Hasher, D: SerializableData, S: NodeStore<D, H::Digest>>(
+  padded_len: usize,
+  arity: usize,
+  store: &mut S,
   hasher: &H,
-) -> Result<(Node<D>, usize), MerkleError> {
-  if data_len < 2 {
-    return Err(MerkleError::InvalidDataLength { len: data_len });
-  }
-
-  let mut level = 1; // skip level 0 (leaves)
-  let mut index = 0;
-  let mut max_index = data_len.next_power_of_two() / 2 - 1;
-  let height = data_len.next_power_of_two().ilog2() as usize;
-
-  while level <= height {
-    while index <= max_index {
-      let left_node = get_left_node(level, index);
-      let right_node = get_right_node(level, index);
-      let left_hash = leaves.get(&left_node).unwrap().hash;
-      let right_hash = leaves.get(&right_node).unwrap().hash;
-      let hash = hasher.hash_internal(&left_hash, &right_hash);
-      leaves.insert((level, index), Node { hash, data: None });
-      index += 1;
+) -> Result<(Node<D, H::Digest>, usize), MerkleError> {
+  let mut height = 0;
+  let mut width = padded_len;
+  let mut children = Vec::with_capacity(arity);
+
+  // skip level 0 (leaves), a tree of a single leaf has that leaf as its root
+  while width > 1 {
+    width /= arity;
+    height += 1;
+    for index in 0..width {
+      children.clear();
+      for child in index * arity..(index + 1) * arity {
+        let node = store
+          .get_node(height - 1, child)?
+          .ok_or(MerkleError::NodeNotFound {
+            level: height - 1,
+            index: child,
+          })?;
+        children.push(node.hash);
+      }
+      let hash = hasher.hash_children(&children);
+      store.put_node(height, index, Node { hash, data: None })?;
     }
-    level += 1;
-    index = 0;
-    max_index = max_index / 2;
   }
 
   // get root node
-  let root = leaves
-    .get(&(height, 0))
+  let root = store
+    .get_node(height, 0)?
     .ok_or(MerkleError::NodeNotFound {
       level: height,
       index: 0,
-    })?
-    .clone();
+    })?;
 
   Ok((root, height))
 }
 
-fn get_parent_node(level: usize, index: usize) -> (usize, usize) {
-  (level + 1, index / 2)
+// smallest power of the arity holding `len` leaves, `None` if it overflows
+pub(crate) fn padded_len(len: usize, arity: usize) -> Option<usize> {
+  let mut padded = 1usize;
+  while padded < len {
+    padded = padded.checked_mul(arity)?;
+  }
+  Some(padded)
 }
 
-fn get_sibling_node(index: usize) -> usize {
-  index ^ 1
+// number of stored nodes at a level, given the height of the fully stored subtree
+pub(crate) fn level_width(base_height: usize, level: usize, arity: usize) -> usize {
+  if level <= base_height {
+    arity.pow((base_height - level) as u32)
+  } else {
+    1
+  }
 }
 
-fn get_left_node(level: usize, index: usize) -> (usize, usize) {
-  (level - 1, index * 2)
+pub(crate) fn get_parent_node(level: usize, index: usize) -> (usize, usize) {
+  (level + 1, index / 2)
 }
 
-fn get_right_node(level: usize, index: usize) -> (usize, usize) {
-  (level - 1, index * 2 + 1)
+pub(crate) fn get_sibling_node(index: usize) -> usize {
+  index ^ 1
 }
@@ -0,0 +1,58 @@
+// Local imports
+use crate::hasher::{hash_pair, Hasher, PairOrder};
+
+// Proof verification without allocating, for `no_std` environments such as on-chain programs and
+// hardware wallets. `MerkleProof` offers the same checks on top of leaf data types.
+
+// root of a binary tree reached from a leaf hash through its siblings from the bottom up, the bits
+// of the leaf index telling on which side each sibling is
+pub fn compute_root<H: Hasher>(
+  hasher: &H,
+  leaf: H::Digest,
+  index: usize,
+  proof: &[H::Digest],
+) -> H::Digest {
+  let mut hash = leaf;
+  let mut index = index;
+  for sibling in proof {
+    hash = if index.is_multiple_of(2) {
+      hasher.hash_internal(&hash, sibling)
+    } else {
+      hasher.hash_internal(sibling, &hash)
+    };
+    index /= 2;
+  }
+  hash
+}
+
+// same as `compute_root` for trees hashing each pair with the smaller hash first, which don't need
+// the index
+pub fn compute_sorted_root<H: Hasher>(
+  hasher: &H,
+  leaf: H::Digest,
+  proof: &[H::Digest],
+) -> H::Digest {
+  proof.iter().fold(leaf, |hash, sibling| {
+    hash_pair(hasher, PairOrder::Sorted, &hash, sibling)
+  })
+}
+
+// check the proof of an encoded leaf against a root
+pub fn verify<H: Hasher>(
+  hasher: &H,
+  root: &H::Digest,
+  leaf: &[u8],
+  index: usize,
+  proof: &[H::Digest],
+) -> bool {
+  compute_root(hasher, hasher.hash_leaf(leaf), index, proof) == *root
+}
+
+pub fn verify_sorted<H: Hasher>(
+  hasher: &H,
+  root: &H::Digest,
+  leaf: &[u8],
+  proof: &[H::Digest],
+) -> bool {
+  compute_sorted_root(hasher, hasher.hash_leaf(leaf), proof) == *root
+}
@@ -0,0 +1,234 @@
+// Local imports
+use crate::errors::MerkleError;
+use crate::hasher::{hash_pair, Digest, Hasher};
+use crate::node::SerializableData;
+use crate::proof::MerkleProof;
+
+// Core lib imports
+use std::collections::{HashMap, VecDeque};
+
+// defined with the hashers, so that the verifier can use it without `std`
+pub use crate::hasher::PairOrder;
+
+// Rough cost of an on-chain claim, fixed work plus one sibling per proof level. The defaults are
+// mainnet estimates for a MerkleDistributor-style claim: 512 gas of calldata, a 64-byte keccak and
+// loop overhead per sibling.
+#[derive(Debug, Clone, Copy)]
+pub struct GasModel {
+  pub base: u64,
+  pub per_level: u64,
+}
+
+impl Default for GasModel {
+  fn default() -> Self {
+    Self {
+      base: 60_000,
+      per_level: 600,
+    }
+  }
+}
+
+// Expected claim gas of the weighted layout and of a balanced tree over the same leaves. With
+// weights given as claim probabilities these are expected totals, other weights scale both alike.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasReport {
+  pub weighted: f64,
+  pub balanced: f64,
+}
+
+impl GasReport {
+  // fraction of the balanced layout gas saved by the weighted one
+  pub fn savings(&self) -> f64 {
+    if self.balanced == 0.0 {
+      return 0.0;
+    }
+    1.0 - self.weighted / self.balanced
+  }
+}
+
+#[derive(Debug, Clone)]
+struct WeightedNode<G> {
+  hash: G,
+  weight: f64,
+  parent: Option<usize>,
+  children: Option<(usize, usize)>,
+  depth: usize,
+}
+
+// Huffman-shaped tree where leaves with higher weights sit closer to the root, so that likely
+// claims carry shorter proofs. Leaves are nodes `0..n` of the arena, in data order, and internal
+// nodes follow in the order they were merged.
+#[derive(Clone)]
+pub struct WeightedMerkleTree<D: SerializableData, H: Hasher> {
+  pub root: H::Digest,
+  pub order: PairOrder,
+  data: Vec<D>,
+  nodes: Vec<WeightedNode<H::Digest>>,
+  indexer: HashMap<[u8; 32], usize>, // key -> leaf position
+  hasher: H,
+}
+
+impl<D: SerializableData, H: Hasher> WeightedMerkleTree<D, H> {
+  // build from (data, weight) pairs, weights must be finite and non-negative
+  pub fn new(data: Vec<(D, f64)>, hasher: H, order: PairOrder) -> Result<Self, MerkleError> {
+    let mut nodes = Vec::with_capacity(2 * data.len());
+    let mut leaves = Vec::with_capacity(data.len());
+    let mut indexer = HashMap::with_capacity(data.len());
+
+    for (index, (data, weight)) in data.into_iter().enumerate() {
+      if !weight.is_finite() || weight < 0.0 {
+        return Err(MerkleError::InvalidWeight { index });
+      }
+      indexer.insert(data.key(), index);
+      nodes.push(WeightedNode {
+        hash: hasher.hash_leaf(&data.to_bytes()),
+        weight,
+        parent: None,
+        children: None,
+        depth: 0,
+      });
+      leaves.push(data);
+    }
+
+    // two-queue Huffman: leaves sorted by weight, merged nodes come out in non-decreasing weight.
+    // The sort is stable and leaves win ties, so the layout only depends on the input order.
+    let mut sorted: Vec<usize> = (0..nodes.len()).collect();
+    sorted.sort_by(|a, b| nodes[*a].weight.total_cmp(&nodes[*b].weight));
+    let mut sorted = VecDeque::from(sorted);
+    let mut merged = VecDeque::new();
+
+    while sorted.len() + merged.len() > 1 {
+      let left = pop_lightest(&nodes, &mut sorted, &mut merged).unwrap();
+      let right = pop_lightest(&nodes, &mut sorted, &mut merged).unwrap();
+      let hash = hash_pair(&hasher, order, &nodes[left].hash, &nodes[right].hash);
+      let parent = nodes.len();
+      nodes.push(WeightedNode {
+        hash,
+        weight: nodes[left].weight + nodes[right].weight,
+        parent: None,
+        children: Some((left, right)),
+        depth: 0,
+      });
+      nodes[left].parent = Some(parent);
+      nodes[right].parent = Some(parent);
+      merged.push_back(parent);
+    }
+
+    // parents always follow their children, so depths are set top down in reverse order
+    for node in (0..nodes.len()).rev() {
+      if let Some(parent) = nodes[node].parent {
+        nodes[node].depth = nodes[parent].depth + 1;
+      }
+    }
+
+    // positional proofs carry the path as the bits of the leaf index
+    let max_depth = nodes.iter().map(|node| node.depth).max().unwrap_or(0);
+    if order == PairOrder::Positional && max_depth > usize::BITS as usize {
+      return Err(MerkleError::DepthExceeded {
+        depth: max_depth,
+        max: usize::BITS as usize,
+      });
+    }
+
+    Ok(Self {
+      root: nodes.last().map(|node| node.hash).unwrap_or(H::zero()),
+      order,
+      data: leaves,
+      nodes,
+      indexer,
+      hasher,
+    })
+  }
+
+  pub fn get_index(&self, key: [u8; 32]) -> Result<usize, MerkleError> {
+    self
+      .indexer
+      .get(&key)
+      .copied()
+      .ok_or(MerkleError::InvalidKey { key })
+  }
+
+  // number of proof levels of the leaf at `index`
+  pub fn depth(&self, index: usize) -> usize {
+    self.nodes[index].depth
+  }
+
+  // proof from the leaf up to the root. Bit `i` of a positional proof index is set when the node
+  // at level `i` is a right child, a sorted proof keeps the leaf position as its index.
+  pub fn get_proof(&self, key: [u8; 32]) -> Result<MerkleProof<D, H::Digest>, MerkleError> {
+    let leaf = self.get_index(key)?;
+    let mut proof = Vec::with_capacity(self.depth(leaf));
+    let mut path = 0;
+    let mut node = leaf;
+
+    while let Some(parent) = self.nodes[node].parent {
+      let (left, right) = self.nodes[parent].children.unwrap();
+      if node == right {
+        if self.order == PairOrder::Positional {
+          path |= 1 << proof.len();
+        }
+        proof.push(self.nodes[left].hash);
+      } else {
+        proof.push(self.nodes[right].hash);
+      }
+      node = parent;
+    }
+
+    Ok(MerkleProof {
+      data: self.data[leaf].clone(),
+      index: match self.order {
+        PairOrder::Positional => path,
+        PairOrder::Sorted => leaf,
+      },
+      proof,
+    })
+  }
+
+  pub fn verify_proof(&self, proof: MerkleProof<D, H::Digest>) -> Result<bool, MerkleError> {
+    match self.order {
+      PairOrder::Positional => proof.verify(self.root, &self.hasher),
+      PairOrder::Sorted => proof.verify_sorted(self.root, &self.hasher),
+    }
+  }
+
+  // expected claim gas, weighting the cost of each leaf's proof by its weight, against a balanced
+  // tree of the same leaves padded to a power of two
+  pub fn gas_report(&self, model: &GasModel) -> GasReport {
+    let leaves = &self.nodes[..self.data.len()];
+    let balanced_depth = self.data.len().next_power_of_two().trailing_zeros() as u64;
+    let cost = |depth: u64| (model.base + model.per_level * depth) as f64;
+
+    GasReport {
+      weighted: leaves
+        .iter()
+        .map(|leaf| leaf.weight * cost(leaf.depth as u64))
+        .sum(),
+      balanced: leaves
+        .iter()
+        .map(|leaf| leaf.weight * cost(balanced_depth))
+        .sum(),
+    }
+  }
+}
+
+impl<D: SerializableData, H: Hasher> std::fmt::Debug for WeightedMerkleTree<D, H> {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    f.debug_struct("WeightedMerkleTree")
+      .field("root", &self.root.to_hex())
+      .field("order", &self.order)
+      .field("leaves", &self.data.len())
+      .finish()
+  }
+}
+
+fn pop_lightest<G>(
+  nodes: &[WeightedNode<G>],
+  sorted: &mut VecDeque<usize>,
+  merged: &mut VecDeque<usize>,
+) -> Option<usize> {
+  match (sorted.front(), merged.front()) {
+    (Some(leaf), Some(node)) if nodes[*node].weight < nodes[*leaf].weight => merged.pop_front(),
+    (Some(_), _) => sorted.pop_front(),
+    (None, _) => merged.pop_front(),
+  }
+}
@@ -0,0 +1,172 @@
+use indexed_merkle_tree::abi::{decode, encode, encode_packed, ParamType, Token};
+use indexed_merkle_tree::errors::DecodeError;
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use primitive_types::U256;
+
+fn uint(value: u64) -> Token {
+  Token::Uint(U256::from(value))
+}
+
+fn words(words: &[&str]) -> Vec<u8> {
+  words
+    .iter()
+    .flat_map(|word| {
+      let word = format!("{:0>64}", word);
+      hex::decode(word).unwrap()
+    })
+    .collect()
+}
+
+fn padded(text: &str) -> String {
+  format!("{:0<64}", hex::encode(text))
+}
+
+#[test]
+fn test_encode_solidity_examples() {
+  // sam(bytes,bool,uint256[]) from the Solidity ABI specification
+  let tokens = vec![
+    Token::Bytes(b"dave".to_vec()),
+    Token::Bool(true),
+    Token::Array(vec![uint(1), uint(2), uint(3)]),
+  ];
+  let expected = words(&["60", "1", "a0", "4", &padded("dave"), "3", "1", "2", "3"]);
+  assert_eq!(encode(&tokens), expected);
+
+  // g(uint256[][],string[]) from the Solidity ABI specification
+  let tokens = vec![
+    Token::Array(vec![
+      Token::Array(vec![uint(1), uint(2)]),
+      Token::Array(vec![uint(3)]),
+    ]),
+    Token::Array(vec![
+      Token::String("one".to_string()),
+      Token::String("two".to_string()),
+      Token::String("three".to_string()),
+    ]),
+  ];
+  let expected = words(&[
+    "40",
+    "140",
+    "2",
+    "40",
+    "a0",
+    "2",
+    "1",
+    "2",
+    "1",
+    "3",
+    "3",
+    "60",
+    "a0",
+    "e0",
+    "3",
+    &padded("one"),
+    "3",
+    &padded("two"),
+    "5",
+    &padded("three"),
+  ]);
+  let bytes = encode(&tokens);
+  assert_eq!(bytes, expected);
+
+  let types = vec![
+    ParamType::Array(Box::new(ParamType::Array(Box::new(ParamType::Uint)))),
+    ParamType::Array(Box::new(ParamType::String)),
+  ];
+  assert_eq!(decode(&types, &bytes).unwrap(), tokens);
+
+  // static fixed arrays are inlined in the head
+  let tokens = vec![
+    Token::FixedArray(vec![Token::Address([0x11; 20]), Token::Address([0x22; 20])]),
+    Token::Bytes32([0xff; 32]),
+    Token::String(String::new()),
+  ];
+  let bytes = encode(&tokens);
+  assert_eq!(&bytes[96..128], &words(&["80"])[..]);
+  assert_eq!(bytes.len(), 160);
+  let types = vec![
+    ParamType::FixedArray(Box::new(ParamType::Address), 2),
+    ParamType::Bytes32,
+    ParamType::String,
+  ];
+  assert_eq!(decode(&types, &bytes).unwrap(), tokens);
+}
+
+#[test]
+fn test_encode_packed() {
+  let account = [0xb3; 20];
+  let amount = U256::from_dec_str("1500000000000000000").unwrap();
+  let packed = encode_packed(&[Token::Address(account), Token::Uint(amount)]);
+  assert_eq!(packed.len(), 52);
+  assert_eq!(&packed[..20], &account);
+  assert_eq!(
+    hex::encode(&packed[20..]),
+    "00000000000000000000000000000000000000000000000014d1120d7b160000"
+  );
+
+  // keccak256(abi.encodePacked("hello"))
+  assert_eq!(
+    hex::encode(KeccakHasher.hash_leaf(&encode_packed(&[Token::String("hello".to_string())]))),
+    "1c8aff950685c2ed4bc3174f3472287b56d9517b9c948127319a09a7a36deac8"
+  );
+
+  // array elements are padded to a full word
+  let packed = encode_packed(&[
+    Token::Bool(true),
+    Token::Array(vec![Token::Address([0x11; 20]), Token::Bool(false)]),
+  ]);
+  let mut expected = vec![1];
+  expected.extend_from_slice(&words(&[&hex::encode([0x11; 20]), "0"]));
+  assert_eq!(packed, expected);
+}
+
+#[test]
+fn test_decode_errors() {
+  let bytes = encode(&[uint(1), Token::String("dave".to_string())]);
+  let types = [ParamType::Uint, ParamType::String];
+
+  assert_eq!(
+    decode(&types, &bytes[..40]).unwrap_err(),
+    DecodeError {
+      offset: 32,
+      reason: "unexpected end of input",
+    }
+  );
+
+  let mut bad_offset = bytes.clone();
+  bad_offset[63] = 0xff;
+  assert_eq!(
+    decode(&types, &bad_offset).unwrap_err(),
+    DecodeError {
+      offset: 32,
+      reason: "offset out of range",
+    }
+  );
+
+  let mut bad_length = bytes.clone();
+  bad_length[95] = 0xff;
+  assert_eq!(
+    decode(&types, &bad_length).unwrap_err(),
+    DecodeError {
+      offset: 96,
+      reason: "unexpected end of input",
+    }
+  );
+
+  let huge_array = words(&["20", "ffffffff"]);
+  assert_eq!(
+    decode(&[ParamType::Array(Box::new(ParamType::Uint))], &huge_array).unwrap_err(),
+    DecodeError {
+      offset: 32,
+      reason: "array length out of range",
+    }
+  );
+
+  let dirty_address = words(&["10000000000000000000000000000000000000001"]);
+  assert_eq!(
+    decode(&[ParamType::Address], &dirty_address)
+      .unwrap_err()
+      .reason,
+    "address out of range"
+  );
+}
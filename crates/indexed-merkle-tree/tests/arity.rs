@@ -0,0 +1,131 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+
+fn kary_tree(len: usize, arity: usize) -> IndexedMerkleTree<TestData, KeccakHasher> {
+  IndexedMerkleTree::with_options(
+    test_data(len),
+    KeccakHasher,
+    TreeOptions {
+      arity,
+      ..Default::default()
+    },
+  )
+  .unwrap()
+}
+
+#[test]
+fn test_kary_proofs() {
+  for (arity, len, height) in [(4, 5, 2), (4, 16, 2), (4, 17, 3), (16, 20, 2), (16, 1, 0)] {
+    let tree = kary_tree(len, arity);
+    assert_eq!(tree.height, height);
+    assert_eq!(tree.arity, arity);
+
+    for data in test_data(len) {
+      let proof = tree.get_proof(data.key()).unwrap();
+      assert_eq!(proof.proof.len(), height * (arity - 1));
+      assert!(proof
+        .verify_with_arity(tree.root.hash, &KeccakHasher, arity)
+        .unwrap());
+      assert!(tree.verify_proof(proof).unwrap());
+    }
+  }
+}
+
+#[test]
+fn test_kary_root_by_hand() {
+  // 4-ary tree of 3 leaves: one group of the leaves and a zero leaf
+  let tree = kary_tree(3, 4);
+  let leaves: Vec<_> = test_data(3)
+    .iter()
+    .map(|data| KeccakHasher.hash_leaf(&data.to_bytes()))
+    .collect();
+  let exp = KeccakHasher.hash_children(&[leaves[0], leaves[1], leaves[2], [0; 32]]);
+  assert_eq!(tree.root.hash, exp);
+
+  // the proof of the second leaf holds the other three nodes in order
+  let proof = tree.get_proof(test_data(3)[1].key()).unwrap();
+  assert_eq!(proof.proof, vec![leaves[0], leaves[2], [0; 32]]);
+
+  // binary trees are unchanged by the default options
+  let binary = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(3), KeccakHasher);
+  assert_eq!(binary.arity, 2);
+  assert_eq!(kary_tree(3, 2).root.hash, binary.root.hash);
+}
+
+#[test]
+fn test_kary_fixed_depth_audit_and_snapshot() {
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::with_options(
+    test_data(6),
+    KeccakHasher,
+    TreeOptions {
+      depth: Some(4),
+      arity: 4,
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  assert_eq!(tree.height, 4);
+  assert!(tree.audit().unwrap().is_clean());
+
+  let proof = tree.get_proof(test_data(6)[5].key()).unwrap();
+  assert_eq!(proof.proof.len(), 12);
+  assert!(tree.verify_proof(proof).unwrap());
+
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+  let loaded =
+    IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher).unwrap();
+  assert_eq!(loaded.root.hash, tree.root.hash);
+  assert_eq!(loaded.arity, 4);
+  let proof = loaded.get_proof(test_data(6)[0].key()).unwrap();
+  assert!(loaded.verify_proof(proof).unwrap());
+
+  // capacity is arity^depth
+  let err = IndexedMerkleTree::<TestData, KeccakHasher>::with_options(
+    test_data(17),
+    KeccakHasher,
+    TreeOptions {
+      depth: Some(2),
+      arity: 4,
+      ..Default::default()
+    },
+  )
+  .unwrap_err();
+  assert!(matches!(
+    err,
+    MerkleError::CapacityExceeded {
+      len: 17,
+      capacity: 16
+    }
+  ));
+}
+
+#[test]
+fn test_invalid_arity() {
+  let err = IndexedMerkleTree::<TestData, KeccakHasher>::with_options(
+    test_data(3),
+    KeccakHasher,
+    TreeOptions {
+      arity: 1,
+      ..Default::default()
+    },
+  )
+  .unwrap_err();
+  assert!(matches!(err, MerkleError::InvalidArity { arity: 1 }));
+
+  // a 4-ary proof does not split into binary levels of the wrong shape
+  let tree = kary_tree(5, 4);
+  let proof = tree.get_proof(test_data(5)[0].key()).unwrap();
+  let err = proof
+    .verify_with_arity(tree.root.hash, &KeccakHasher, 4 + 1)
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    MerkleError::InvalidProofLength { len: 6, arity: 5 }
+  ));
+}
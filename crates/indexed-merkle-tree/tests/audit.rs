@@ -0,0 +1,126 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::audit::Discrepancy;
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+
+fn build_tree() -> IndexedMerkleTree<TestData, KeccakHasher> {
+  let data: Vec<TestData> = test_data(6);
+  IndexedMerkleTree::<TestData, KeccakHasher>::new(data, KeccakHasher)
+}
+
+#[test]
+fn test_audit_passes_for_built_tree() {
+  assert!(build_tree().audit().unwrap().is_clean());
+}
+
+#[test]
+fn test_audit_reports_every_discrepancy() {
+  let mut tree = build_tree();
+
+  // corrupt a leaf's data, an internal node, a padding leaf and the key index
+  tree.store.nodes.get_mut(&(0, 1)).unwrap().data = Some(TestData("forged".to_string()));
+  tree.store.nodes.get_mut(&(1, 2)).unwrap().hash = [1; 32];
+  tree.store.nodes.get_mut(&(0, 7)).unwrap().hash = [2; 32];
+  tree
+    .store
+    .indexer
+    .insert(TestData("leaf-4".to_string()).key(), 5);
+  tree.root.hash = [3; 32];
+
+  let report = tree.audit().unwrap();
+  let forged_key = TestData("forged".to_string()).key();
+  let kinds: Vec<&Discrepancy> = report.discrepancies.iter().collect();
+
+  assert_eq!(report.discrepancies.len(), 9, "{:?}", kinds);
+  assert!(matches!(kinds[0], Discrepancy::LeafHash { index: 1, .. }));
+  assert!(matches!(kinds[1], Discrepancy::UnindexedKey { key, index: 1 } if *key == forged_key));
+  assert!(matches!(
+    kinds[2],
+    Discrepancy::MisindexedKey { exp: 4, act: 5, .. }
+  ));
+  assert!(matches!(
+    kinds[3],
+    Discrepancy::PaddingHash { index: 7, .. }
+  ));
+  // both the corrupted node and the parent of the corrupted padding leaf no longer match their
+  // children, and neither does the level 2 node above them
+  assert!(matches!(
+    kinds[4],
+    Discrepancy::InternalHash {
+      level: 1,
+      index: 2,
+      ..
+    }
+  ));
+  assert!(matches!(
+    kinds[5],
+    Discrepancy::InternalHash {
+      level: 1,
+      index: 3,
+      ..
+    }
+  ));
+  assert!(matches!(
+    kinds[6],
+    Discrepancy::InternalHash {
+      level: 2,
+      index: 1,
+      ..
+    }
+  ));
+  assert!(matches!(kinds[7], Discrepancy::RootHash { .. }));
+  // the key of the forged leaf is still indexed at its old position
+  let old_key = TestData("leaf-1".to_string()).key();
+  assert!(matches!(kinds[8], Discrepancy::DanglingKey { key, index: 1 } if *key == old_key));
+}
+
+#[test]
+fn test_audit_checks_every_indexed_key() {
+  let mut tree = build_tree();
+  let removed = TestData("removed".to_string()).key();
+  let padding = TestData("padding".to_string()).key();
+  tree.store.indexer.insert(removed, 2);
+  tree.store.indexer.insert(padding, 7);
+  tree.store.indexer.insert([9; 32], 100);
+
+  let report = tree.audit().unwrap();
+  assert_eq!(
+    report.discrepancies,
+    [
+      Discrepancy::DanglingKey {
+        key: removed,
+        index: 2
+      },
+      Discrepancy::DanglingKey {
+        key: padding,
+        index: 7
+      },
+      Discrepancy::DanglingKey {
+        key: [9; 32],
+        index: 100
+      },
+    ]
+  );
+}
+
+#[test]
+fn test_snapshot_load_fails_audit_on_corrupt_hash() {
+  let tree = build_tree();
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+
+  // flip a bit in the root hash at the end of the snapshot
+  let last = bytes.len() - 1;
+  bytes[last] ^= 1;
+
+  let err = IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher)
+    .expect_err("Corrupt snapshot");
+  assert!(matches!(
+    err,
+    MerkleError::AuditFailed { discrepancies } if discrepancies.len() == 1
+  ));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::batch::{verify_batch, verify_batch_parallel};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::proof::MerkleProof;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+
+fn batch_proofs() -> (
+  IndexedMerkleTree<TestData, KeccakHasher>,
+  Vec<MerkleProof<TestData>>,
+) {
+  let data: Vec<TestData> = test_data(40);
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(data.clone(), KeccakHasher);
+  let mut proofs: Vec<MerkleProof<TestData>> = data
+    .iter()
+    .map(|entry| tree.get_proof(entry.key()).unwrap())
+    .collect();
+
+  // tamper with proofs whose neighbours share most of their path
+  proofs[3].proof[0] = [1; 32];
+  proofs[8].proof[4] = [2; 32];
+  proofs[9].data = TestData("forged".to_string());
+  proofs[20].index ^= 1;
+  let duplicate = tree.get_proof(data[0].key()).unwrap();
+  proofs.push(duplicate);
+
+  (tree, proofs)
+}
+
+#[test]
+fn test_batch_matches_individual_verification() {
+  let (tree, proofs) = batch_proofs();
+  let results = verify_batch(tree.root.hash, &proofs, &KeccakHasher, 2);
+
+  assert_eq!(results.len(), proofs.len());
+  for (proof, result) in proofs.iter().zip(results) {
+    let expected = proof.verify(tree.root.hash, &KeccakHasher).is_ok();
+    assert_eq!(result.is_ok(), expected, "proof at index {}", proof.index);
+  }
+  let valid = verify_batch(tree.root.hash, &proofs, &KeccakHasher, 2)
+    .iter()
+    .filter(|result| result.is_ok())
+    .count();
+  assert_eq!(valid, proofs.len() - 4);
+}
+
+#[test]
+fn test_parallel_batch_matches_sequential() {
+  let (tree, proofs) = batch_proofs();
+  let sequential = verify_batch(tree.root.hash, &proofs, &KeccakHasher, 2);
+  let parallel = verify_batch_parallel(tree.root.hash, &proofs, &KeccakHasher, 2);
+
+  let sequential: Vec<bool> = sequential.iter().map(|result| result.is_ok()).collect();
+  let parallel: Vec<bool> = parallel.iter().map(|result| result.is_ok()).collect();
+  assert_eq!(parallel, sequential);
+}
+
+#[test]
+fn test_batch_verifies_other_arities() {
+  let data: Vec<TestData> = test_data(20);
+  for arity in [3, 4] {
+    let options = TreeOptions {
+      arity,
+      ..Default::default()
+    };
+    let tree = IndexedMerkleTree::<TestData, KeccakHasher>::with_options(
+      data.clone(),
+      KeccakHasher,
+      options,
+    )
+    .unwrap();
+    let mut proofs: Vec<MerkleProof<TestData>> = data
+      .iter()
+      .map(|entry| tree.get_proof(entry.key()).unwrap())
+      .collect();
+    proofs[5].proof[1] = [1; 32];
+
+    for results in [
+      verify_batch(tree.root.hash, &proofs, &KeccakHasher, arity),
+      verify_batch_parallel(tree.root.hash, &proofs, &KeccakHasher, arity),
+    ] {
+      assert_eq!(results.len(), proofs.len());
+      for (index, result) in results.iter().enumerate() {
+        match index {
+          5 => assert!(matches!(result, Err(MerkleError::InvalidRootHash { .. }))),
+          _ => assert!(result.as_ref().unwrap()),
+        }
+      }
+    }
+  }
+}
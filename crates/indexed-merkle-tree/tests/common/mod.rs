@@ -0,0 +1,59 @@
+// helpers shared by the integration tests, each test binary only uses some of them
+#![allow(dead_code)]
+
+use indexed_merkle_tree::errors::DecodeError;
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use indexed_merkle_tree::node::SerializableData;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TestData(pub String);
+
+impl SerializableData for TestData {
+  fn to_bytes(&self) -> Vec<u8> {
+    self.0.as_bytes().to_vec()
+  }
+
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError> {
+    Ok(TestData(String::from_utf8(bytes)?))
+  }
+
+  fn key(&self) -> [u8; 32] {
+    KeccakHasher.hash_leaf(self.0.as_bytes())
+  }
+}
+
+pub fn test_data(len: usize) -> Vec<TestData> {
+  (0..len).map(|i| TestData(format!("leaf-{}", i))).collect()
+}
+
+// up to `max_len` random bytes, seeded so failures can be replayed
+pub fn random_bytes(rng: &mut fastrand::Rng, max_len: usize) -> Vec<u8> {
+  (0..rng.usize(..=max_len)).map(|_| rng.u8(..)).collect()
+}
+
+// flip, insert, remove or truncate bytes of a valid encoding
+pub fn mutate(rng: &mut fastrand::Rng, bytes: &[u8]) -> Vec<u8> {
+  let mut bytes = bytes.to_vec();
+  for _ in 0..rng.usize(1..=4) {
+    match rng.usize(..4) {
+      0 if !bytes.is_empty() => {
+        let i = rng.usize(..bytes.len());
+        bytes[i] ^= 1 << rng.u32(..8);
+      }
+      1 => {
+        let i = rng.usize(..=bytes.len());
+        bytes.insert(i, rng.u8(..));
+      }
+      2 if !bytes.is_empty() => {
+        let i = rng.usize(..bytes.len());
+        bytes.remove(i);
+      }
+      _ => {
+        let len = rng.usize(..=bytes.len());
+        bytes.truncate(len);
+      }
+    }
+  }
+  bytes
+}
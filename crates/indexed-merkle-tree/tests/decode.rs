@@ -0,0 +1,132 @@
+mod common;
+
+use common::{mutate, random_bytes};
+use indexed_merkle_tree::abi::{self, ParamType, Token};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::proof::MerkleProof;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+
+#[derive(Debug, Clone, PartialEq, SerializableData)]
+struct Entry {
+  #[key]
+  address: String,
+  amount: String,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializableData)]
+struct Claim {
+  #[key]
+  #[merkle(encoding = "abi")]
+  account: [u8; 20],
+  #[merkle(encoding = "abi")]
+  amount: u128,
+  flag: bool,
+  #[merkle(encoding = "packed")]
+  memo: String,
+}
+
+const ITERATIONS: usize = 5_000;
+
+fn entries() -> Vec<Entry> {
+  (0..5)
+    .map(|i| Entry {
+      address: format!("0x{:040x}", i),
+      amount: (i * 100).to_string(),
+    })
+    .collect()
+}
+
+#[test]
+fn test_arbitrary_leaf_bytes_never_panic() {
+  let mut rng = fastrand::Rng::with_seed(0x9e37_79b9_7f4a_7c15);
+  let entry = entries().remove(1);
+  let claim = Claim {
+    account: [7; 20],
+    amount: 42,
+    flag: true,
+    memo: "memo".to_string(),
+  };
+
+  for _ in 0..ITERATIONS {
+    let bytes = random_bytes(&mut rng, 96);
+    let _ = Entry::from_bytes(bytes.clone());
+    let _ = Claim::from_bytes(bytes);
+
+    // a decoded value always re-encodes to its input
+    let bytes = mutate(&mut rng, &entry.to_bytes());
+    if let Ok(decoded) = Entry::from_bytes(bytes.clone()) {
+      assert_eq!(decoded.to_bytes(), bytes);
+    }
+    let bytes = mutate(&mut rng, &claim.to_bytes());
+    if let Ok(decoded) = Claim::from_bytes(bytes.clone()) {
+      assert_eq!(decoded.to_bytes(), bytes);
+    }
+  }
+}
+
+#[test]
+fn test_arbitrary_abi_bytes_never_panic() {
+  let mut rng = fastrand::Rng::with_seed(0x2545_f491_4f6c_dd1d);
+  let types = [
+    ParamType::Address,
+    ParamType::Array(Box::new(ParamType::String)),
+    ParamType::FixedArray(Box::new(ParamType::Bytes), 2),
+    ParamType::Bool,
+  ];
+  let valid = abi::encode(&[
+    Token::Address([1; 20]),
+    Token::Array(vec![
+      Token::String("a".to_string()),
+      Token::String("bc".to_string()),
+    ]),
+    Token::FixedArray(vec![Token::Bytes(vec![1, 2]), Token::Bytes(vec![])]),
+    Token::Bool(true),
+  ]);
+  assert!(abi::decode(&types, &valid).is_ok());
+
+  for _ in 0..ITERATIONS {
+    let _ = abi::decode(&types, &random_bytes(&mut rng, 320));
+    let _ = abi::decode(&types, &mutate(&mut rng, &valid));
+  }
+}
+
+#[test]
+fn test_arbitrary_proof_and_snapshot_bytes_never_panic() {
+  let mut rng = fastrand::Rng::with_seed(0xdead_beef_cafe_f00d);
+  let tree = IndexedMerkleTree::<Entry, KeccakHasher>::new(entries(), KeccakHasher);
+  let entry = entries().remove(2);
+  let proof = tree
+    .get_proof(entry.key())
+    .unwrap()
+    .encode(&KeccakHasher, 2)
+    .unwrap();
+  let mut snapshot = Vec::new();
+  tree.save_snapshot(&mut snapshot).unwrap();
+
+  // proof decoding computes zero hashes up to the claimed length, and snapshot loads hash the whole
+  // tree, so run fewer of both
+  for _ in 0..ITERATIONS / 10 {
+    let _ = MerkleProof::decode(
+      entry.clone(),
+      &random_bytes(&mut rng, 128),
+      &KeccakHasher,
+      2,
+    );
+    let _ = MerkleProof::decode(entry.clone(), &mutate(&mut rng, &proof), &KeccakHasher, 2);
+  }
+
+  for _ in 0..ITERATIONS / 10 {
+    let bytes = mutate(&mut rng, &snapshot);
+    let _ = IndexedMerkleTree::<Entry, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher);
+  }
+
+  // a corrupt leaf is reported as a decode error rather than a panic, the first address starts
+  // after the header, the "insertion" ordering and two length prefixes
+  let mut corrupt = snapshot.clone();
+  corrupt[46] = 0xff;
+  let err = IndexedMerkleTree::<Entry, KeccakHasher>::load_snapshot(&corrupt[..], KeccakHasher)
+    .unwrap_err();
+  assert!(matches!(err, MerkleError::Decode { .. }), "{:?}", err);
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+
+fn fixed_depth_tree(len: usize, depth: usize) -> IndexedMerkleTree<TestData, KeccakHasher> {
+  IndexedMerkleTree::with_options(
+    test_data(len),
+    KeccakHasher,
+    TreeOptions {
+      depth: Some(depth),
+      ..Default::default()
+    },
+  )
+  .unwrap()
+}
+
+#[test]
+fn test_fixed_depth_proofs() {
+  let tree = fixed_depth_tree(5, 20);
+  assert_eq!(tree.height, 20);
+
+  // the 8-leaf subtree is stored in full, the 17 levels above it only hold their leftmost node
+  assert_eq!(tree.store.nodes.len(), 15 + 17);
+
+  let zero_hashes = KeccakHasher.zero_hashes(20);
+  let natural = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(5), KeccakHasher);
+  let mut root = natural.root.hash;
+  for zero_hash in &zero_hashes[3..] {
+    root = KeccakHasher.hash_internal(&root, zero_hash);
+  }
+  assert_eq!(tree.root.hash, root);
+
+  for entry in test_data(5) {
+    let proof = tree.get_proof(entry.key()).unwrap();
+    assert_eq!(proof.proof.len(), 20);
+    assert_eq!(&proof.proof[3..], &zero_hashes[3..]);
+    assert!(tree.verify_proof(proof).unwrap());
+  }
+
+  // the proof length only depends on the depth
+  let larger = fixed_depth_tree(300, 20);
+  let proof = larger
+    .get_proof(TestData("leaf-299".to_string()).key())
+    .unwrap();
+  assert_eq!(proof.proof.len(), 20);
+  assert!(larger.verify_proof(proof).unwrap());
+
+  // a depth matching the natural height builds the usual tree
+  assert_eq!(fixed_depth_tree(5, 3).root.hash, natural.root.hash);
+  assert!(tree.audit().unwrap().is_clean());
+}
+
+#[test]
+fn test_fixed_depth_proof_rejects_missing_nodes() {
+  // only the empty subtrees above the stored leaves stand in for missing siblings
+  let mut tree = fixed_depth_tree(5, 20);
+  tree.store.nodes.remove(&(0, 3));
+  let err = tree
+    .get_proof(TestData("leaf-2".to_string()).key())
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    MerkleError::NodeNotFound { level: 0, index: 3 }
+  ));
+
+  let mut natural = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(5), KeccakHasher);
+  natural.store.nodes.remove(&(1, 3));
+  let err = natural
+    .get_proof(TestData("leaf-4".to_string()).key())
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    MerkleError::NodeNotFound { level: 1, index: 3 }
+  ));
+}
+
+#[test]
+fn test_fixed_depth_capacity() {
+  let err = IndexedMerkleTree::<TestData, KeccakHasher>::with_options(
+    test_data(9),
+    KeccakHasher,
+    TreeOptions {
+      depth: Some(3),
+      ..Default::default()
+    },
+  )
+  .unwrap_err();
+  assert!(matches!(
+    err,
+    MerkleError::CapacityExceeded {
+      len: 9,
+      capacity: 8
+    }
+  ));
+
+  let full = fixed_depth_tree(8, 3);
+  assert_eq!(full.height, 3);
+  assert_eq!(fixed_depth_tree(0, 4).height, 4);
+}
+
+#[test]
+fn test_fixed_depth_snapshot() {
+  let tree = fixed_depth_tree(5, 32);
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+
+  let loaded =
+    IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher).unwrap();
+  assert_eq!(loaded.root.hash, tree.root.hash);
+  assert_eq!(loaded.height, 32);
+  assert_eq!(loaded.store.nodes.len(), tree.store.nodes.len());
+  let proof = loaded
+    .get_proof(TestData("leaf-4".to_string()).key())
+    .unwrap();
+  assert_eq!(proof.proof.len(), 32);
+  assert!(tree.verify_proof(proof).unwrap());
+}
@@ -0,0 +1,122 @@
+use indexed_merkle_tree::errors::DecodeError;
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+
+#[derive(Debug, Clone, PartialEq, SerializableData)]
+struct Claim {
+  #[key]
+  #[merkle(encoding = "abi")]
+  account: [u8; 20],
+  #[merkle(encoding = "abi")]
+  amount: u128,
+  #[merkle(encoding = "packed")]
+  round: u32,
+  memo: String,
+}
+
+#[derive(Debug, Clone, PartialEq, SerializableData)]
+struct Entry {
+  #[key]
+  address: String,
+  amount: String,
+}
+
+fn claim() -> Claim {
+  Claim {
+    account: [0xab; 20],
+    amount: 1_000_000,
+    round: 7,
+    memo: "airdrop".to_string(),
+  }
+}
+
+#[test]
+fn test_derived_encoding() {
+  let claim = claim();
+  let bytes = claim.to_bytes();
+
+  let mut expected = vec![0u8; 12];
+  expected.extend_from_slice(&[0xab; 20]);
+  expected.extend_from_slice(&[0u8; 16]);
+  expected.extend_from_slice(&1_000_000u128.to_be_bytes());
+  expected.extend_from_slice(&7u32.to_be_bytes());
+  expected.extend_from_slice(&7u32.to_le_bytes());
+  expected.extend_from_slice(b"airdrop");
+  assert_eq!(bytes, expected);
+  assert_eq!(claim.key(), KeccakHasher.hash_leaf(&[0xab; 20]));
+  assert_eq!(Claim::from_bytes(bytes), Ok(claim));
+
+  // matches the hand-written length-prefixed layout
+  let entry = Entry {
+    address: "0x1234".to_string(),
+    amount: "42".to_string(),
+  };
+  let mut expected = Vec::new();
+  expected.extend_from_slice(&6u32.to_le_bytes());
+  expected.extend_from_slice(b"0x1234");
+  expected.extend_from_slice(&2u32.to_le_bytes());
+  expected.extend_from_slice(b"42");
+  assert_eq!(entry.to_bytes(), expected);
+  assert_eq!(entry.key(), KeccakHasher.hash_leaf(b"0x1234"));
+
+  let tree = IndexedMerkleTree::<Entry, KeccakHasher>::new(vec![entry.clone()], KeccakHasher);
+  let proof = tree.get_proof(entry.key()).unwrap();
+  assert_eq!(proof.data, entry);
+  assert!(tree.verify_proof(proof).unwrap());
+}
+
+#[test]
+fn test_derived_decoding_errors() {
+  let bytes = claim().to_bytes();
+
+  let truncated = Claim::try_from_bytes(&bytes[..40]).unwrap_err();
+  assert_eq!(
+    truncated,
+    DecodeError {
+      offset: 32,
+      reason: "unexpected end of input",
+    }
+  );
+
+  let mut dirty = bytes.clone();
+  dirty[0] = 1;
+  assert_eq!(
+    Claim::try_from_bytes(&dirty).unwrap_err(),
+    DecodeError {
+      offset: 0,
+      reason: "address out of range",
+    }
+  );
+
+  let mut invalid = bytes.clone();
+  invalid[72] = 0xff;
+  assert_eq!(
+    Claim::try_from_bytes(&invalid).unwrap_err().reason,
+    "invalid utf-8"
+  );
+
+  let mut trailing = bytes.clone();
+  trailing.push(0);
+  assert_eq!(
+    Claim::try_from_bytes(&trailing).unwrap_err(),
+    DecodeError {
+      offset: bytes.len(),
+      reason: "trailing bytes",
+    }
+  );
+
+  let mut overlong = Entry {
+    address: "0x1234".to_string(),
+    amount: "42".to_string(),
+  }
+  .to_bytes();
+  overlong[10] = 200;
+  assert_eq!(
+    Entry::try_from_bytes(&overlong).unwrap_err(),
+    DecodeError {
+      offset: 14,
+      reason: "unexpected end of input",
+    }
+  );
+}
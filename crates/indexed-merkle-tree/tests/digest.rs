@@ -0,0 +1,201 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::{
+  Blake2b512Hasher, Digest, Hasher, KeccakHasher, Ripemd160Hasher, Sha384Hasher,
+};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::proof::MerkleProof;
+use indexed_merkle_tree::redb_store::RedbStore;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+
+// proofs, compact encoding and snapshots of a tree hashed with `hasher`
+fn check_hasher<H: Hasher + Clone>(hasher: H, len: usize) {
+  let tree = IndexedMerkleTree::<TestData, H>::new(test_data(7), hasher.clone());
+  assert_eq!(tree.root.hash.as_ref().len(), len);
+
+  for data in test_data(7) {
+    let proof = tree.get_proof(data.key()).unwrap();
+    assert!(proof.proof.iter().all(|hash| hash.as_ref().len() == len));
+
+    let bytes = proof.encode(&hasher, 2).unwrap();
+    let decoded = MerkleProof::decode(data, &bytes, &hasher, 2).unwrap();
+    assert_eq!(decoded.proof, proof.proof);
+    assert!(tree.verify_proof(decoded).unwrap());
+  }
+
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+  let loaded = IndexedMerkleTree::<TestData, H>::load_snapshot(&bytes[..], hasher).unwrap();
+  assert_eq!(loaded.root.hash, tree.root.hash);
+}
+
+#[test]
+fn test_snapshot_rejects_other_digest_length() {
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(7), KeccakHasher);
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+  let err = IndexedMerkleTree::<TestData, Sha384Hasher>::load_snapshot(&bytes[..], Sha384Hasher)
+    .unwrap_err();
+  assert!(matches!(err, MerkleError::InvalidSnapshot { .. }));
+}
+
+#[test]
+fn test_digest_sizes() {
+  check_hasher(Ripemd160Hasher, 20);
+  check_hasher(KeccakHasher, 32);
+  check_hasher(Sha384Hasher, 48);
+  check_hasher(Blake2b512Hasher, 64);
+
+  // known answers for the empty input
+  assert_eq!(
+    Ripemd160Hasher.hash_leaf(b"").to_hex(),
+    "0x9c1185a5c5e9fc54612808977ee8f548b2258d31"
+  );
+  assert_eq!(
+    Sha384Hasher.hash_leaf(b"").to_hex(),
+    "0x38b060a751ac96384cd9327eb1b1e36a21fdb71114be07434c0cc7bf63f6e1da274edebfe76f65fbd51ad2f14898b95b"
+  );
+  assert_eq!(
+    Blake2b512Hasher.hash_leaf(b"").to_hex(),
+    "0x786a02f742015903c6c6fd852552d272912f4740e15847618a86e217f71f5419d25e1031afee585313896444934eb04b903a685b1448b755d56f701afe9be2ce"
+  );
+}
+
+#[test]
+fn test_digest_hex_and_serde() {
+  let hash = Blake2b512Hasher.hash_leaf(b"leaf");
+  assert_eq!(<[u8; 64]>::from_hex(&hash.to_hex()), Some(hash));
+  assert_eq!(<[u8; 64]>::from_hex(&hash.to_hex()[2..]), Some(hash));
+  assert_eq!(<[u8; 48]>::from_hex(&hash.to_hex()), None);
+  assert_eq!(<[u8; 20]>::from_hex("0xzz"), None);
+
+  // proofs serialize their siblings as hex strings of the digest size
+  let tree = IndexedMerkleTree::<TestData, Sha384Hasher>::new(test_data(7), Sha384Hasher);
+  let proof = tree.get_proof(test_data(7)[3].key()).unwrap();
+  let json = serde_json::to_value(&proof).unwrap();
+  assert_eq!(json["proof"][0], proof.proof[0].to_hex());
+  let decoded: MerkleProof<TestData, [u8; 48]> = serde_json::from_value(json).unwrap();
+  assert_eq!(decoded.proof, proof.proof);
+
+  let mut json = serde_json::to_value(&proof).unwrap();
+  json["proof"][0] = serde_json::Value::String(KeccakHasher.hash_leaf(b"").to_hex());
+  assert!(serde_json::from_value::<MerkleProof<TestData, [u8; 48]>>(json).is_err());
+}
+
+#[test]
+fn test_digest_errors_and_stores() {
+  let tree = IndexedMerkleTree::<TestData, Ripemd160Hasher>::new(test_data(7), Ripemd160Hasher);
+  let other = IndexedMerkleTree::<TestData, Ripemd160Hasher>::new(
+    test_data(7)[1..].to_vec(),
+    Ripemd160Hasher,
+  );
+  let proof = tree.get_proof(test_data(7)[0].key()).unwrap();
+  match proof.verify(other.root.hash, &Ripemd160Hasher).unwrap_err() {
+    MerkleError::InvalidRootHash { exp, act } => {
+      assert_eq!(exp, other.root.hash.to_vec());
+      assert_eq!(act, tree.root.hash.to_vec());
+    }
+    err => panic!("{:?}", err),
+  }
+
+  // on-disk nodes take the digest size of the hasher
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("tree.redb");
+  let store = RedbStore::<TestData, [u8; 64]>::create(&path).unwrap();
+  let disk = IndexedMerkleTree::build(test_data(7), Blake2b512Hasher, store).unwrap();
+  let memory = IndexedMerkleTree::<TestData, Blake2b512Hasher>::new(test_data(7), Blake2b512Hasher);
+  assert_eq!(disk.root.hash, memory.root.hash);
+  drop(disk);
+
+  let store = RedbStore::<TestData, [u8; 64]>::open(&path).unwrap();
+  let reopened = IndexedMerkleTree::open(store, Blake2b512Hasher).unwrap();
+  let proof = reopened.get_proof(test_data(7)[6].key()).unwrap();
+  assert!(memory.verify_proof(proof).unwrap());
+}
+
+#[cfg(feature = "starknet")]
+#[test]
+fn test_starknet_hasher() {
+  use indexed_merkle_tree::hasher::StarknetHasher;
+
+  check_hasher(StarknetHasher, 32);
+
+  // `poseidon_hash` answer generated by cairo-lang
+  let left =
+    <[u8; 32]>::from_hex("0x000b662f9017fa7956fd70e26129b1833e10ad000fd37b4d9f4e0ce6884b7bbe");
+  let right =
+    <[u8; 32]>::from_hex("0x001fe356bf76102cdae1bfbdc173602ead228b12904c00dad9cf16e035468bea");
+  assert_eq!(
+    StarknetHasher
+      .hash_internal(&left.unwrap(), &right.unwrap())
+      .to_hex(),
+    "0x075540825a6ecc5dc7d7c2f5f868164182742227f1367d66c43ee51ec7937a81"
+  );
+
+  // leaf words wrap around the Stark prime, and a short last word is right-aligned
+  let prime =
+    <[u8; 32]>::from_hex("0x0800000000000011000000000000000000000000000000000000000000000001")
+      .unwrap();
+  assert_eq!(
+    StarknetHasher.hash_leaf(&prime),
+    StarknetHasher.hash_leaf(&[0; 32])
+  );
+  let mut word = [0; 32];
+  word[31] = 7;
+  assert_eq!(
+    StarknetHasher.hash_leaf(&[7]),
+    StarknetHasher.hash_leaf(&word)
+  );
+  assert_ne!(
+    StarknetHasher.hash_leaf(&[7]),
+    StarknetHasher.hash_leaf(&[0; 32])
+  );
+}
+
+#[cfg(feature = "poseidon")]
+#[test]
+fn test_bn254_poseidon_hasher() {
+  use indexed_merkle_tree::hasher::{Bn254PoseidonHasher, POSEIDON_LEAF_FIELDS};
+
+  check_hasher(Bn254PoseidonHasher, 32);
+
+  // circomlib `Poseidon(2)` of 1 and 2
+  let field = |value: u8| Bn254PoseidonHasher::field(&[value]);
+  assert_eq!(
+    Bn254PoseidonHasher
+      .hash_internal(&field(1), &field(2))
+      .to_hex(),
+    "0x115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a"
+  );
+
+  // leaves hash their words as field elements, chaining past 11 of them
+  for len in [0, 1, 3, 11, 12, 30] {
+    let data: Vec<u8> = (0..len * 32).map(|i| i as u8).collect();
+    let fields: Vec<[u8; 32]> = data.chunks(32).map(Bn254PoseidonHasher::field).collect();
+    assert_eq!(
+      Bn254PoseidonHasher.hash_leaf(&data),
+      Bn254PoseidonHasher.hash_fields(&fields)
+    );
+  }
+
+  // zero-padded to a fixed width
+  let fields: Vec<[u8; 32]> = (1..=12).map(field).collect();
+  let mut padded = fields.clone();
+  padded.resize(2 * POSEIDON_LEAF_FIELDS, [0; 32]);
+  assert_eq!(
+    Bn254PoseidonHasher.hash_fields(&fields),
+    Bn254PoseidonHasher.hash_fields(&padded)
+  );
+  assert_eq!(
+    Bn254PoseidonHasher.hash_fields(&[]),
+    Bn254PoseidonHasher.hash_fields(&[[0; 32]; POSEIDON_LEAF_FIELDS])
+  );
+
+  // a leaf of two fields is not the pair of them
+  assert_ne!(
+    Bn254PoseidonHasher.hash_fields(&fields[..2]),
+    Bn254PoseidonHasher.hash_internal(&fields[0], &fields[1])
+  );
+}
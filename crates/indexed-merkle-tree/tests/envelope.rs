@@ -0,0 +1,232 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::envelope::{
+  leaf_capacity, CommitmentEnvelope, EnvelopeOptions, TreeScheme,
+};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher, PairOrder, StandardHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::shard::ShardedMerkleTree;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+use primitive_types::U256;
+
+const SCHEME: TreeScheme = TreeScheme {
+  name: "keccak256/raw",
+  pair_order: PairOrder::Positional,
+  arity: 2,
+  depth: None,
+};
+
+fn options(scheme: &TreeScheme) -> EnvelopeOptions {
+  EnvelopeOptions {
+    total: U256::from(1_000u64),
+    round_id: 3,
+    chain_id: 1,
+    token: [0xaa; 20],
+    scheme_id: scheme.id(),
+  }
+}
+
+#[test]
+fn test_envelope_encoding() {
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(5), KeccakHasher);
+  let envelope = tree.envelope(options(&SCHEME)).unwrap();
+  assert_eq!(envelope.root, tree.root.hash);
+  assert_eq!(envelope.leaf_count, 5);
+  assert_eq!(envelope.leaf_capacity, 8);
+
+  // eight static words, as `abi.encode(bytes32, uint256 x5, address, bytes32)`
+  let bytes = envelope.encode();
+  assert_eq!(bytes.len(), 8 * 32);
+  assert_eq!(&bytes[..32], &tree.root.hash);
+  assert_eq!(U256::from_big_endian(&bytes[32..64]), U256::from(1_000u64));
+  assert_eq!(U256::from_big_endian(&bytes[64..96]), U256::from(5u64));
+  assert_eq!(U256::from_big_endian(&bytes[96..128]), U256::from(8u64));
+  assert_eq!(U256::from_big_endian(&bytes[128..160]), U256::from(3u64));
+  assert_eq!(U256::from_big_endian(&bytes[160..192]), U256::from(1u64));
+  assert_eq!(&bytes[192..204], &[0u8; 12]);
+  assert_eq!(&bytes[204..224], &[0xaa; 20]);
+  assert_eq!(
+    &bytes[224..],
+    &KeccakHasher.hash_leaf(b"keccak256/raw/positional/arity-2/depth-natural")
+  );
+  assert_eq!(envelope.hash(), KeccakHasher.hash_leaf(&bytes));
+}
+
+#[test]
+fn test_envelope_proofs() {
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(5), KeccakHasher);
+  let envelope = tree.envelope(options(&SCHEME)).unwrap();
+  let envelope_hash = envelope.hash();
+
+  for data in test_data(5) {
+    let proof = tree.get_proof(data.key()).unwrap();
+    assert!(envelope
+      .verify_proof(envelope_hash, &SCHEME, &proof, &KeccakHasher)
+      .unwrap());
+    assert!(tree
+      .verify_envelope_proof(&envelope, envelope_hash, proof)
+      .unwrap());
+  }
+
+  // the same root committed for another chain is rejected
+  let proof = tree.get_proof(test_data(5)[0].key()).unwrap();
+  let replayed = CommitmentEnvelope {
+    chain_id: 10,
+    ..envelope.clone()
+  };
+  let err = replayed
+    .verify_proof(envelope_hash, &SCHEME, &proof, &KeccakHasher)
+    .unwrap_err();
+  assert!(matches!(err, MerkleError::InvalidEnvelope { .. }));
+
+  // an index past the committed capacity aliases the leaf with the same low bits, which the proof
+  // alone accepts
+  let mut aliased = tree.get_proof(test_data(5)[0].key()).unwrap();
+  aliased.index = 8;
+  assert!(aliased.verify(tree.root.hash, &KeccakHasher).unwrap());
+  let err = envelope
+    .verify_proof(envelope_hash, &SCHEME, &aliased, &KeccakHasher)
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    MerkleError::IndexOutOfCapacity {
+      index: 8,
+      capacity: 8
+    }
+  ));
+
+  // proofs are checked in the shape of the committed scheme only
+  let sorted = TreeScheme {
+    pair_order: PairOrder::Sorted,
+    ..SCHEME
+  };
+  let err = envelope
+    .verify_proof(envelope_hash, &sorted, &proof, &KeccakHasher)
+    .unwrap_err();
+  assert!(matches!(err, MerkleError::InvalidEnvelope { .. }));
+
+  // an envelope of another tree does not verify against this one
+  let other =
+    IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(5)[..3].to_vec(), KeccakHasher);
+  let other_envelope = other.envelope(options(&SCHEME)).unwrap();
+  let err = tree
+    .verify_envelope_proof(&other_envelope, other_envelope.hash(), proof)
+    .unwrap_err();
+  assert!(matches!(err, MerkleError::InvalidRootHash { .. }));
+}
+
+#[test]
+fn test_envelope_padded_layouts() {
+  // leaves of fixed-depth and sharded trees sit past the leaf count, but within the capacity
+  let padded = IndexedMerkleTree::<TestData, KeccakHasher>::with_options(
+    test_data(5),
+    KeccakHasher,
+    TreeOptions {
+      depth: Some(6),
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  let scheme = TreeScheme {
+    depth: Some(6),
+    ..SCHEME
+  };
+  let envelope = padded.envelope(options(&scheme)).unwrap();
+  assert_eq!(envelope.leaf_capacity, 64);
+  for data in test_data(5) {
+    let proof = padded.get_proof(data.key()).unwrap();
+    assert!(envelope
+      .verify_proof(envelope.hash(), &scheme, &proof, &KeccakHasher)
+      .unwrap());
+  }
+
+  let sharded = ShardedMerkleTree::new(test_data(20), 2, KeccakHasher).unwrap();
+  let height = sharded.height().unwrap();
+  let scheme = TreeScheme {
+    depth: Some(height),
+    ..SCHEME
+  };
+  let envelope = CommitmentEnvelope::new(
+    sharded.root.hash,
+    20,
+    leaf_capacity(2, height),
+    options(&scheme),
+  );
+  let mut past_leaf_count = 0;
+  for data in test_data(20) {
+    let proof = sharded.get_proof(data.key()).unwrap();
+    past_leaf_count += (proof.index >= 20) as usize;
+    assert!(envelope
+      .verify_proof(envelope.hash(), &scheme, &proof, &KeccakHasher)
+      .unwrap());
+  }
+  assert!(past_leaf_count > 0);
+}
+
+#[test]
+fn test_envelope_scheme_shapes() {
+  // k-ary trees verify with the arity of the scheme
+  let scheme = TreeScheme { arity: 4, ..SCHEME };
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::with_options(
+    test_data(9),
+    KeccakHasher,
+    TreeOptions {
+      arity: 4,
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  let envelope = tree.envelope(options(&scheme)).unwrap();
+  for data in test_data(9) {
+    let proof = tree.get_proof(data.key()).unwrap();
+    assert!(envelope
+      .verify_proof(envelope.hash(), &scheme, &proof, &KeccakHasher)
+      .unwrap());
+  }
+
+  // OpenZeppelin trees verify sorted pairs of their double keccak256 leaves, while the envelope is
+  // still hashed once with keccak256
+  let scheme = TreeScheme {
+    pair_order: PairOrder::Sorted,
+    ..SCHEME
+  };
+  let tree = IndexedMerkleTree::<TestData, StandardHasher>::standard(test_data(5)).unwrap();
+  let envelope = tree.envelope(options(&scheme)).unwrap();
+  assert_eq!(envelope.hash(), KeccakHasher.hash_leaf(&envelope.encode()));
+  for data in test_data(5) {
+    let proof = tree.get_standard_proof(data.key()).unwrap();
+    assert!(envelope
+      .verify_proof(envelope.hash(), &scheme, &proof, &StandardHasher)
+      .unwrap());
+  }
+}
+
+#[test]
+fn test_scheme_id_covers_tree_shape() {
+  let ids = [
+    SCHEME.id(),
+    TreeScheme {
+      pair_order: PairOrder::Sorted,
+      ..SCHEME
+    }
+    .id(),
+    TreeScheme { arity: 4, ..SCHEME }.id(),
+    TreeScheme {
+      depth: Some(20),
+      ..SCHEME
+    }
+    .id(),
+    TreeScheme {
+      name: "keccak256/abi",
+      ..SCHEME
+    }
+    .id(),
+  ];
+  for (i, a) in ids.iter().enumerate() {
+    for b in &ids[i + 1..] {
+      assert_ne!(a, b);
+    }
+  }
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::export::{ExportOptions, NodeRole};
+use indexed_merkle_tree::hasher::{Digest, KeccakHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+
+#[test]
+fn test_export_levels() {
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(5), KeccakHasher);
+  let view = tree.export(&ExportOptions::default()).unwrap();
+  assert_eq!(view.root, tree.root.hash.to_hex());
+  assert_eq!(view.levels.len(), 4);
+
+  // every stored node, from the leaves up to the root
+  for (level, expected) in view.levels.iter().zip([8, 4, 2, 1]) {
+    assert_eq!(level.width, expected);
+    assert_eq!(level.first, 0);
+    assert_eq!(level.nodes.len(), expected);
+    for node in &level.nodes {
+      let stored = tree.get_node(level.level, node.index).unwrap();
+      assert_eq!(node.hash, stored.hash.to_hex());
+      assert!(node.role.is_none());
+    }
+  }
+  assert_eq!(
+    view.levels[0].nodes[1].data.as_deref(),
+    Some(format!("0x{}", hex::encode("leaf-1")).as_str())
+  );
+  assert!(view.levels[0].nodes[5].data.is_none());
+  assert_eq!(view.levels[3].nodes[0].hash, view.root);
+
+  let json = serde_json::to_value(&view).unwrap();
+  assert_eq!(json["levels"][0]["nodes"][0]["index"], 0);
+  assert!(json["levels"][1]["nodes"][0].get("data").is_none());
+
+  // padding levels of a fixed-depth tree only hold their leftmost node
+  let options = TreeOptions {
+    depth: Some(5),
+    ..Default::default()
+  };
+  let tree = IndexedMerkleTree::with_options(test_data(3), KeccakHasher, options).unwrap();
+  let view = tree.export(&ExportOptions::default()).unwrap();
+  let widths: Vec<usize> = view.levels.iter().map(|level| level.width).collect();
+  assert_eq!(widths, [4, 2, 1, 1, 1, 1]);
+  assert_eq!(view.levels[5].nodes[0].hash, tree.root.hash.to_hex());
+}
+
+#[test]
+fn test_export_highlighted_path() {
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(100), KeccakHasher);
+  let key = test_data(100)[50].key();
+  let proof = tree.get_proof(key).unwrap();
+  let options = ExportOptions {
+    highlight: Some(key),
+    max_width: Some(5),
+  };
+  let view = tree.export(&options).unwrap();
+
+  // the path and the proof siblings are kept in each truncated level
+  let mut index = proof.index;
+  for level in &view.levels {
+    assert!(level.nodes.len() <= 5);
+    assert_eq!(level.first, level.nodes[0].index);
+    let path = level.nodes.iter().find(|node| node.index == index).unwrap();
+    assert_eq!(path.role, Some(NodeRole::Path));
+
+    let siblings: Vec<&str> = level
+      .nodes
+      .iter()
+      .filter(|node| node.role == Some(NodeRole::Sibling))
+      .map(|node| node.hash.as_str())
+      .collect();
+    match proof.proof.get(level.level) {
+      Some(sibling) => assert_eq!(siblings, [sibling.to_hex()]),
+      None => assert!(siblings.is_empty()),
+    }
+    index /= 2;
+  }
+  assert_eq!(view.levels[0].width, 128);
+  assert_eq!(view.levels[0].first, 48);
+
+  let dot = tree.to_dot(&options).unwrap();
+  assert!(dot.starts_with("digraph merkle_tree {"));
+  assert!(dot.contains("n0_50 [label=\"0:50\\n"));
+  assert!(dot.contains("fillcolor=gold"));
+  assert!(dot.contains("n0_before [label=\"48 more\""));
+  assert!(dot.contains("n0_after [label=\"75 more\""));
+  assert!(dot.contains("n1_25 -> n0_50;"));
+  assert!(dot.contains("n1_25 -> n0_51;"));
+  assert_eq!(dot.matches("fillcolor=gold").count(), view.levels.len());
+
+  let options = ExportOptions {
+    highlight: Some([0; 32]),
+    ..Default::default()
+  };
+  assert!(matches!(
+    tree.export(&options),
+    Err(MerkleError::InvalidKey { .. })
+  ));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use indexed_merkle_tree::index::{KeyIndex, SortedKeyIndex};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::store::CompactMemoryStore;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+
+use std::collections::HashMap;
+
+#[test]
+fn test_compact_index_tree_matches_default() {
+  let data: Vec<TestData> = test_data(21);
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(data.clone(), KeccakHasher);
+  let compact =
+    IndexedMerkleTree::build(data.clone(), KeccakHasher, CompactMemoryStore::default()).unwrap();
+
+  assert_eq!(compact.root.hash, tree.root.hash);
+  for entry in &data {
+    let proof = compact.get_proof(entry.key()).unwrap();
+    assert_eq!(proof.index, tree.get_index(entry.key()).unwrap());
+    tree.verify_proof(proof).unwrap();
+  }
+  compact
+    .get_proof(TestData("missing".to_string()).key())
+    .expect_err("Unknown key");
+}
+
+#[test]
+fn test_sorted_index_matches_map_semantics() {
+  let keys: Vec<[u8; 32]> = (0..50u32)
+    .map(|i| KeccakHasher.hash_leaf(&(i % 30).to_le_bytes()))
+    .collect();
+
+  let mut map: HashMap<[u8; 32], usize> = HashMap::new();
+  let mut sorted = SortedKeyIndex::default();
+  for (index, key) in keys.iter().enumerate() {
+    KeyIndex::insert(&mut map, *key, index).unwrap();
+    sorted.insert(*key, index).unwrap();
+  }
+
+  // duplicates resolve to the last insert both before and after sealing
+  for key in &keys {
+    assert_eq!(sorted.get(key), KeyIndex::get(&map, key));
+  }
+  sorted.seal();
+  assert_eq!(sorted.len(), map.len());
+  for key in &keys {
+    assert_eq!(sorted.get(key), KeyIndex::get(&map, key));
+  }
+  assert_eq!(sorted.get(&[0xff; 32]), None);
+}
@@ -0,0 +1,294 @@
+use indexed_merkle_tree::abi::{decode, encode, ParamType, Token};
+use indexed_merkle_tree::errors::{DecodeError, MerkleError};
+use indexed_merkle_tree::hasher::{Digest, Hasher, KeccakHasher, StandardHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::openzeppelin::StandardTreeDump;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+use primitive_types::U256;
+
+// `abi.encode(address, uint256)` leaf keyed by account
+#[derive(Debug, Clone, PartialEq)]
+struct Claim {
+  account: [u8; 20],
+  amount: U256,
+}
+
+impl SerializableData for Claim {
+  fn to_bytes(&self) -> Vec<u8> {
+    encode(&[Token::Address(self.account), Token::Uint(self.amount)])
+  }
+
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError> {
+    if bytes.len() != 64 {
+      return Err(DecodeError {
+        offset: bytes.len().min(64),
+        reason: "invalid claim length",
+      });
+    }
+    match decode(&[ParamType::Address, ParamType::Uint], &bytes)?.as_slice() {
+      [Token::Address(account), Token::Uint(amount)] => Ok(Claim {
+        account: *account,
+        amount: *amount,
+      }),
+      _ => unreachable!(),
+    }
+  }
+
+  fn key(&self) -> [u8; 32] {
+    KeccakHasher.hash_leaf(&self.account)
+  }
+}
+
+fn test_claims(len: usize) -> Vec<Claim> {
+  (0..len)
+    .map(|i| Claim {
+      account: [i as u8 + 1; 20],
+      amount: U256::from(1000 * (i as u64 + 1)),
+    })
+    .collect()
+}
+
+// `makeMerkleTree` and `getProof` of `@openzeppelin/merkle-tree`, over leaves sorted by hash
+fn reference_tree(claims: &[Claim]) -> Vec<[u8; 32]> {
+  let mut leaves: Vec<[u8; 32]> = claims
+    .iter()
+    .map(|claim| KeccakHasher.hash_leaf(&KeccakHasher.hash_leaf(&claim.to_bytes())))
+    .collect();
+  leaves.sort();
+  let mut tree = vec![[0; 32]; 2 * leaves.len() - 1];
+  let len = tree.len();
+  for (i, leaf) in leaves.into_iter().enumerate() {
+    tree[len - 1 - i] = leaf;
+  }
+  for i in (0..len - claims.len()).rev() {
+    let (left, right) = (tree[2 * i + 1], tree[2 * i + 2]);
+    tree[i] = KeccakHasher.hash_internal(&left.min(right), &left.max(right));
+  }
+  tree
+}
+
+fn reference_proof(tree: &[[u8; 32]], mut i: usize) -> Vec<[u8; 32]> {
+  let mut proof = Vec::new();
+  while i > 0 {
+    let sibling = if i % 2 == 1 { i + 1 } else { i - 1 };
+    proof.push(tree[sibling]);
+    i = (i - 1) / 2;
+  }
+  proof
+}
+
+#[test]
+fn test_standard_tree() {
+  for len in 1..=13 {
+    let claims = test_claims(len);
+    let reference = reference_tree(&claims);
+    let tree = IndexedMerkleTree::standard(claims.clone()).unwrap();
+    assert_eq!(tree.root.hash, reference[0]);
+    assert_eq!(tree.leaf_count().unwrap(), len);
+    assert!(tree.audit().unwrap().is_clean());
+
+    let dump = tree.to_standard_dump(&["address", "uint256"]).unwrap();
+    assert_eq!(dump.format, "standard-v1");
+    let hashes: Vec<String> = reference.iter().map(Digest::to_hex).collect();
+    assert_eq!(dump.tree, hashes);
+
+    for claim in &claims {
+      let value = dump
+        .values
+        .iter()
+        .find(|value| value.value[0] == format!("0x{}", hex::encode(claim.account)))
+        .unwrap();
+      assert_eq!(value.value[1], claim.amount.to_string());
+
+      // proofs are those of the array tree, and verify as sorted pairs
+      let proof = tree.get_standard_proof(claim.key()).unwrap();
+      assert_eq!(proof.data, *claim);
+      assert_eq!(proof.proof, reference_proof(&reference, value.tree_index));
+      assert!(proof
+        .verify_sorted(tree.root.hash, &StandardHasher)
+        .unwrap());
+      assert!(tree
+        .verify_proof(tree.get_proof(claim.key()).unwrap())
+        .unwrap());
+    }
+  }
+
+  // two leaves from the `@openzeppelin/merkle-tree` README
+  let claims = vec![
+    Claim {
+      account: [0x11; 20],
+      amount: U256::from_dec_str("5000000000000000000").unwrap(),
+    },
+    Claim {
+      account: [0x22; 20],
+      amount: U256::from_dec_str("2500000000000000000").unwrap(),
+    },
+  ];
+  let tree = IndexedMerkleTree::standard(claims).unwrap();
+  assert_eq!(
+    tree.root.hash.to_hex(),
+    "0xd4dee0beab2d53f2cc83e567171bd2820e49898130a22622b10ead383e90bd77"
+  );
+}
+
+#[test]
+fn test_standard_snapshot_round_trips() {
+  // upper leaves sit next to empty nodes unless the leaf count is a power of two
+  for len in [3, 5, 6, 7, 9, 13] {
+    let claims = test_claims(len);
+    let tree = IndexedMerkleTree::standard(claims.clone()).unwrap();
+    let mut bytes = Vec::new();
+    tree.save_snapshot(&mut bytes).unwrap();
+
+    let loaded =
+      IndexedMerkleTree::<Claim, StandardHasher>::load_snapshot(&bytes[..], StandardHasher)
+        .unwrap();
+    assert_eq!(loaded.root.hash, tree.root.hash);
+    assert_eq!(loaded.leaf_count().unwrap(), len);
+    assert_eq!(
+      loaded.to_standard_dump(&["address", "uint256"]).unwrap(),
+      tree.to_standard_dump(&["address", "uint256"]).unwrap()
+    );
+    for claim in &claims {
+      assert_eq!(
+        loaded.get_index(claim.key()).unwrap(),
+        tree.get_index(claim.key()).unwrap()
+      );
+      assert_eq!(
+        loaded.get_standard_proof(claim.key()).unwrap().proof,
+        tree.get_standard_proof(claim.key()).unwrap().proof
+      );
+    }
+
+    // and so do trees loaded from a dump
+    let dump = tree.to_standard_dump(&["address", "uint256"]).unwrap();
+    let from_dump = IndexedMerkleTree::<Claim, StandardHasher>::from_standard_dump(&dump).unwrap();
+    let mut bytes = Vec::new();
+    from_dump.save_snapshot(&mut bytes).unwrap();
+    let loaded =
+      IndexedMerkleTree::<Claim, StandardHasher>::load_snapshot(&bytes[..], StandardHasher)
+        .unwrap();
+    assert_eq!(loaded.root.hash, tree.root.hash);
+    assert_eq!(loaded.leaf_count().unwrap(), len);
+  }
+}
+
+#[test]
+fn test_load_standard_dump() {
+  let claims = test_claims(5);
+  let tree = IndexedMerkleTree::standard(claims.clone()).unwrap();
+  let json =
+    serde_json::to_string(&tree.to_standard_dump(&["address", "uint256"]).unwrap()).unwrap();
+  assert!(json.contains("\"leafEncoding\":[\"address\",\"uint256\"]"));
+  assert!(json.contains("\"treeIndex\":4"));
+
+  let dump: StandardTreeDump = serde_json::from_str(&json).unwrap();
+  let loaded = IndexedMerkleTree::<Claim, StandardHasher>::from_standard_dump(&dump).unwrap();
+  assert_eq!(loaded.root.hash, tree.root.hash);
+  assert_eq!(
+    loaded.to_standard_dump(&["address", "uint256"]).unwrap(),
+    dump
+  );
+  for claim in &claims {
+    assert_eq!(
+      loaded.get_standard_proof(claim.key()).unwrap().proof,
+      tree.get_standard_proof(claim.key()).unwrap().proof
+    );
+  }
+
+  // integers may be numbers or hex strings, and values come in any order
+  let mut dump = dump;
+  dump.values.reverse();
+  let amount = |value: &serde_json::Value| U256::from_dec_str(value.as_str().unwrap()).unwrap();
+  dump.values[0].value[1] = serde_json::json!(amount(&dump.values[0].value[1]).as_u64());
+  dump.values[1].value[1] = serde_json::json!(format!("{:#x}", amount(&dump.values[1].value[1])));
+  let loaded = IndexedMerkleTree::<Claim, StandardHasher>::from_standard_dump(&dump).unwrap();
+  assert_eq!(loaded.root.hash, tree.root.hash);
+
+  // binary trees of a power of two leaves are array trees, in insertion order
+  let options = TreeOptions {
+    depth: Some(6),
+    ..Default::default()
+  };
+  let tree = IndexedMerkleTree::with_options(test_claims(4), StandardHasher, options).unwrap();
+  let dump = tree.to_standard_dump(&["address", "uint256"]).unwrap();
+  assert_eq!(dump.tree[0], tree.root.hash.to_hex());
+  let loaded = IndexedMerkleTree::<Claim, StandardHasher>::from_standard_dump(&dump).unwrap();
+  assert_eq!(loaded.root.hash, tree.root.hash);
+  assert_eq!(loaded.metadata.ordering, "insertion");
+}
+
+#[test]
+fn test_invalid_standard_dump() {
+  let tree = IndexedMerkleTree::standard(test_claims(6)).unwrap();
+  let dump = tree.to_standard_dump(&["address", "uint256"]).unwrap();
+  let load =
+    |dump: &StandardTreeDump| match IndexedMerkleTree::<Claim, StandardHasher>::from_standard_dump(
+      dump,
+    ) {
+      Err(MerkleError::InvalidDump { reason }) => reason,
+      result => panic!("unexpected result {:?}", result.map(|tree| tree.root.hash)),
+    };
+
+  let mut invalid = dump.clone();
+  invalid.format = "standard-v2".to_string();
+  assert_eq!(load(&invalid), "unknown format");
+  let mut invalid = dump.clone();
+  invalid.leaf_encoding[1] = "uint8".to_string();
+  assert_eq!(load(&invalid), "unsupported leaf encoding");
+  let mut invalid = dump.clone();
+  invalid.tree.pop();
+  assert_eq!(load(&invalid), "tree size does not match the values");
+  let mut invalid = dump.clone();
+  invalid.tree[3] = "0x1234".to_string();
+  assert_eq!(load(&invalid), "invalid node hash");
+
+  let mut invalid = dump.clone();
+  invalid.values[0].tree_index = 2;
+  assert_eq!(load(&invalid), "tree index is not a leaf");
+  let mut invalid = dump.clone();
+  invalid.values[1].tree_index = invalid.values[0].tree_index;
+  assert_eq!(load(&invalid), "duplicate tree index");
+  let mut invalid = dump.clone();
+  invalid.values[0].value[1] = serde_json::json!("1e18");
+  assert_eq!(load(&invalid), "value does not match the leaf encoding");
+  let mut invalid = dump.clone();
+  invalid.values[0].value[1] = serde_json::json!("1");
+  assert_eq!(load(&invalid), "leaf hash mismatch");
+
+  // internal nodes and the root are checked against the leaves
+  let mut invalid = dump.clone();
+  invalid.tree[1] = invalid.tree[2].clone();
+  assert_eq!(load(&invalid), "node hash mismatch");
+  let mut invalid = dump.clone();
+  invalid.tree[0] = format!("0x{}", "00".repeat(32));
+  assert_eq!(load(&invalid), "node hash mismatch");
+
+  // values must decode into the leaf data
+  let mut invalid = dump.clone();
+  invalid.leaf_encoding.push("bool".to_string());
+  for value in &mut invalid.values {
+    value.value.push(serde_json::json!(true));
+  }
+  assert!(matches!(
+    IndexedMerkleTree::<Claim, StandardHasher>::from_standard_dump(&invalid),
+    Err(MerkleError::Decode { .. })
+  ));
+
+  // leaves of other layouts are not array trees, and leaf data must match the encoding
+  let tree = IndexedMerkleTree::new(test_claims(5), StandardHasher);
+  assert!(matches!(
+    tree.to_standard_dump(&["address", "uint256"]),
+    Err(MerkleError::InvalidDump { .. })
+  ));
+  let tree = IndexedMerkleTree::standard(test_claims(5)).unwrap();
+  assert!(matches!(
+    tree.to_standard_dump(&["address"]),
+    Err(MerkleError::InvalidDump { .. })
+  ));
+  assert!(matches!(
+    tree.to_standard_dump(&["address", "uint256", "bool"]),
+    Err(MerkleError::Decode { .. })
+  ));
+  assert!(IndexedMerkleTree::<Claim, StandardHasher>::standard(vec![]).is_err());
+}
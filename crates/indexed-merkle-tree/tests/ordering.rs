@@ -0,0 +1,151 @@
+use indexed_merkle_tree::errors::{DecodeError, MerkleError};
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::ordering::{LeafOrdering, TreeMetadata};
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+use primitive_types::U256;
+
+#[derive(Debug, Clone, PartialEq)]
+struct TestData(String, u64);
+
+impl SerializableData for TestData {
+  fn to_bytes(&self) -> Vec<u8> {
+    format!("{}:{}", self.0, self.1).into_bytes()
+  }
+
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError> {
+    let data = String::from_utf8(bytes)?;
+    let (name, amount) = data.split_once(':').ok_or(DecodeError {
+      offset: 0,
+      reason: "missing separator",
+    })?;
+    let amount = amount.parse().map_err(|_| DecodeError {
+      offset: name.len() + 1,
+      reason: "invalid amount",
+    })?;
+    Ok(TestData(name.to_string(), amount))
+  }
+
+  fn key(&self) -> [u8; 32] {
+    KeccakHasher.hash_leaf(self.0.as_bytes())
+  }
+}
+
+fn test_data() -> Vec<TestData> {
+  [("carol", 5), ("alice", 300), ("dave", 5), ("bob", 40)]
+    .into_iter()
+    .map(|(name, amount)| TestData(name.to_string(), amount))
+    .collect()
+}
+
+fn ordered_tree(ordering: LeafOrdering<TestData>) -> IndexedMerkleTree<TestData, KeccakHasher> {
+  IndexedMerkleTree::with_options(
+    test_data(),
+    KeccakHasher,
+    TreeOptions {
+      ordering,
+      ..Default::default()
+    },
+  )
+  .unwrap()
+}
+
+fn leaves(tree: &IndexedMerkleTree<TestData, KeccakHasher>) -> Vec<TestData> {
+  (0..tree.leaf_count().unwrap())
+    .map(|index| tree.get_node(0, index).unwrap().data.unwrap())
+    .collect()
+}
+
+#[test]
+fn test_leaf_orderings() {
+  // insertion order is the default
+  let tree = ordered_tree(LeafOrdering::Insertion);
+  assert_eq!(leaves(&tree), test_data());
+  assert_eq!(
+    tree.root.hash,
+    IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(), KeccakHasher)
+      .root
+      .hash
+  );
+
+  let tree = ordered_tree(LeafOrdering::Key);
+  let keys: Vec<_> = leaves(&tree).iter().map(|data| data.key()).collect();
+  assert!(keys.is_sorted());
+
+  let tree = ordered_tree(LeafOrdering::LeafHash);
+  let hashes: Vec<_> = (0..4)
+    .map(|index| tree.get_node(0, index).unwrap().hash)
+    .collect();
+  assert!(hashes.is_sorted());
+
+  // ties keep their insertion order
+  let tree = ordered_tree(LeafOrdering::Descending {
+    name: "amount",
+    weight: |data| U256::from(data.1),
+  });
+  let names: Vec<_> = leaves(&tree).into_iter().map(|data| data.0).collect();
+  assert_eq!(names, ["alice", "bob", "carol", "dave"]);
+  assert_eq!(tree.metadata.ordering, "desc:amount");
+
+  let tree = ordered_tree(LeafOrdering::Custom {
+    name: "name-desc",
+    cmp: |a, b| b.0.cmp(&a.0),
+  });
+  let names: Vec<_> = leaves(&tree).into_iter().map(|data| data.0).collect();
+  assert_eq!(names, ["dave", "carol", "bob", "alice"]);
+  assert_eq!(tree.metadata.ordering, "custom:name-desc");
+
+  // proofs follow the ordered positions
+  for data in test_data() {
+    let proof = tree.get_proof(data.key()).unwrap();
+    assert_eq!(proof.data, data);
+    assert!(tree.verify_proof(proof).unwrap());
+  }
+}
+
+#[test]
+fn test_ordering_reproduces_root() {
+  // the recorded ordering rebuilds the same root from shuffled data
+  let tree = ordered_tree(LeafOrdering::LeafHash);
+  assert_eq!(tree.metadata.ordering, "leaf-hash");
+  let mut shuffled = test_data();
+  shuffled.reverse();
+  let rebuilt = IndexedMerkleTree::<TestData, KeccakHasher>::with_options(
+    shuffled,
+    KeccakHasher,
+    TreeOptions {
+      ordering: LeafOrdering::LeafHash,
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  assert_eq!(rebuilt.root.hash, tree.root.hash);
+
+  // and is kept by snapshots
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+  let loaded =
+    IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher).unwrap();
+  assert_eq!(loaded.root.hash, tree.root.hash);
+  assert_eq!(
+    loaded.metadata,
+    TreeMetadata {
+      ordering: "leaf-hash".to_string()
+    }
+  );
+}
+
+#[test]
+fn test_snapshot_rejects_long_ordering_name() {
+  // names that don't fit the u16 length prefix are rejected rather than cut, which could split a
+  // utf-8 character
+  let name: &'static str = "é".repeat(u16::MAX as usize).leak();
+  let tree = ordered_tree(LeafOrdering::Custom {
+    name,
+    cmp: |a, b| a.0.cmp(&b.0),
+  });
+  let mut bytes = Vec::new();
+  let err = tree.save_snapshot(&mut bytes).unwrap_err();
+  assert!(matches!(err, MerkleError::InvalidSnapshot { .. }));
+  assert!(bytes.is_empty());
+}
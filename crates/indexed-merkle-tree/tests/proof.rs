@@ -0,0 +1,95 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::proof::MerkleProof;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+
+fn build_tree(len: usize) -> IndexedMerkleTree<TestData, KeccakHasher> {
+  let data = test_data(len);
+  IndexedMerkleTree::<TestData, KeccakHasher>::new(data, KeccakHasher)
+}
+
+#[test]
+fn test_compact_encoding_round_trips() {
+  let tree = build_tree(9);
+  for i in 0..9 {
+    let proof = tree
+      .get_proof(TestData(format!("leaf-{}", i)).key())
+      .unwrap();
+    let encoded = proof.encode(&KeccakHasher, 2).unwrap();
+    let decoded = MerkleProof::decode(proof.data.clone(), &encoded, &KeccakHasher, 2).unwrap();
+
+    assert_eq!(decoded.index, proof.index);
+    assert_eq!(decoded.proof, proof.proof);
+    tree.verify_proof(decoded).unwrap();
+  }
+}
+
+#[test]
+fn test_compact_encoding_elides_zero_siblings() {
+  // the last leaf of 9 is padded with 7 empty leaves, so its siblings at levels 0, 1 and 2 are
+  // all zero subtrees and only the level 3 sibling is sent
+  let tree = build_tree(9);
+  let proof = tree
+    .get_proof(TestData("leaf-8".to_string()).key())
+    .unwrap();
+  let encoded = proof.encode(&KeccakHasher, 2).unwrap();
+
+  assert_eq!(proof.proof.len(), 4);
+  assert_eq!(encoded, [&[8, 4, 0b0111][..], &proof.proof[3][..]].concat());
+}
+
+#[test]
+fn test_compact_decoding_rejects_malformed_input() {
+  let tree = build_tree(5);
+  let proof = tree
+    .get_proof(TestData("leaf-0".to_string()).key())
+    .unwrap();
+  let encoded = proof.encode(&KeccakHasher, 2).unwrap();
+
+  let data = proof.data.clone();
+  let truncated = &encoded[..encoded.len() - 1];
+  MerkleProof::decode(data.clone(), truncated, &KeccakHasher, 2).expect_err("Truncated proof");
+
+  let mut trailing = encoded.clone();
+  trailing.push(0);
+  MerkleProof::decode(data.clone(), &trailing, &KeccakHasher, 2).expect_err("Trailing bytes");
+
+  MerkleProof::decode(data, &[0x80; 12], &KeccakHasher, 2).expect_err("Overlong varint");
+}
+
+#[test]
+fn test_compact_encoding_rejects_other_arities() {
+  let tree = build_tree(5);
+  let proof = tree
+    .get_proof(TestData("leaf-0".to_string()).key())
+    .unwrap();
+  let encoded = proof.encode(&KeccakHasher, 2).unwrap();
+
+  assert!(matches!(
+    proof.encode(&KeccakHasher, 4),
+    Err(MerkleError::InvalidArity { arity: 4 })
+  ));
+  assert!(matches!(
+    MerkleProof::decode(proof.data.clone(), &encoded, &KeccakHasher, 3),
+    Err(MerkleError::InvalidArity { arity: 3 })
+  ));
+}
+
+#[test]
+fn test_verify_proof_checks_index() {
+  let tree = build_tree(4);
+  let mut proof = tree
+    .get_proof(TestData("leaf-1".to_string()).key())
+    .unwrap();
+
+  // a proof claiming another position than the leaf of its key is rejected
+  proof.index = 3;
+  assert!(matches!(
+    tree.verify_proof(proof),
+    Err(MerkleError::InvalidProofIndex { exp: 1, act: 3 })
+  ));
+}
@@ -0,0 +1,72 @@
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::salt::derive_salt;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+
+#[derive(Debug, Clone, PartialEq, SerializableData)]
+struct Entry {
+  #[key]
+  address: String,
+  amount: String,
+  #[merkle(encoding = "packed")]
+  salt: Option<[u8; 32]>,
+}
+
+fn entry(address: &str, amount: &str, seed: Option<&[u8]>) -> Entry {
+  let mut entry = Entry {
+    address: address.to_string(),
+    amount: amount.to_string(),
+    salt: None,
+  };
+  entry.salt = seed.map(|seed| derive_salt(seed, &entry.key()));
+  entry
+}
+
+#[test]
+fn test_derive_salt() {
+  let seed: Vec<u8> = (0..32).collect();
+  let salt = derive_salt(&seed, &[0x11; 32]);
+  // HKDF-SHA256 with an empty salt, info = "indexed-merkle-tree/leaf-salt" || key
+  assert_eq!(
+    hex::encode(salt),
+    "1096f3e2108c4cc7298413acc6206ac0c4b0248362d9ced5beaad87186ca57fa"
+  );
+  assert_eq!(derive_salt(&seed, &[0x11; 32]), salt);
+  assert_ne!(derive_salt(&seed, &[0x12; 32]), salt);
+  assert_ne!(derive_salt(b"another seed", &[0x11; 32]), salt);
+}
+
+#[test]
+fn test_salted_leaves() {
+  let seed = b"operator seed";
+  let unsalted = entry("alice", "100", None);
+  let salted = entry("alice", "100", Some(seed));
+
+  // the salt is appended to the preimage, unsalted leaves keep their encoding
+  let bytes = salted.to_bytes();
+  assert_eq!(
+    &bytes[..unsalted.to_bytes().len()],
+    &unsalted.to_bytes()[..]
+  );
+  assert_eq!(&bytes[bytes.len() - 32..], &salted.salt.unwrap());
+  assert_eq!(Entry::from_bytes(bytes), Ok(salted.clone()));
+  assert_eq!(Entry::from_bytes(unsalted.to_bytes()), Ok(unsalted.clone()));
+  assert_eq!(salted.key(), unsalted.key());
+
+  // the same seed rebuilds the same tree, guessing amounts without the salt doesn't verify
+  let build = |seed: &[u8]| {
+    let data = vec![
+      entry("alice", "100", Some(seed)),
+      entry("bob", "200", Some(seed)),
+    ];
+    IndexedMerkleTree::<Entry, KeccakHasher>::new(data, KeccakHasher)
+  };
+  let tree = build(seed);
+  assert_eq!(build(seed).root.hash, tree.root.hash);
+  assert_ne!(build(b"other seed").root.hash, tree.root.hash);
+
+  let mut proof = tree.get_proof(salted.key()).unwrap();
+  assert_eq!(proof.data, salted);
+  proof.data = unsalted;
+  assert!(tree.verify_proof(proof).is_err());
+}
@@ -0,0 +1,99 @@
+mod common;
+
+use common::test_data;
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::shard::{partition, shard_height, shard_of, ShardedMerkleTree};
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+
+use std::collections::HashMap;
+
+#[test]
+fn test_sharded_tree_proofs_verify_against_root() {
+  let data = test_data(50);
+  let tree = ShardedMerkleTree::new(data.clone(), 3, KeccakHasher).unwrap();
+
+  // shards are padded to the height of the largest one, so every proof has the same length and
+  // the shard id sits at the same bit of every leaf index
+  let height = shard_height(&partition(data.clone(), 3));
+  for entry in data {
+    let proof = tree.get_proof(entry.key()).unwrap();
+    let shard = shard_of(&entry.key(), 3);
+    assert_eq!(tree.shards[&shard].height, height);
+
+    assert_eq!(proof.proof.len(), height + 3);
+    assert_eq!(proof.index >> height, shard);
+    proof.verify(tree.root.hash, &KeccakHasher).unwrap();
+  }
+}
+
+#[test]
+fn test_sharded_tree_serves_subset_of_shards() {
+  let data = test_data(50);
+  let full = ShardedMerkleTree::new(data.clone(), 2, KeccakHasher).unwrap();
+
+  // build only shard 1 independently at the common shard height, as a replica would
+  let options = TreeOptions {
+    depth: Some(shard_height(&partition(data.clone(), 2))),
+    ..Default::default()
+  };
+  let shard_data = partition(data.clone(), 2).swap_remove(1);
+  let shard = IndexedMerkleTree::with_options(shard_data, KeccakHasher, options).unwrap();
+  let replica = ShardedMerkleTree::from_shards(
+    2,
+    full.shard_roots.clone(),
+    HashMap::from([(1, shard)]),
+    KeccakHasher,
+  )
+  .unwrap();
+
+  assert_eq!(replica.root.hash, full.root.hash);
+  for entry in data {
+    let key = entry.key();
+    if shard_of(&key, 2) == 1 {
+      let proof = replica.get_proof(key).unwrap();
+      full.verify_proof(proof).unwrap();
+    } else {
+      replica.get_proof(key).expect_err("Shard not loaded");
+    }
+  }
+}
+
+#[test]
+fn test_sharded_tree_rejects_mismatched_shard() {
+  let data = test_data(20);
+  let full = ShardedMerkleTree::new(data.clone(), 1, KeccakHasher).unwrap();
+  let wrong_shard = IndexedMerkleTree::new(test_data(3), KeccakHasher);
+
+  ShardedMerkleTree::from_shards(
+    1,
+    full.shard_roots.clone(),
+    HashMap::from([(0, wrong_shard)]),
+    KeccakHasher,
+  )
+  .expect_err("Shard root mismatch");
+}
+
+#[test]
+fn test_sharded_tree_rejects_uneven_shard_heights() {
+  let mut partitions = partition(test_data(20), 1);
+  let options = TreeOptions {
+    depth: Some(10),
+    ..Default::default()
+  };
+  let padded = IndexedMerkleTree::with_options(partitions.pop().unwrap(), KeccakHasher, options);
+  let shards = HashMap::from([
+    (
+      0,
+      IndexedMerkleTree::new(partitions.pop().unwrap(), KeccakHasher),
+    ),
+    (1, padded.unwrap()),
+  ]);
+  let shard_roots = vec![shards[&0].root.hash, shards[&1].root.hash];
+
+  assert!(matches!(
+    ShardedMerkleTree::from_shards(1, shard_roots, shards, KeccakHasher),
+    Err(MerkleError::InvalidShardHeight { .. })
+  ));
+}
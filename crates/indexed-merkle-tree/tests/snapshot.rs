@@ -0,0 +1,75 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+
+#[test]
+fn test_snapshot_round_trips() {
+  let data: Vec<TestData> = test_data(11);
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(data.clone(), KeccakHasher);
+
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+  let loaded =
+    IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher).unwrap();
+
+  assert_eq!(loaded.root.hash, tree.root.hash);
+  assert_eq!(loaded.height, tree.height);
+  assert_eq!(loaded.store.nodes.len(), tree.store.nodes.len());
+  assert_eq!(loaded.leaf_count().unwrap(), 11);
+  for entry in data {
+    let proof = loaded.get_proof(entry.key()).unwrap();
+    tree.verify_proof(proof).unwrap();
+  }
+}
+
+#[test]
+fn test_snapshot_rejects_truncated_input() {
+  let data: Vec<TestData> = test_data(4);
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(data, KeccakHasher);
+
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+  bytes.truncate(bytes.len() - 1);
+
+  IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher)
+    .expect_err("Truncated snapshot");
+  IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&b"nope"[..], KeccakHasher)
+    .expect_err("Invalid magic");
+
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+  bytes[4] = 2;
+  let err = IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher)
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    MerkleError::InvalidSnapshot {
+      reason: "unsupported version"
+    }
+  ));
+}
+
+#[test]
+fn test_snapshot_rejects_leaf_data_of_other_hashes() {
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(4), KeccakHasher);
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+
+  // leaves are placed by their hash, so changed data matches no node
+  let at = bytes
+    .windows(6)
+    .position(|window| window == b"leaf-0")
+    .unwrap();
+  bytes[at + 5] = b'x';
+  let err = IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher)
+    .unwrap_err();
+  assert!(
+    matches!(err, MerkleError::InvalidSnapshot { .. }),
+    "{:?}",
+    err
+  );
+}
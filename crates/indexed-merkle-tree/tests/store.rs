@@ -0,0 +1,139 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::audit::Discrepancy;
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::ordering::LeafOrdering;
+use indexed_merkle_tree::redb_store::RedbStore;
+use indexed_merkle_tree::store::NodeStore;
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+
+#[test]
+fn test_redb_store_matches_memory_store() {
+  let data: Vec<TestData> = test_data(13);
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("tree.redb");
+
+  let memory = IndexedMerkleTree::<TestData, KeccakHasher>::new(data.clone(), KeccakHasher);
+  let store = RedbStore::create(&path).unwrap();
+  let disk = IndexedMerkleTree::build(data.clone(), KeccakHasher, store).unwrap();
+
+  assert_eq!(disk.root.hash, memory.root.hash);
+  assert_eq!(disk.height, memory.height);
+  assert_eq!(disk.leaf_count().unwrap(), 13);
+  for entry in &data {
+    let proof = disk.get_proof(entry.key()).unwrap();
+    memory.verify_proof(proof).unwrap();
+  }
+}
+
+#[test]
+fn test_redb_store_survives_reopen() {
+  let data: Vec<TestData> = test_data(6);
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("tree.redb");
+
+  let root = {
+    let store = RedbStore::create(&path).unwrap();
+    IndexedMerkleTree::build(data.clone(), KeccakHasher, store)
+      .unwrap()
+      .root
+      .hash
+  };
+
+  let store = RedbStore::<TestData>::open(&path).unwrap();
+  let tree = IndexedMerkleTree::open(store, KeccakHasher).unwrap();
+
+  assert_eq!(tree.root.hash, root);
+  assert_eq!(tree.height, 3);
+  let proof = tree.get_proof(data[5].key()).unwrap();
+  assert_eq!(proof.index, 5);
+  tree.verify_proof(proof).unwrap();
+  tree
+    .get_proof(TestData("missing".to_string()).key())
+    .expect_err("Unknown key");
+}
+
+#[test]
+fn test_redb_store_keeps_leaf_ordering() {
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("tree.redb");
+
+  let built = IndexedMerkleTree::build_with_options(
+    test_data(6),
+    KeccakHasher,
+    RedbStore::create(&path).unwrap(),
+    TreeOptions {
+      ordering: LeafOrdering::Key,
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  drop(built);
+
+  let store = RedbStore::<TestData>::open(&path).unwrap();
+  let tree = IndexedMerkleTree::open(store, KeccakHasher).unwrap();
+  assert_eq!(tree.metadata.ordering, "key");
+}
+
+#[test]
+fn test_redb_store_rebuild_replaces_previous_tree() {
+  let data: Vec<TestData> = test_data(9);
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("tree.redb");
+
+  let store = RedbStore::create(&path).unwrap();
+  IndexedMerkleTree::build(data.clone(), KeccakHasher, store).unwrap();
+
+  // a smaller tree built over the same file leaves no nodes or keys of the first one behind
+  let store = RedbStore::create(&path).unwrap();
+  let root = IndexedMerkleTree::build(data[..3].to_vec(), KeccakHasher, store)
+    .unwrap()
+    .root
+    .hash;
+
+  let store = RedbStore::<TestData>::open(&path).unwrap();
+  let tree = IndexedMerkleTree::open(store, KeccakHasher).unwrap();
+  assert_eq!(tree.root.hash, root);
+  assert_eq!(tree.height, 2);
+  assert_eq!(tree.leaf_count().unwrap(), 3);
+  tree
+    .get_proof(data[8].key())
+    .expect_err("Key of the previous tree");
+
+  // opening requires a built tree
+  let empty = dir.path().join("empty.redb");
+  RedbStore::<TestData>::create(&empty).unwrap();
+  assert!(RedbStore::<TestData>::open(&empty)
+    .and_then(|store| IndexedMerkleTree::open(store, KeccakHasher))
+    .is_err());
+  assert!(RedbStore::<TestData>::open(dir.path().join("missing.redb")).is_err());
+}
+
+#[test]
+fn test_redb_store_open_audited_rejects_corrupt_tree() {
+  let data: Vec<TestData> = test_data(5);
+  let dir = tempfile::tempdir().unwrap();
+  let path = dir.path().join("tree.redb");
+
+  let store = RedbStore::create(&path).unwrap();
+  let mut tree = IndexedMerkleTree::build(data, KeccakHasher, store).unwrap();
+  let key = TestData("stale".to_string()).key();
+  tree.store.put_index(key, 6).unwrap();
+  tree.store.flush().unwrap();
+  drop(tree);
+
+  // a plain open only reads the header and the root
+  let store = RedbStore::<TestData>::open(&path).unwrap();
+  IndexedMerkleTree::open(store, KeccakHasher).unwrap();
+
+  let store = RedbStore::<TestData>::open(&path).unwrap();
+  match IndexedMerkleTree::open_audited(store, KeccakHasher) {
+    Err(MerkleError::AuditFailed { discrepancies }) => {
+      assert_eq!(discrepancies, [Discrepancy::DanglingKey { key, index: 6 }]);
+    }
+    result => panic!("unexpected result {:?}", result.map(|tree| tree.root.hash)),
+  }
+}
@@ -0,0 +1,326 @@
+use indexed_merkle_tree::errors::{DecodeError, MerkleError};
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher, Sha384Hasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::ordering::LeafOrdering;
+use indexed_merkle_tree::stream::{build_snapshot, StreamOptions};
+use indexed_merkle_tree::tree::{IndexedMerkleTree, TreeOptions};
+
+#[derive(Debug, Clone, PartialEq)]
+struct TestData(String);
+
+impl SerializableData for TestData {
+  fn to_bytes(&self) -> Vec<u8> {
+    self.0.as_bytes().to_vec()
+  }
+
+  fn from_bytes(bytes: Vec<u8>) -> Result<Self, DecodeError> {
+    Ok(TestData(String::from_utf8(bytes)?))
+  }
+
+  // the part before ':' identifies the leaf, so that entries can share a key
+  fn key(&self) -> [u8; 32] {
+    let id = self.0.split(':').next().unwrap();
+    KeccakHasher.hash_leaf(id.as_bytes())
+  }
+}
+
+fn test_data(len: usize) -> Vec<TestData> {
+  (0..len).map(|i| TestData(format!("leaf-{}", i))).collect()
+}
+
+fn in_memory_snapshot<H: Hasher>(
+  data: Vec<TestData>,
+  hasher: H,
+  depth: Option<usize>,
+  arity: usize,
+) -> Vec<u8> {
+  ordered_snapshot(data, hasher, depth, arity, LeafOrdering::Key)
+}
+
+fn ordered_snapshot<H: Hasher>(
+  data: Vec<TestData>,
+  hasher: H,
+  depth: Option<usize>,
+  arity: usize,
+  ordering: LeafOrdering<TestData>,
+) -> Vec<u8> {
+  let tree = IndexedMerkleTree::<TestData, H>::with_options(
+    data,
+    hasher,
+    TreeOptions {
+      depth,
+      arity,
+      ordering,
+    },
+  )
+  .unwrap();
+  let mut bytes = Vec::new();
+  tree.save_snapshot(&mut bytes).unwrap();
+  bytes
+}
+
+#[test]
+fn test_stream_matches_in_memory_build() {
+  for (len, depth, arity) in [
+    (0, None, 2),
+    (1, None, 2),
+    (37, None, 2),
+    (37, None, 3),
+    (37, Some(12), 4),
+  ] {
+    let mut bytes = Vec::new();
+    let summary = build_snapshot(
+      test_data(len),
+      &KeccakHasher,
+      &mut bytes,
+      StreamOptions {
+        // several runs, the last one partial
+        run_len: 5,
+        depth,
+        arity,
+        ..Default::default()
+      },
+    )
+    .unwrap();
+    assert_eq!(summary.leaf_count, len);
+    assert_eq!(
+      bytes,
+      in_memory_snapshot(test_data(len), KeccakHasher, depth, arity)
+    );
+
+    let loaded =
+      IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher).unwrap();
+    assert_eq!(loaded.root.hash, summary.root);
+    assert_eq!(loaded.height, summary.height);
+    assert_eq!(loaded.metadata.ordering, "key");
+  }
+
+  // other digest widths
+  let mut bytes = Vec::new();
+  build_snapshot(
+    test_data(20),
+    &Sha384Hasher,
+    &mut bytes,
+    StreamOptions::default(),
+  )
+  .unwrap();
+  assert_eq!(
+    bytes,
+    in_memory_snapshot(test_data(20), Sha384Hasher, None, 2)
+  );
+}
+
+#[test]
+fn test_stream_merges_duplicates() {
+  // duplicates within a run and across runs are merged in input order
+  let data = ["a:1", "b:1", "a:2", "c:1", "b:2", "a:3", "d:1", "c:2"]
+    .iter()
+    .map(|leaf| TestData(leaf.to_string()));
+  let options = StreamOptions {
+    run_len: 3,
+    merge: |earlier: TestData, later: TestData| {
+      let amount = later.0.split(':').nth(1).unwrap();
+      TestData(format!("{}+{}", earlier.0, amount))
+    },
+    ..Default::default()
+  };
+  let mut bytes = Vec::new();
+  let summary = build_snapshot(data, &KeccakHasher, &mut bytes, options).unwrap();
+  assert_eq!(summary.leaf_count, 4);
+
+  let merged = ["a:1+2+3", "b:1+2", "c:1+2", "d:1"]
+    .iter()
+    .map(|leaf| TestData(leaf.to_string()))
+    .collect();
+  assert_eq!(bytes, in_memory_snapshot(merged, KeccakHasher, None, 2));
+
+  // the later entry wins by default
+  let data = ["a:1", "b:1", "a:2"]
+    .iter()
+    .map(|leaf| TestData(leaf.to_string()));
+  let mut bytes = Vec::new();
+  build_snapshot(data, &KeccakHasher, &mut bytes, StreamOptions::default()).unwrap();
+  let loaded =
+    IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher).unwrap();
+  let index = loaded.get_index(TestData("a".to_string()).key()).unwrap();
+  let node = loaded.get_node(0, index).unwrap();
+  assert_eq!(node.data, Some(TestData("a:2".to_string())));
+}
+
+// "leaf-{i}:{amount}" with every fifth leaf repeated later in the input
+fn weighted_data() -> Vec<TestData> {
+  let mut data: Vec<TestData> = (0..30)
+    .map(|i| TestData(format!("leaf-{}:{}", i, (i * 7) % 11)))
+    .collect();
+  data.extend(
+    (0..30)
+      .step_by(5)
+      .map(|i| TestData(format!("leaf-{}:{}", i, i))),
+  );
+  data
+}
+
+fn amount(data: &TestData) -> u64 {
+  data.0.split(':').nth(1).unwrap().parse().unwrap()
+}
+
+fn add_amounts(earlier: TestData, later: TestData) -> TestData {
+  let id = earlier.0.split(':').next().unwrap();
+  TestData(format!("{}:{}", id, amount(&earlier) + amount(&later)))
+}
+
+// the merged leaves in key order, as the in-memory build takes them
+fn merged_by_key(data: Vec<TestData>) -> Vec<TestData> {
+  let mut merged: Vec<TestData> = Vec::new();
+  let mut data = data;
+  data.sort_by_key(|data| data.key());
+  for data in data {
+    match merged.last_mut() {
+      Some(last) if last.key() == data.key() => *last = add_amounts(last.clone(), data),
+      _ => merged.push(data),
+    }
+  }
+  merged
+}
+
+#[test]
+fn test_stream_orderings() {
+  let orderings: Vec<LeafOrdering<TestData>> = vec![
+    LeafOrdering::LeafHash,
+    LeafOrdering::Descending {
+      name: "amount",
+      weight: |data| amount(data).into(),
+    },
+    LeafOrdering::Custom {
+      name: "id-desc",
+      cmp: |a, b| b.0.cmp(&a.0),
+    },
+  ];
+  for ordering in orderings {
+    let mut bytes = Vec::new();
+    build_snapshot(
+      weighted_data(),
+      &KeccakHasher,
+      &mut bytes,
+      StreamOptions {
+        run_len: 4,
+        fan_in: 3,
+        ordering: ordering.clone(),
+        merge: add_amounts,
+        ..Default::default()
+      },
+    )
+    .unwrap();
+    assert_eq!(
+      bytes,
+      ordered_snapshot(
+        merged_by_key(weighted_data()),
+        KeccakHasher,
+        None,
+        2,
+        ordering
+      )
+    );
+  }
+
+  // insertion order is lost once leaves are merged
+  let options = StreamOptions {
+    ordering: LeafOrdering::Insertion,
+    ..Default::default()
+  };
+  let err = build_snapshot(test_data(3), &KeccakHasher, Vec::new(), options).unwrap_err();
+  assert!(matches!(err, MerkleError::UnsupportedOrdering { .. }));
+}
+
+#[test]
+fn test_stream_merges_in_several_passes() {
+  // 15 runs merged two at a time still merge duplicates in input order
+  let mut bytes = Vec::new();
+  build_snapshot(
+    weighted_data(),
+    &KeccakHasher,
+    &mut bytes,
+    StreamOptions {
+      run_len: 3,
+      fan_in: 2,
+      merge: |earlier: TestData, later: TestData| {
+        TestData(format!(
+          "{}+{}",
+          earlier.0,
+          later.0.split(':').nth(1).unwrap()
+        ))
+      },
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  let loaded =
+    IndexedMerkleTree::<TestData, KeccakHasher>::load_snapshot(&bytes[..], KeccakHasher).unwrap();
+  assert_eq!(loaded.leaf_count().unwrap(), 30);
+  let index = loaded
+    .get_index(TestData("leaf-25".to_string()).key())
+    .unwrap();
+  let node = loaded.get_node(0, index).unwrap();
+  assert_eq!(node.data, Some(TestData("leaf-25:10+25".to_string())));
+
+  let mut single_pass = Vec::new();
+  build_snapshot(
+    weighted_data(),
+    &KeccakHasher,
+    &mut single_pass,
+    StreamOptions {
+      merge: add_amounts,
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  let mut multi_pass = Vec::new();
+  build_snapshot(
+    weighted_data(),
+    &KeccakHasher,
+    &mut multi_pass,
+    StreamOptions {
+      run_len: 1,
+      fan_in: 2,
+      merge: add_amounts,
+      ..Default::default()
+    },
+  )
+  .unwrap();
+  assert_eq!(multi_pass, single_pass);
+}
+
+#[test]
+fn test_stream_invalid_options() {
+  let options = StreamOptions {
+    depth: Some(3),
+    ..Default::default()
+  };
+  let err = build_snapshot(test_data(9), &KeccakHasher, Vec::new(), options).unwrap_err();
+  assert!(matches!(
+    err,
+    MerkleError::CapacityExceeded {
+      len: 9,
+      capacity: 8
+    }
+  ));
+
+  let options = StreamOptions {
+    arity: 1,
+    ..Default::default()
+  };
+  let err = build_snapshot(test_data(9), &KeccakHasher, Vec::new(), options).unwrap_err();
+  assert!(matches!(err, MerkleError::InvalidArity { arity: 1 }));
+}
+
+#[test]
+fn test_stream_removes_work_dir() {
+  let dir = tempfile::tempdir().unwrap();
+  let options = StreamOptions {
+    run_len: 4,
+    temp_dir: dir.path().to_path_buf(),
+    ..Default::default()
+  };
+  build_snapshot(test_data(20), &KeccakHasher, Vec::new(), options).unwrap();
+  assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
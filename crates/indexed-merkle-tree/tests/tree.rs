@@ -1,24 +1,10 @@
+mod common;
+
+use common::TestData;
 use indexed_merkle_tree::hasher::{Hasher, KeccakHasher};
 use indexed_merkle_tree::node::SerializableData;
 use indexed_merkle_tree::tree::IndexedMerkleTree;
 
-#[derive(Debug, Clone)]
-struct TestData(String);
-
-impl SerializableData for TestData {
-  fn to_bytes(&self) -> Vec<u8> {
-    self.0.as_bytes().to_vec()
-  }
-
-  fn from_bytes(bytes: Vec<u8>) -> Self {
-    TestData(String::from_utf8(bytes).unwrap())
-  }
-
-  fn key(&self) -> [u8; 32] {
-    KeccakHasher.hash_leaf(&self.0.as_bytes())
-  }
-}
-
 #[test]
 fn test_tree_verifies_proof() {
   let data: Vec<TestData> = vec![
@@ -46,6 +32,27 @@ fn test_tree_rejects_invalid_proof() {
 
   let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(data, KeccakHasher);
   let mut proof = tree.get_proof(TestData("hello".to_string()).key()).unwrap();
-  proof.proof[0] = KeccakHasher.hash_leaf(&TestData("random".to_string()).0.as_bytes());
+  proof.proof[0] = KeccakHasher.hash_leaf(TestData("random".to_string()).0.as_bytes());
   tree.verify_proof(proof).expect_err("Invalid proof");
 }
+
+#[test]
+fn test_tree_of_fewer_than_two_leaves() {
+  // empty trees and single leaves are valid, so that shards and fixed-depth trees of any size build
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(vec![], KeccakHasher);
+  assert_eq!(tree.height, 0);
+  assert_eq!(tree.root.hash, KeccakHasher::zero());
+  assert_eq!(tree.leaf_count().unwrap(), 0);
+  tree
+    .get_proof(TestData("hello".to_string()).key())
+    .expect_err("Empty tree");
+
+  // a single leaf is the root, with an empty proof
+  let leaf = TestData("hello".to_string());
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(vec![leaf.clone()], KeccakHasher);
+  assert_eq!(tree.height, 0);
+  assert_eq!(tree.root.hash, KeccakHasher.hash_leaf(&leaf.to_bytes()));
+  let proof = tree.get_proof(leaf.key()).unwrap();
+  assert!(proof.proof.is_empty());
+  assert!(tree.verify_proof(proof).unwrap());
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use common::{test_data, TestData};
+use indexed_merkle_tree::hasher::{Hasher, KeccakHasher, PairOrder, Sha384Hasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+use indexed_merkle_tree::verify::{compute_root, verify, verify_sorted};
+use indexed_merkle_tree::weighted::WeightedMerkleTree;
+
+#[test]
+fn test_verify_without_proof_type() {
+  let tree = IndexedMerkleTree::<TestData, KeccakHasher>::new(test_data(11), KeccakHasher);
+  for data in test_data(11) {
+    let proof = tree.get_proof(data.key()).unwrap();
+    let leaf = data.to_bytes();
+    assert!(verify(
+      &KeccakHasher,
+      &tree.root.hash,
+      &leaf,
+      proof.index,
+      &proof.proof
+    ));
+    assert!(!verify(
+      &KeccakHasher,
+      &tree.root.hash,
+      b"leaf-x",
+      proof.index,
+      &proof.proof
+    ));
+    assert!(!verify(
+      &KeccakHasher,
+      &tree.root.hash,
+      &leaf,
+      proof.index ^ 1,
+      &proof.proof
+    ));
+  }
+
+  let data: Vec<(TestData, f64)> = test_data(6).into_iter().map(|data| (data, 1.0)).collect();
+  let tree = WeightedMerkleTree::new(data, KeccakHasher, PairOrder::Sorted).unwrap();
+  let proof = tree
+    .get_proof(TestData("leaf-3".to_string()).key())
+    .unwrap();
+  assert!(verify_sorted(
+    &KeccakHasher,
+    &tree.root,
+    b"leaf-3",
+    &proof.proof
+  ));
+}
+
+#[test]
+fn test_default_hash_children() {
+  // reduces the children pairwise level by level, carrying an odd one up
+  let leaves: Vec<[u8; 48]> = (0..9u8).map(|i| Sha384Hasher.hash_leaf(&[i])).collect();
+  for len in 1..=leaves.len() {
+    let mut level = leaves[..len].to_vec();
+    while level.len() > 1 {
+      level = level
+        .chunks(2)
+        .map(|pair| match pair {
+          [left, right] => Sha384Hasher.hash_internal(left, right),
+          [single] => *single,
+          _ => unreachable!(),
+        })
+        .collect();
+    }
+    assert_eq!(Sha384Hasher.hash_children(&leaves[..len]), level[0]);
+  }
+
+  // a binary path is the same as the pairwise root of a two-leaf tree
+  let root = compute_root(&Sha384Hasher, leaves[1], 1, &[leaves[0]]);
+  assert_eq!(root, Sha384Hasher.hash_children(&leaves[..2]));
+}
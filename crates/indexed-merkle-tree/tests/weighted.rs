@@ -0,0 +1,111 @@
+mod common;
+
+use common::TestData;
+use indexed_merkle_tree::errors::MerkleError;
+use indexed_merkle_tree::hasher::KeccakHasher;
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+use indexed_merkle_tree::weighted::{GasModel, PairOrder, WeightedMerkleTree};
+
+// one whale, a few regular recipients and a long tail of dust
+fn weighted_data() -> Vec<(TestData, f64)> {
+  let mut data = vec![(TestData("whale".to_string()), 0.99)];
+  data.extend((0..4).map(|i| (TestData(format!("regular-{}", i)), 0.5)));
+  data.extend((0..27).map(|i| (TestData(format!("dust-{}", i)), 0.01)));
+  data
+}
+
+#[test]
+fn test_weighted_proofs() {
+  for order in [PairOrder::Positional, PairOrder::Sorted] {
+    let tree = WeightedMerkleTree::new(weighted_data(), KeccakHasher, order).unwrap();
+    for (data, _) in weighted_data() {
+      let proof = tree.get_proof(data.key()).unwrap();
+      assert_eq!(
+        proof.proof.len(),
+        tree.depth(tree.get_index(data.key()).unwrap())
+      );
+      match order {
+        PairOrder::Positional => assert!(proof.verify(tree.root, &KeccakHasher).unwrap()),
+        PairOrder::Sorted => assert!(proof.verify_sorted(tree.root, &KeccakHasher).unwrap()),
+      }
+      assert!(tree.verify_proof(proof).unwrap());
+    }
+  }
+
+  // heavier leaves never sit deeper than lighter ones
+  let tree = WeightedMerkleTree::new(weighted_data(), KeccakHasher, PairOrder::Positional).unwrap();
+  let whale = tree.depth(0);
+  let regular = (1..5).map(|i| tree.depth(i)).max().unwrap();
+  let dust = (5..32).map(|i| tree.depth(i)).min().unwrap();
+  assert!(whale <= regular && regular <= dust);
+  assert!(whale < 5);
+
+  // a proof checked against the other pair order fails
+  let proof = tree
+    .get_proof(TestData("dust-3".to_string()).key())
+    .unwrap();
+  assert!(proof.verify_sorted(tree.root, &KeccakHasher).is_err());
+}
+
+#[test]
+fn test_equal_weights_match_balanced_tree() {
+  let data: Vec<_> = (0..8)
+    .map(|i| (TestData(format!("leaf-{}", i)), 1.0))
+    .collect();
+  let tree = WeightedMerkleTree::new(data.clone(), KeccakHasher, PairOrder::Positional).unwrap();
+  for index in 0..8 {
+    assert_eq!(tree.depth(index), 3);
+  }
+
+  let report = tree.gas_report(&GasModel::default());
+  assert_eq!(report.weighted, report.balanced);
+  assert_eq!(report.savings(), 0.0);
+
+  // with equal weights the leaves pair up in order, like the positional tree
+  let balanced = IndexedMerkleTree::<TestData, KeccakHasher>::new(
+    data.into_iter().map(|(data, _)| data).collect(),
+    KeccakHasher,
+  );
+  assert_eq!(tree.root, balanced.root.hash);
+}
+
+#[test]
+fn test_gas_report() {
+  let tree = WeightedMerkleTree::new(weighted_data(), KeccakHasher, PairOrder::Sorted).unwrap();
+  let model = GasModel {
+    base: 1_000,
+    per_level: 100,
+  };
+  let report = tree.gas_report(&model);
+
+  // 32 leaves balance to 5 levels each
+  let total_weight: f64 = weighted_data().iter().map(|(_, weight)| weight).sum();
+  assert!((report.balanced - total_weight * 1_500.0).abs() < 1e-6);
+  assert!(report.weighted < report.balanced);
+  assert!(report.savings() > 0.0);
+}
+
+#[test]
+fn test_invalid_weights() {
+  for weight in [-1.0, f64::NAN, f64::INFINITY] {
+    let data = vec![
+      (TestData("a".to_string()), 1.0),
+      (TestData("b".to_string()), weight),
+    ];
+    let err = WeightedMerkleTree::new(data, KeccakHasher, PairOrder::Positional).unwrap_err();
+    assert!(matches!(err, MerkleError::InvalidWeight { index: 1 }));
+  }
+
+  // exponentially decreasing weights make a path too long for a positional index
+  let data: Vec<_> = (0..70)
+    .map(|i| (TestData(format!("leaf-{}", i)), 0.5f64.powi(i)))
+    .collect();
+  let err = WeightedMerkleTree::new(data.clone(), KeccakHasher, PairOrder::Positional).unwrap_err();
+  assert!(matches!(err, MerkleError::DepthExceeded { depth: 69, .. }));
+  let tree = WeightedMerkleTree::new(data, KeccakHasher, PairOrder::Sorted).unwrap();
+  let proof = tree
+    .get_proof(TestData("leaf-69".to_string()).key())
+    .unwrap();
+  assert!(tree.verify_proof(proof).unwrap());
+}
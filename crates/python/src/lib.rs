@@ -0,0 +1,254 @@
+// Local imports
+use distribution_entry::{
+  address_key, address_ordering, apply_salts, check_entry, merge_entries, DistributionEntry,
+  LEAF_SCHEME,
+};
+use indexed_merkle_tree::{
+  errors::MerkleError,
+  hasher::{Digest, Hasher, KeccakHasher},
+  proof::MerkleProof,
+  tree::{IndexedMerkleTree, TreeOptions},
+};
+
+// Core lib imports
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+
+// External imports
+use primitive_types::U256;
+use pyo3::exceptions::{PyIOError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+type Tree = IndexedMerkleTree<DistributionEntry, KeccakHasher>;
+type TreeDigest = <KeccakHasher as Hasher>::Digest;
+
+// distribution tree built the same way as by the `api` binary, so that roots and proofs match
+#[pyclass(module = "merkle_distributor", name = "MerkleTree")]
+struct PyMerkleTree {
+  tree: Tree,
+  total_amount: String,
+}
+
+#[pymethods]
+impl PyMerkleTree {
+  // entries are dicts with `address` and `amount` keys or `(address, amount)` tuples, amounts being
+  // ints or decimal strings. Entries sharing an address are summed.
+  #[new]
+  #[pyo3(signature = (entries, depth=None, salt_seed=None))]
+  fn new(
+    entries: &Bound<'_, PyAny>,
+    depth: Option<usize>,
+    salt_seed: Option<&[u8]>,
+  ) -> PyResult<Self> {
+    let entries = entries
+      .try_iter()?
+      .map(|entry| parse_entry(&entry?))
+      .collect::<PyResult<Vec<_>>>()?;
+    Self::build(entries, depth, salt_seed)
+  }
+
+  // build from the `address` and `amount` columns of a pandas or polars DataFrame
+  #[staticmethod]
+  #[pyo3(signature = (df, address="address", amount="amount", depth=None, salt_seed=None))]
+  fn from_dataframe(
+    df: &Bound<'_, PyAny>,
+    address: &str,
+    amount: &str,
+    depth: Option<usize>,
+    salt_seed: Option<&[u8]>,
+  ) -> PyResult<Self> {
+    let addresses = df.get_item(address)?.try_iter()?;
+    let amounts = df.get_item(amount)?.try_iter()?;
+    let entries = addresses
+      .zip(amounts)
+      .map(|(address, amount)| to_entry(&address?, &amount?))
+      .collect::<PyResult<Vec<_>>>()?;
+    Self::build(entries, depth, salt_seed)
+  }
+
+  #[staticmethod]
+  fn load_snapshot(path: &str) -> PyResult<Self> {
+    let reader = BufReader::new(File::open(path)?);
+    let tree = Tree::load_snapshot(reader, KeccakHasher).map_err(to_py_err)?;
+
+    let mut total_amount = U256::zero();
+    for index in 0..tree.leaf_count().map_err(to_py_err)? {
+      if let Some(entry) = tree.get_node(0, index).map_err(to_py_err)?.data {
+        let amount =
+          U256::from_dec_str(&entry.amount).map_err(|_| invalid_entry(&entry, "invalid amount"))?;
+        total_amount = total_amount
+          .checked_add(amount)
+          .ok_or_else(|| invalid_entry(&entry, "total amount overflows"))?;
+      }
+    }
+    Ok(Self {
+      tree,
+      total_amount: total_amount.to_string(),
+    })
+  }
+
+  fn save_snapshot(&self, path: &str) -> PyResult<()> {
+    let writer = BufWriter::new(File::create(path)?);
+    self.tree.save_snapshot(writer).map_err(to_py_err)
+  }
+
+  #[getter]
+  fn root(&self) -> String {
+    self.tree.root.hash.to_hex()
+  }
+
+  #[getter]
+  fn total_amount(&self) -> &str {
+    &self.total_amount
+  }
+
+  #[getter]
+  fn height(&self) -> usize {
+    self.tree.height
+  }
+
+  fn __len__(&self) -> PyResult<usize> {
+    self.tree.leaf_count().map_err(to_py_err)
+  }
+
+  fn __repr__(&self) -> String {
+    format!(
+      "MerkleTree(root={}, height={})",
+      self.root(),
+      self.tree.height
+    )
+  }
+
+  // proof in the format of the `/proof` endpoint, plus the address
+  fn get_proof<'py>(&self, py: Python<'py>, address: &str) -> PyResult<Bound<'py, PyDict>> {
+    let proof = self
+      .tree
+      .get_proof(address_key(address))
+      .map_err(|_| PyKeyError::new_err(address.to_string()))?;
+
+    let dict = PyDict::new(py);
+    dict.set_item("address", proof.data.address)?;
+    dict.set_item("amount", proof.data.amount)?;
+    dict.set_item("index", proof.index)?;
+    dict.set_item(
+      "proof",
+      proof.proof.iter().map(Digest::to_hex).collect::<Vec<_>>(),
+    )?;
+    if let Some(salt) = proof.data.salt {
+      dict.set_item("salt", format!("0x{}", hex::encode(salt)))?;
+    }
+    Ok(dict)
+  }
+
+  // verify a proof returned by `get_proof`, or a dict with the same keys, against the root
+  fn verify_proof(&self, proof: &Bound<'_, PyDict>) -> PyResult<bool> {
+    let address: String = required_item(proof, "address")?.extract()?;
+    let entry = DistributionEntry {
+      address: address.clone(),
+      amount: required_item(proof, "amount")?.str()?.to_string(),
+      salt: match proof.get_item("salt")? {
+        Some(salt) => Some(parse_hex(&salt.extract::<String>()?)?),
+        None => None,
+      },
+    };
+    let Ok(index) = self.tree.get_index(address_key(&address)) else {
+      return Ok(false);
+    };
+    // entries that can't be encoded as a leaf can't be part of the tree
+    if check_entry(&entry).is_err() {
+      return Ok(false);
+    }
+    let hashes = required_item(proof, "proof")?
+      .extract::<Vec<String>>()?
+      .iter()
+      .map(|hash| parse_hex(hash))
+      .collect::<PyResult<Vec<TreeDigest>>>()?;
+
+    let proof = MerkleProof {
+      data: entry,
+      index,
+      proof: hashes,
+    };
+    match self.tree.verify_proof(proof) {
+      Err(MerkleError::InvalidRootHash { .. }) => Ok(false),
+      result => result.map_err(to_py_err),
+    }
+  }
+}
+
+impl PyMerkleTree {
+  fn build(
+    entries: Vec<DistributionEntry>,
+    depth: Option<usize>,
+    salt_seed: Option<&[u8]>,
+  ) -> PyResult<Self> {
+    let (mut data, total_amount) =
+      merge_entries(entries).map_err(|err| invalid_entry(&err.entry, err.reason))?;
+    if let Some(seed) = salt_seed {
+      apply_salts(&mut data, seed);
+    }
+
+    let options = TreeOptions {
+      depth,
+      ordering: address_ordering(),
+      ..Default::default()
+    };
+    let tree = Tree::with_options(data, KeccakHasher, options).map_err(to_py_err)?;
+    Ok(Self { tree, total_amount })
+  }
+}
+
+fn parse_entry(entry: &Bound<'_, PyAny>) -> PyResult<DistributionEntry> {
+  match entry.downcast::<PyDict>() {
+    Ok(dict) => to_entry(
+      &required_item(dict, "address")?,
+      &required_item(dict, "amount")?,
+    ),
+    Err(_) => {
+      let (address, amount) = entry.extract::<(Bound<'_, PyAny>, Bound<'_, PyAny>)>()?;
+      to_entry(&address, &amount)
+    }
+  }
+}
+
+fn to_entry(address: &Bound<'_, PyAny>, amount: &Bound<'_, PyAny>) -> PyResult<DistributionEntry> {
+  let entry = DistributionEntry {
+    address: address.extract()?,
+    amount: amount.str()?.to_string(),
+    salt: None,
+  };
+  check_entry(&entry).map_err(|reason| invalid_entry(&entry, reason))?;
+  Ok(entry)
+}
+
+fn required_item<'py>(dict: &Bound<'py, PyDict>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+  dict
+    .get_item(key)?
+    .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+}
+
+fn parse_hex<G: Digest>(hex: &str) -> PyResult<G> {
+  G::from_hex(hex).ok_or_else(|| PyValueError::new_err(format!("invalid hex digest {}", hex)))
+}
+
+fn invalid_entry(entry: &DistributionEntry, reason: &str) -> PyErr {
+  PyValueError::new_err(format!(
+    "{} for {} with amount {}",
+    reason, entry.address, entry.amount
+  ))
+}
+
+fn to_py_err(err: MerkleError) -> PyErr {
+  match err {
+    MerkleError::Io { err } => PyIOError::new_err(err.to_string()),
+    err => PyValueError::new_err(format!("{:?}", err)),
+  }
+}
+
+#[pymodule]
+fn merkle_distributor(m: &Bound<'_, PyModule>) -> PyResult<()> {
+  m.add_class::<PyMerkleTree>()?;
+  m.add("LEAF_SCHEME", LEAF_SCHEME)?;
+  Ok(())
+}
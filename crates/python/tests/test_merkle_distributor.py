@@ -0,0 +1,87 @@
+import pytest
+
+from merkle_distributor import LEAF_SCHEME, MerkleTree
+
+ENTRIES = [
+    {"address": "0x1111111111111111111111111111111111111111", "amount": "100"},
+    {"address": "0x2222222222222222222222222222222222222222", "amount": "250"},
+    {"address": "0x3333333333333333333333333333333333333333", "amount": "75"},
+]
+
+
+def test_build_and_verify():
+    tree = MerkleTree(ENTRIES)
+    assert len(tree) == 3
+    assert tree.total_amount == "425"
+    assert tree.root.startswith("0x") and len(tree.root) == 66
+    assert LEAF_SCHEME.startswith("keccak256/")
+
+    for entry in ENTRIES:
+        proof = tree.get_proof(entry["address"])
+        assert proof["amount"] == entry["amount"]
+        assert len(proof["proof"]) == tree.height
+        assert tree.verify_proof(proof)
+
+    proof = tree.get_proof(ENTRIES[0]["address"])
+    proof["amount"] = "101"
+    assert not tree.verify_proof(proof)
+
+
+def test_entry_formats():
+    # the root doesn't depend on the input order or format, and duplicates are summed
+    tree = MerkleTree(ENTRIES)
+    tuples = [(entry["address"], int(entry["amount"])) for entry in reversed(ENTRIES)]
+    assert MerkleTree(tuples).root == tree.root
+
+    split = ENTRIES[1:] + [
+        {"address": ENTRIES[0]["address"], "amount": 60},
+        {"address": ENTRIES[0]["address"], "amount": 40},
+    ]
+    assert MerkleTree(split).root == tree.root
+
+    with pytest.raises(ValueError):
+        MerkleTree([(ENTRIES[0]["address"], "1.5")])
+    with pytest.raises(KeyError):
+        MerkleTree([{"address": ENTRIES[0]["address"]}])
+    with pytest.raises(KeyError):
+        tree.get_proof("0x4444444444444444444444444444444444444444")
+
+
+def test_from_dataframe():
+    pd = pytest.importorskip("pandas")
+    df = pd.DataFrame(
+        {
+            "account": [entry["address"] for entry in ENTRIES],
+            "amount": [int(entry["amount"]) for entry in ENTRIES],
+        }
+    )
+    tree = MerkleTree.from_dataframe(df, address="account")
+    assert tree.root == MerkleTree(ENTRIES).root
+
+
+def test_depth_and_salt():
+    tree = MerkleTree(ENTRIES, depth=16, salt_seed=bytes(32))
+    assert tree.height == 16
+    assert tree.root != MerkleTree(ENTRIES, depth=16).root
+
+    proof = tree.get_proof(ENTRIES[2]["address"])
+    assert len(proof["proof"]) == 16
+    assert proof["salt"].startswith("0x")
+    assert tree.verify_proof(proof)
+    del proof["salt"]
+    assert not tree.verify_proof(proof)
+
+
+def test_snapshot(tmp_path):
+    tree = MerkleTree(ENTRIES, depth=8)
+    path = str(tmp_path / "tree.snapshot")
+    tree.save_snapshot(path)
+
+    loaded = MerkleTree.load_snapshot(path)
+    assert loaded.root == tree.root
+    assert loaded.height == 8
+    assert loaded.total_amount == tree.total_amount
+    assert loaded.verify_proof(tree.get_proof(ENTRIES[1]["address"]))
+
+    with pytest.raises(OSError):
+        MerkleTree.load_snapshot(str(tmp_path / "missing"))
@@ -0,0 +1,68 @@
+// Local imports
+use distribution_entry::check_entry;
+pub use distribution_entry::DistributionEntry;
+use indexed_merkle_tree::{
+  hasher::{Digest, Hasher, KeccakHasher},
+  node::SerializableData,
+  proof::MerkleProof,
+};
+
+// External imports
+use wasm_bindgen::prelude::*;
+
+type TreeDigest = <KeccakHasher as Hasher>::Digest;
+
+// entry of the API's leaf layout, `None` if `check_entry` rejects it or the salt isn't 32 bytes of
+// hex
+pub fn parse_entry(address: &str, amount: &str, salt: Option<&str>) -> Option<DistributionEntry> {
+  let salt = match salt {
+    Some(salt) => Some(parse_hex(salt)?),
+    None => None,
+  };
+  let entry = DistributionEntry {
+    address: address.to_string(),
+    amount: amount.to_string(),
+    salt,
+  };
+  check_entry(&entry).ok()?;
+  Some(entry)
+}
+
+// hash of the leaf for an address and amount, with the salt returned by `/proof` for salted trees
+#[wasm_bindgen(js_name = leafHash)]
+pub fn leaf_hash(address: &str, amount: &str, salt: Option<String>) -> Result<String, JsError> {
+  let leaf =
+    parse_entry(address, amount, salt.as_deref()).ok_or_else(|| JsError::new("Invalid leaf"))?;
+  Ok(KeccakHasher.hash_leaf(&leaf.to_bytes()).to_hex())
+}
+
+// verify a proof returned by `/proof` against the root, malformed input being reported as invalid.
+// `amount`, `proof`, `index` and `salt` are the fields of the `/proof` response passed as they
+// come, since wasm-bindgen only takes plain values and decoding a JS object would need serde glue
+// in the module.
+#[wasm_bindgen(js_name = verifyProof)]
+pub fn verify_proof(
+  root: &str,
+  address: &str,
+  amount: &str,
+  proof: Vec<String>,
+  index: usize,
+  salt: Option<String>,
+) -> bool {
+  let Some(root) = parse_hex::<TreeDigest>(root) else {
+    return false;
+  };
+  let Some(data) = parse_entry(address, amount, salt.as_deref()) else {
+    return false;
+  };
+  let Some(proof) = proof.iter().map(|hash| parse_hex(hash)).collect() else {
+    return false;
+  };
+
+  let proof = MerkleProof { data, index, proof };
+  proof.verify(root, &KeccakHasher).unwrap_or(false)
+}
+
+fn parse_hex<G: Digest>(hex: &str) -> Option<G> {
+  G::from_hex(hex)
+}
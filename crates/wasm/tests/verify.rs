@@ -0,0 +1,153 @@
+// run natively with `cargo test`, and under Node with `wasm-pack test --node`
+use indexed_merkle_tree::hasher::{Digest, Hasher, KeccakHasher};
+use indexed_merkle_tree::node::SerializableData;
+use indexed_merkle_tree::tree::IndexedMerkleTree;
+use merkle_distributor_wasm::{leaf_hash, parse_entry, verify_proof, DistributionEntry};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_test::wasm_bindgen_test as test;
+
+const ADDRESSES: [&str; 3] = [
+  "0x1111111111111111111111111111111111111111",
+  "0x2222222222222222222222222222222222222222",
+  "0x3333333333333333333333333333333333333333",
+];
+const SALT: &str = "0x0101010101010101010101010101010101010101010101010101010101010101";
+
+fn test_leaves(salt: Option<&str>) -> Vec<DistributionEntry> {
+  ADDRESSES
+    .iter()
+    .enumerate()
+    .map(|(i, address)| parse_entry(address, &(i * 100).to_string(), salt).unwrap())
+    .collect()
+}
+
+#[test]
+fn test_verify_proof() {
+  for salt in [None, Some(SALT)] {
+    let tree =
+      IndexedMerkleTree::<DistributionEntry, KeccakHasher>::new(test_leaves(salt), KeccakHasher);
+    let root = tree.root.hash.to_hex();
+
+    for leaf in test_leaves(salt) {
+      let proof = tree.get_proof(leaf.key()).unwrap();
+      let hashes: Vec<String> = proof.proof.iter().map(Digest::to_hex).collect();
+      let salt = salt.map(str::to_string);
+      assert!(verify_proof(
+        &root,
+        &leaf.address,
+        &leaf.amount,
+        hashes.clone(),
+        proof.index,
+        salt.clone()
+      ));
+
+      // any change to the claim invalidates it
+      assert!(!verify_proof(
+        &root,
+        &leaf.address,
+        "1",
+        hashes.clone(),
+        proof.index,
+        salt.clone()
+      ));
+      assert!(!verify_proof(
+        &root,
+        &leaf.address,
+        &leaf.amount,
+        hashes.clone(),
+        proof.index ^ 1,
+        salt.clone()
+      ));
+      assert!(!verify_proof(
+        &root,
+        &leaf.address,
+        &leaf.amount,
+        hashes[1..].to_vec(),
+        proof.index,
+        salt.clone()
+      ));
+
+      // malformed input is invalid rather than an error
+      assert!(!verify_proof(
+        "0x1234",
+        &leaf.address,
+        &leaf.amount,
+        hashes.clone(),
+        proof.index,
+        salt.clone()
+      ));
+      assert!(!verify_proof(
+        &root,
+        &leaf.address,
+        "-1",
+        hashes,
+        proof.index,
+        salt
+      ));
+    }
+  }
+}
+
+#[test]
+fn test_leaf_hash() {
+  for leaf in test_leaves(Some(SALT)) {
+    let hash = leaf_hash(&leaf.address, &leaf.amount, Some(SALT.to_string())).unwrap();
+    assert_eq!(hash, KeccakHasher.hash_leaf(&leaf.to_bytes()).to_hex());
+    assert_ne!(hash, leaf_hash(&leaf.address, &leaf.amount, None).unwrap());
+  }
+
+  assert!(parse_entry(ADDRESSES[0], "1.5", None).is_none());
+  assert!(parse_entry(ADDRESSES[0], "1", Some("0x01")).is_none());
+}
+
+// leaves and proofs match those of the API, in whichever leaf layout both are built with
+#[cfg(not(target_arch = "wasm32"))]
+#[test]
+fn test_matches_api() {
+  use api::data_parser::{address_key, address_ordering, apply_salts, merge_entries};
+  use api::{build_sharded_tree, DistributionTree};
+  use indexed_merkle_tree::tree::TreeOptions;
+
+  // mixed case addresses, which ABI leaves key case insensitively
+  let entries = (0..10).map(|i| DistributionEntry {
+    address: format!("0x{:040X}", 0xabc0 + i),
+    amount: (i * 100).to_string(),
+    salt: None,
+  });
+  let (mut data, _) = merge_entries(entries).unwrap();
+  apply_salts(&mut data, b"seed");
+
+  let options = TreeOptions {
+    ordering: address_ordering(),
+    ..Default::default()
+  };
+  let full = IndexedMerkleTree::with_options(data.clone(), KeccakHasher, options).unwrap();
+  let trees = [
+    DistributionTree::Full(full),
+    DistributionTree::Sharded(build_sharded_tree(data.clone(), 2, None).unwrap()),
+  ];
+
+  for tree in trees {
+    let root = tree.root_hash().to_hex();
+    for entry in &data {
+      let proof = tree.get_proof(address_key(&entry.address)).unwrap();
+      let salt = proof
+        .data
+        .salt
+        .map(|salt| format!("0x{}", hex::encode(salt)));
+      assert_eq!(
+        leaf_hash(&proof.data.address, &proof.data.amount, salt.clone()).unwrap(),
+        KeccakHasher.hash_leaf(&proof.data.to_bytes()).to_hex()
+      );
+      assert!(verify_proof(
+        &root,
+        &proof.data.address,
+        &proof.data.amount,
+        proof.proof.iter().map(Digest::to_hex).collect(),
+        proof.index,
+        salt
+      ));
+    }
+  }
+}